@@ -1,8 +1,85 @@
 extern crate winres;
 
+use std::env;
+use std::fs;
+use std::path::Path;
+
 fn main() {
+    embed_catalogs();
+    generate_message_ids();
+
     let mut res = winres::WindowsResource::new();
     res.set_icon("icon.ico");
     res.set_manifest_file("icc_auto_reloader.manifest");
     res.compile().unwrap();
-}
\ No newline at end of file
+}
+
+// Every `lang/<tag>.ftl` becomes an entry of `EMBEDDED` in
+// `$OUT_DIR/catalogs.rs`, so adding a language needs no code change.
+fn embed_catalogs() {
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
+    let lang_dir = Path::new(&manifest_dir).join("lang");
+    println!("cargo:rerun-if-changed={}", lang_dir.display());
+
+    let mut catalogs: Vec<(String, String)> = fs::read_dir(&lang_dir)
+        .expect("lang directory is missing")
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().map_or(false, |ext| ext.eq_ignore_ascii_case("ftl")))
+        .map(|path| {
+            println!("cargo:rerun-if-changed={}", path.display());
+            let tag = path.file_stem().unwrap().to_string_lossy().to_string();
+            (tag, path.to_string_lossy().to_string())
+        })
+        .collect();
+    catalogs.sort();
+
+    let mut out = String::from("pub static EMBEDDED: &[(&str, &str)] = &[\n");
+    for (tag, path) in &catalogs {
+        out.push_str(&format!("    ({:?}, include_str!({:?})),\n", tag, path));
+    }
+    out.push_str("];\n");
+
+    let out_dir = env::var("OUT_DIR").unwrap();
+    fs::write(Path::new(&out_dir).join("catalogs.rs"), out).unwrap();
+}
+
+// Turns the ids in the English catalog into the `Msg` enum, so a misspelled
+// message id is a compile error instead of a raw key on screen.
+fn generate_message_ids() {
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
+    let source = fs::read_to_string(Path::new(&manifest_dir).join("lang").join("en.ftl")).expect("lang/en.ftl is missing");
+
+    let ids: Vec<&str> = source
+        .lines()
+        .filter(|line| !line.starts_with(|c: char| c.is_whitespace() || c == '#' || c == '-'))
+        .filter_map(|line| line.split_once('=').map(|(id, _)| id.trim()))
+        .filter(|id| !id.is_empty())
+        .collect();
+
+    let variant = |id: &str| -> String {
+        id.split(|c| c == '_' || c == '-')
+            .map(|part| {
+                let mut chars = part.chars();
+                chars.next().map_or(String::new(), |c| c.to_ascii_uppercase().to_string() + chars.as_str())
+            })
+            .collect()
+    };
+
+    // Catalogs may carry messages the code does not use (yet)
+    let mut out = String::from("#[allow(dead_code)]\n#[derive(Debug, Clone, Copy, PartialEq, Eq)]\npub enum Msg {\n");
+    for id in &ids {
+        out.push_str(&format!("    {},\n", variant(id)));
+    }
+    out.push_str("}\n\nimpl Msg {\n    #[cfg(test)]\n    pub const ALL: &'static [Msg] = &[\n");
+    for id in &ids {
+        out.push_str(&format!("        Msg::{},\n", variant(id)));
+    }
+    out.push_str("    ];\n\n    pub fn id(self) -> &'static str {\n        match self {\n");
+    for id in &ids {
+        out.push_str(&format!("            Msg::{} => {:?},\n", variant(id), id));
+    }
+    out.push_str("        }\n    }\n}\n");
+
+    let out_dir = env::var("OUT_DIR").unwrap();
+    fs::write(Path::new(&out_dir).join("messages.rs"), out).unwrap();
+}
@@ -0,0 +1,152 @@
+# English messages for Icc Auto Reloader.
+# Syntax is a subset of Project Fluent: `id = value`, indented continuation
+# lines for multi-line values and `{ $name }` placeholders.
+
+-language-name = English
+
+tray_reload = Reload Calibration
+tray_import = Import ICC Profile...
+tray_polling = Enable Auto-Reload (Polling)
+tray_pause = Pause Auto-Reload
+tray_pause_15m = For 15 Minutes
+tray_pause_1h = For 1 Hour
+tray_pause_restart = Until Restart
+tray_pause_resume = Resume Now
+tray_startup = Run at Startup
+tray_settings = Settings...
+tray_exit = Exit
+settings_title = Settings
+lbl_interval = Polling Interval (seconds):
+lbl_lang = Language:
+btn_save = Save
+msg_install_success = Profile installed and loaded successfully.
+msg_install_fail = Failed to install profile.
+msg_dispwin_missing = Could not find dispwin.exe
+msg_select_profile = Select ICC Profile
+wiz_title = Icc Auto Reloader - Setup Wizard
+wiz_lbl_lang = Please select your language:
+wiz_welcome =
+    Welcome!
+
+    Please import an ICC profile to get started.
+    This ensures your display colors remain accurate.
+wiz_btn_import = Import
+wiz_btn_skip = Skip
+wiz_btn_next = Next >
+wiz_congrats =
+    Congratulations! You are all set.
+
+    Icc Auto Reloader will run in the background
+    and protect your calibration.
+wiz_chk_startup = Run at startup
+wiz_btn_finish = Finish
+wiz_title_welcome = Welcome
+wiz_step1_title = Import ICC Profile
+wiz_step1_desc = Select an ICC profile, or skip to use the Windows default profile.
+wiz_step1_no_profile = No profile selected (Windows default will be used)
+wiz_step1_profile = Profile: { $file }
+wiz_step2_title = Setup Complete!
+wiz_btn_back = < Back
+wiz_btn_cancel = Cancel
+exit_confirm_title = Confirm Exit
+exit_confirm_msg = Warning: After exiting, the system will no longer automatically load color calibration. Continue?
+chk_rpc = Enable local control API (JSON-RPC)
+lbl_fullscreen = While fullscreen:
+fullscreen_keep = Keep reloading
+fullscreen_suspend = Pause polling
+fullscreen_reload_on_exit = Reload when it ends
+msg_paused = Auto-reload disabled.
+msg_resumed = Auto-reload enabled.
+msg_paused_for = Auto-reload paused for { $minutes } min.
+msg_paused_restart = Auto-reload paused until restart.
+msg_headless_no_ui = Not available in headless mode.
+msg_reload_requested = Calibration reload requested.
+msg_settings_opened = Settings window opened.
+msg_quitting = Icc Auto Reloader is exiting.
+msg_already_running = Icc Auto Reloader is already running.
+msg_not_running = Icc Auto Reloader is not running.
+msg_ipc_unreachable = Could not reach the running Icc Auto Reloader
+
+tray_current_profile = Current Profile
+info_display = { $display }: { $file }
+info_no_profile = No profile associated
+info_description = Description: { $value }
+info_device = Device: { $value }
+info_copyright = Copyright: { $value }
+info_created = Created: { $value }
+info_version = Version: { $version } ({ $class })
+info_white_point = White point: { $value }
+info_luminance = Luminance: { $value } cd/m²
+info_primaries = Primaries: { $value }
+info_vcgt_present = Calibration curves (vcgt): present
+info_vcgt_missing = Calibration curves (vcgt): none
+info_mhc2 = Advanced color (MHC2): { $value }
+info_mhc2_none = none
+info_mhc2_summary = { $min } to { $max } cd/m², { $entries }-entry LUTs ({ $luts }), matrix { $matrix }
+info_mhc2_matrix = MHC2 matrix: { $value }
+info_mhc2_identity = identity
+info_mhc2_custom = custom
+msg_mhc2_only = { $file } keeps its calibration in the MHC2 tag instead of vcgt. Reloading the gamma ramp does not restore it.
+tip_mhc2_only = MHC2 calibration, not reloaded: { $display }
+tip_held = Reloads on hold: { $reason }
+tip_paused_for = Auto-reload paused, { $minutes } min left
+tip_paused_restart = Auto-reload paused until restart
+info_measurements = Measurements (targ): { $value }
+info_measurements_none = none embedded
+info_measurements_summary = { $patches } patches, { $instrument }
+info_accuracy = Profile accuracy (ΔE2000): { $value }
+info_accuracy_summary = mean { $mean }, 95th percentile { $p95 }, max { $max }
+info_accuracy_no_model = no forward model to evaluate
+info_unknown = unknown
+msg_profile_unreadable = Could not read profile: { $error }
+age_tip_overdue = Re-calibration due: { $display }
+age_notify_title = Re-calibration due
+age_notify_body = { $display }: { $file } is { $days } days old (limit { $max } days).
+info_gamut_coverage = Gamut coverage ({ $space }): { $value }
+info_gamut_area = Gamut area ({ $space }): { $value }
+info_curve_gamma = Curve gamma: { $value }
+info_curve_levels = Distinct levels (8-bit/10-bit): { $value }
+info_curve_issues = Curve issues: { $value }
+info_curve_ok = none
+info_curve_non_monotonic = { $channel } not monotonic at { $ranges }
+info_curve_clipped = { $channel } clipped ({ $black } black, { $white } white)
+info_curve_white = Calibrated white: { $value }
+msg_calibration_invalid = Not a usable calibration file: { $error }
+tray_export = Export Calibration
+tray_export_live = Live Ramp...
+tray_export_expected = Expected Ramp...
+tray_export_size = { $size } Entries
+msg_export_done = Calibration saved to { $file }
+msg_export_failed = Export failed: { $error }
+tray_merge = Merge Calibration into Profile...
+msg_select_curves = Select the calibration to merge
+msg_merge_done = Merged profile saved to { $file }
+msg_merge_failed = Merge failed: { $error }
+msg_repair_done = Repaired copy saved to { $file }
+msg_repair_failed = Repair failed: { $error }
+msg_profile_has_errors = The profile was not installed because it has errors. Run "profile lint --repair" to write a fixed copy. { $issues }
+tray_library = Profile Library
+tray_library_manage = Manage Library...
+tray_library_empty = (empty)
+library_title = Profile Library
+lbl_library_search = Search:
+lbl_library_display = Display:
+btn_library = Profile Library...
+btn_library_import = Import...
+btn_library_assign = Assign
+btn_library_delete = Delete
+library_confirm_delete = Remove { $file } from the profile library?
+msg_library_imported = Added { $file } to the profile library ({ $hash })
+msg_library_import_failed = Could not add the file to the profile library: { $error }
+msg_library_deleted = Removed { $file } from the profile library
+msg_library_delete_failed = Could not remove the profile: { $error }
+msg_assign_done = { $file } assigned to display { $display }
+msg_assign_failed = Could not assign the profile: { $error }
+tray_presets = Presets
+tray_presets_empty = (no presets)
+msg_preset_applied = Switched to preset { $name }
+msg_preset_unknown = There is no preset named { $name }.
+msg_preset_saved = Preset { $name } saved
+msg_preset_deleted = Preset { $name } deleted
+msg_preset_failed = Preset not changed: { $error }
+apply_failed_title = Profile not switched
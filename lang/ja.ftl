@@ -0,0 +1,152 @@
+# Japanese messages for Icc Auto Reloader.
+# Syntax is a subset of Project Fluent: `id = value`, indented continuation
+# lines for multi-line values and `{ $name }` placeholders.
+
+-language-name = 日本語
+
+tray_reload = キャリブレーションを再読み込み
+tray_import = ICCプロファイルをインポート...
+tray_polling = 自動再読み込みを有効化 (ポーリング)
+tray_pause = 自動再読み込みを一時停止
+tray_pause_15m = 15 分間
+tray_pause_1h = 1 時間
+tray_pause_restart = 再起動まで
+tray_pause_resume = 今すぐ再開
+tray_startup = スタートアップに登録
+tray_settings = 設定...
+tray_exit = 終了
+settings_title = 設定
+lbl_interval = ポーリング間隔 (秒):
+lbl_lang = 言語:
+btn_save = 保存
+msg_install_success = プロファイルが正常にインストールされ、読み込まれました。
+msg_install_fail = プロファイルのインストールに失敗しました。
+msg_dispwin_missing = dispwin.exe が見つかりません
+msg_select_profile = ICCプロファイルを選択
+wiz_title = Icc Auto Reloader - セットアップウィザード
+wiz_lbl_lang = 言語を選択してください:
+wiz_welcome =
+    ようこそ！
+
+    開始するには ICC プロファイルをインポートしてください。
+    これにより、モニターの色が常に正確に保たれます。
+wiz_btn_import = インポート
+wiz_btn_skip = スキップ
+wiz_btn_next = 次へ >
+wiz_congrats =
+    おめでとうございます！準備が完了しました。
+
+    Icc Auto Reloader はバックグラウンドで実行され、
+    キャリブレーションを保護します。
+wiz_chk_startup = スタートアップ時に実行
+wiz_btn_finish = 完了
+wiz_title_welcome = ようこそ
+wiz_step1_title = ICCプロファイルをインポート
+wiz_step1_desc = ICCプロファイルを選択するか、スキップしてWindowsのデフォルトプロファイルを使用します。
+wiz_step1_no_profile = プロファイル未選択（Windowsのデフォルトを使用）
+wiz_step1_profile = プロファイル: { $file }
+wiz_step2_title = セットアップ完了！
+wiz_btn_back = < 戻る
+wiz_btn_cancel = キャンセル
+exit_confirm_title = 終了の確認
+exit_confirm_msg = 注意：終了すると、システムは自動的にキャリブレーションを読み込まなくなります。続行しますか？
+chk_rpc = ローカル制御 API を有効化 (JSON-RPC)
+lbl_fullscreen = 全画面表示中:
+fullscreen_keep = 通常どおり再読み込み
+fullscreen_suspend = ポーリングを一時停止
+fullscreen_reload_on_exit = 終了後に再読み込み
+msg_paused = 自動再読み込みを無効にしました。
+msg_resumed = 自動再読み込みを有効にしました。
+msg_paused_for = 自動再読み込みを { $minutes } 分間一時停止しました。
+msg_paused_restart = 自動再読み込みを再起動まで一時停止しました。
+msg_headless_no_ui = ヘッドレスモードでは使用できません。
+msg_reload_requested = キャリブレーションの再読み込みを要求しました。
+msg_settings_opened = 設定ウィンドウを開きました。
+msg_quitting = Icc Auto Reloader を終了しています。
+msg_already_running = Icc Auto Reloader は既に実行中です。
+msg_not_running = Icc Auto Reloader は実行されていません。
+msg_ipc_unreachable = 実行中の Icc Auto Reloader に接続できません
+
+tray_current_profile = 現在のプロファイル
+info_display = { $display }: { $file }
+info_no_profile = プロファイルが関連付けられていません
+info_description = 説明: { $value }
+info_device = デバイス: { $value }
+info_copyright = 著作権: { $value }
+info_created = 作成日時: { $value }
+info_version = バージョン: { $version } ({ $class })
+info_white_point = 白色点: { $value }
+info_luminance = 輝度: { $value } cd/m²
+info_primaries = 原色: { $value }
+info_vcgt_present = キャリブレーションカーブ (vcgt): あり
+info_vcgt_missing = キャリブレーションカーブ (vcgt): なし
+info_mhc2 = アドバンストカラー (MHC2): { $value }
+info_mhc2_none = なし
+info_mhc2_summary = { $min } ～ { $max } cd/m², { $entries } エントリの LUT ({ $luts }), マトリックス { $matrix }
+info_mhc2_matrix = MHC2 マトリックス: { $value }
+info_mhc2_identity = 恒等
+info_mhc2_custom = カスタム
+msg_mhc2_only = { $file } のキャリブレーションは vcgt ではなく MHC2 タグにあります。ガンマランプを再読み込みしても復元されません。
+tip_mhc2_only = MHC2 キャリブレーションは再読み込みされません: { $display }
+tip_held = 再読み込みを保留中: { $reason }
+tip_paused_for = 自動再読み込み一時停止中、残り { $minutes } 分
+tip_paused_restart = 自動再読み込みは再起動まで一時停止中
+info_measurements = 測定データ (targ): { $value }
+info_measurements_none = 埋め込みなし
+info_measurements_summary = { $patches } パッチ, { $instrument }
+info_accuracy = プロファイル精度 (ΔE2000): { $value }
+info_accuracy_summary = 平均 { $mean }, 95 パーセンタイル { $p95 }, 最大 { $max }
+info_accuracy_no_model = 評価できる順方向モデルがありません
+info_unknown = 不明
+msg_profile_unreadable = プロファイルを読み込めません: { $error }
+age_tip_overdue = 再キャリブレーションが必要: { $display }
+age_notify_title = 再キャリブレーションが必要です
+age_notify_body = { $display }: { $file } は作成から { $days } 日経過しています (上限 { $max } 日)。
+info_gamut_coverage = 色域カバー率 ({ $space }): { $value }
+info_gamut_area = 色域面積比 ({ $space }): { $value }
+info_curve_gamma = カーブのガンマ: { $value }
+info_curve_levels = 識別可能な階調 (8 ビット/10 ビット): { $value }
+info_curve_issues = カーブの問題: { $value }
+info_curve_ok = なし
+info_curve_non_monotonic = { $channel } が { $ranges } で単調でない
+info_curve_clipped = { $channel } クリップ (暗部 { $black }, 明部 { $white })
+info_curve_white = 校正後の白色点: { $value }
+msg_calibration_invalid = 使用できない校正ファイルです: { $error }
+tray_export = キャリブレーションのエクスポート
+tray_export_live = 現在のランプ...
+tray_export_expected = 期待されるランプ...
+tray_export_size = { $size } エントリ
+msg_export_done = キャリブレーションを { $file } に保存しました
+msg_export_failed = エクスポートに失敗しました: { $error }
+tray_merge = キャリブレーションをプロファイルに統合...
+msg_select_curves = 統合するキャリブレーションを選択
+msg_merge_done = 統合したプロファイルを { $file } に保存しました
+msg_merge_failed = 統合に失敗しました: { $error }
+msg_repair_done = 修復したコピーを { $file } に保存しました
+msg_repair_failed = 修復に失敗しました: { $error }
+msg_profile_has_errors = プロファイルにエラーがあるためインストールしませんでした。"profile lint --repair" で修復したコピーを作成できます。{ $issues }
+tray_library = プロファイルライブラリ
+tray_library_manage = ライブラリを管理...
+tray_library_empty = (空)
+library_title = プロファイルライブラリ
+lbl_library_search = 検索:
+lbl_library_display = ディスプレイ:
+btn_library = プロファイルライブラリ...
+btn_library_import = インポート...
+btn_library_assign = 割り当て
+btn_library_delete = 削除
+library_confirm_delete = { $file } をプロファイルライブラリから削除しますか？
+msg_library_imported = { $file } をプロファイルライブラリに追加しました ({ $hash })
+msg_library_import_failed = ファイルをプロファイルライブラリに追加できませんでした: { $error }
+msg_library_deleted = { $file } をプロファイルライブラリから削除しました
+msg_library_delete_failed = プロファイルを削除できませんでした: { $error }
+msg_assign_done = { $file } をディスプレイ { $display } に割り当てました
+msg_assign_failed = プロファイルを割り当てられませんでした: { $error }
+tray_presets = プリセット
+tray_presets_empty = (プリセットなし)
+msg_preset_applied = プリセット { $name } に切り替えました
+msg_preset_unknown = { $name } という名前のプリセットはありません。
+msg_preset_saved = プリセット { $name } を保存しました
+msg_preset_deleted = プリセット { $name } を削除しました
+msg_preset_failed = プリセットは変更されていません: { $error }
+apply_failed_title = プロファイルを切り替えられませんでした
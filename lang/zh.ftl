@@ -0,0 +1,151 @@
+# Chinese (Simplified) messages for Icc Auto Reloader.
+# Syntax is a subset of Project Fluent: `id = value`, indented continuation
+# lines for multi-line values and `{ $name }` placeholders.
+
+-language-name = 中文
+
+tray_reload = 重新加载校准
+tray_import = 导入 ICC 配置文件...
+tray_polling = 启用自动重新加载 (轮询)
+tray_pause = 暂停自动重新加载
+tray_pause_15m = 15 分钟
+tray_pause_1h = 1 小时
+tray_pause_restart = 直到重新启动
+tray_pause_resume = 立即恢复
+tray_startup = 开机启动
+tray_settings = 设置...
+tray_exit = 退出
+settings_title = 设置
+lbl_interval = 轮询间隔 (秒):
+lbl_lang = 语言:
+btn_save = 保存
+msg_install_success = 配置文件已安装并加载成功。
+msg_install_fail = 安装配置文件失败。
+msg_dispwin_missing = 未找到 dispwin.exe
+msg_select_profile = 选择 ICC 配置文件
+wiz_title = Icc Auto Reloader - 设置向导
+wiz_lbl_lang = 请选择界面语言:
+wiz_welcome =
+    欢迎！
+
+    请导入一个 ICC 配置文件以开始使用。
+    这将确保您的显示器颜色始终准确。
+wiz_btn_import = 导入
+wiz_btn_skip = 跳过
+wiz_btn_next = 下一步 >
+wiz_congrats =
+    恭喜！设置已完成。
+
+    Icc Auto Reloader 将在后台运行并保护您的校准。
+wiz_chk_startup = 开机时自动运行
+wiz_btn_finish = 完成
+wiz_title_welcome = 欢迎
+wiz_step1_title = 导入 ICC 配置文件
+wiz_step1_desc = 选择一个ICC配置文件，或跳过以使用Windows已安装的配置文件。
+wiz_step1_no_profile = 未选择配置文件（将使用Windows默认）
+wiz_step1_profile = 配置文件: { $file }
+wiz_step2_title = 设置完成！
+wiz_btn_back = < 返回
+wiz_btn_cancel = 取消
+exit_confirm_title = 确认退出
+exit_confirm_msg = 注意，退出后系统将不再自动加载色彩校准，是否继续？
+chk_rpc = 启用本地控制接口 (JSON-RPC)
+lbl_fullscreen = 全屏时:
+fullscreen_keep = 照常重新加载
+fullscreen_suspend = 暂停轮询
+fullscreen_reload_on_exit = 结束后重新加载
+msg_paused = 自动重新加载已关闭。
+msg_resumed = 自动重新加载已开启。
+msg_paused_for = 自动重新加载已暂停 { $minutes } 分钟。
+msg_paused_restart = 自动重新加载已暂停，直到重新启动。
+msg_headless_no_ui = 无界面模式下不可用。
+msg_reload_requested = 已请求重新加载校准。
+msg_settings_opened = 已打开设置窗口。
+msg_quitting = Icc Auto Reloader 正在退出。
+msg_already_running = Icc Auto Reloader 已在运行。
+msg_not_running = Icc Auto Reloader 未在运行。
+msg_ipc_unreachable = 无法连接到正在运行的 Icc Auto Reloader
+
+tray_current_profile = 当前配置文件
+info_display = { $display }: { $file }
+info_no_profile = 未关联配置文件
+info_description = 描述: { $value }
+info_device = 设备: { $value }
+info_copyright = 版权: { $value }
+info_created = 创建时间: { $value }
+info_version = 版本: { $version } ({ $class })
+info_white_point = 白点: { $value }
+info_luminance = 亮度: { $value } cd/m²
+info_primaries = 原色: { $value }
+info_vcgt_present = 校准曲线 (vcgt): 有
+info_vcgt_missing = 校准曲线 (vcgt): 无
+info_mhc2 = 高级颜色 (MHC2): { $value }
+info_mhc2_none = 无
+info_mhc2_summary = { $min } 至 { $max } cd/m², { $entries } 项 LUT ({ $luts }), 矩阵 { $matrix }
+info_mhc2_matrix = MHC2 矩阵: { $value }
+info_mhc2_identity = 恒等
+info_mhc2_custom = 自定义
+msg_mhc2_only = { $file } 的校准数据保存在 MHC2 标签而不是 vcgt 中。重新加载伽马曲线无法恢复它。
+tip_mhc2_only = MHC2 校准，未重新加载: { $display }
+tip_held = 重新加载已搁置: { $reason }
+tip_paused_for = 自动重新加载已暂停，剩余 { $minutes } 分钟
+tip_paused_restart = 自动重新加载已暂停，直到重新启动
+info_measurements = 测量数据 (targ): { $value }
+info_measurements_none = 未嵌入
+info_measurements_summary = { $patches } 个色块, { $instrument }
+info_accuracy = 配置文件精度 (ΔE2000): { $value }
+info_accuracy_summary = 平均 { $mean }, 95 百分位 { $p95 }, 最大 { $max }
+info_accuracy_no_model = 没有可计算的正向模型
+info_unknown = 未知
+msg_profile_unreadable = 无法读取配置文件: { $error }
+age_tip_overdue = 需要重新校准: { $display }
+age_notify_title = 需要重新校准
+age_notify_body = { $display }: { $file } 已使用 { $days } 天 (上限 { $max } 天)。
+info_gamut_coverage = 色域覆盖率 ({ $space }): { $value }
+info_gamut_area = 色域面积比 ({ $space }): { $value }
+info_curve_gamma = 曲线伽马: { $value }
+info_curve_levels = 可区分色阶 (8 位/10 位): { $value }
+info_curve_issues = 曲线问题: { $value }
+info_curve_ok = 无
+info_curve_non_monotonic = { $channel } 在 { $ranges } 处非单调
+info_curve_clipped = { $channel } 截断 (暗部 { $black }, 亮部 { $white })
+info_curve_white = 校准后白点: { $value }
+msg_calibration_invalid = 无法使用的校准文件: { $error }
+tray_export = 导出校准
+tray_export_live = 当前硬件曲线...
+tray_export_expected = 预期曲线...
+tray_export_size = { $size } 个条目
+msg_export_done = 校准已保存到 { $file }
+msg_export_failed = 导出失败: { $error }
+tray_merge = 将校准合并到配置文件...
+msg_select_curves = 选择要合并的校准
+msg_merge_done = 合并后的配置文件已保存到 { $file }
+msg_merge_failed = 合并失败: { $error }
+msg_repair_done = 修复后的副本已保存到 { $file }
+msg_repair_failed = 修复失败: { $error }
+msg_profile_has_errors = 配置文件存在错误，未安装。可运行 "profile lint --repair" 生成修复后的副本。{ $issues }
+tray_library = 配置文件库
+tray_library_manage = 管理配置文件库...
+tray_library_empty = (空)
+library_title = 配置文件库
+lbl_library_search = 搜索:
+lbl_library_display = 显示器:
+btn_library = 配置文件库...
+btn_library_import = 导入...
+btn_library_assign = 分配
+btn_library_delete = 删除
+library_confirm_delete = 要从配置文件库中移除 { $file } 吗？
+msg_library_imported = 已将 { $file } 添加到配置文件库 ({ $hash })
+msg_library_import_failed = 无法将文件添加到配置文件库: { $error }
+msg_library_deleted = 已从配置文件库中移除 { $file }
+msg_library_delete_failed = 无法移除配置文件: { $error }
+msg_assign_done = 已将 { $file } 分配给显示器 { $display }
+msg_assign_failed = 无法分配配置文件: { $error }
+tray_presets = 预设
+tray_presets_empty = (无预设)
+msg_preset_applied = 已切换到预设 { $name }
+msg_preset_unknown = 不存在名为 { $name } 的预设。
+msg_preset_saved = 预设 { $name } 已保存
+msg_preset_deleted = 预设 { $name } 已删除
+msg_preset_failed = 预设未更改: { $error }
+apply_failed_title = 未能切换配置文件
@@ -0,0 +1,301 @@
+// --- Profile Accuracy ---
+//
+// ArgyllCMS and DisplayCAL embed the measurements a profile was built from in
+// the `targ` tag, as the CGATS text of the `.ti3` file. Running each patch's
+// device RGB through the profile's own forward model and comparing with what
+// was measured shows how well the profile describes the display.
+//
+// The forward model is `A2B0` when it is a `mft1`/`mft2` LUT, otherwise the
+// rXYZ/gXYZ/bXYZ matrix with the TRC curves. Measurements are normalized to
+// the white patch and adapted to D50 like the profile's PCS values (through
+// `chad`, or Bradford from the measured white), then both sides are compared
+// in CIELAB with ΔE2000.
+
+use crate::cgats::{self, Table};
+use crate::color::{self, round, Lab, Matrix3, D50};
+use crate::icc::{read_s15f16, read_u16, read_u32, Profile, Xyz};
+use crate::json::Value;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccuracyReport {
+    pub patches: usize,
+    pub instrument: Option<String>,
+    // "lut" or "matrix"; None when the profile has no model we can evaluate
+    pub model: Option<&'static str>,
+    // ΔE2000 statistics, None without a model
+    pub mean: Option<f64>,
+    pub p95: Option<f64>,
+    pub max: Option<f64>,
+}
+
+impl AccuracyReport {
+    pub fn to_json(&self) -> Value {
+        let number = |v: Option<f64>| Value::from(v.map(|v| round(v, 2)));
+        Value::object(vec![
+            ("patches", Value::from(self.patches)),
+            ("instrument", Value::from(self.instrument.clone())),
+            ("model", Value::from(self.model.map(str::to_string))),
+            ("mean", number(self.mean)),
+            ("p95", number(self.p95)),
+            ("max", number(self.max)),
+        ])
+    }
+}
+
+// None when the profile has no readable `targ` measurements.
+pub fn analyze(profile: &Profile) -> Option<AccuracyReport> {
+    let tables = cgats::parse(&profile.text(b"targ")?).ok()?;
+    let table = tables.iter().find(|t| ["RGB_R", "RGB_G", "RGB_B", "XYZ_X", "XYZ_Y", "XYZ_Z"].iter().all(|f| t.field_index(f).is_some()))?;
+    let patches = measurements(table)?;
+    let instrument = table
+        .keyword("TARGET_INSTRUMENT")
+        .or_else(|| table.keyword("INSTRUMENT"))
+        .map(str::to_string)
+        .or_else(|| profile.dict(b"meta").into_iter().find(|(k, _)| k == "MEASUREMENT_device").map(|(_, v)| v))
+        .filter(|i| !i.is_empty());
+
+    let model = Model::of(profile);
+    let mut errors: Vec<f64> = match &model {
+        Some(model) => {
+            let to_pcs = to_pcs(profile, &patches);
+            patches
+                .iter()
+                .filter_map(|(rgb, measured)| {
+                    let expected = model.eval(*rgb)?;
+                    let measured = color::mul_vec(&to_pcs, measured);
+                    Some(color::delta_e_2000(&color::xyz_to_lab(&measured, &D50), &color::xyz_to_lab(&expected, &D50)))
+                })
+                .collect()
+        }
+        None => Vec::new(),
+    };
+    errors.sort_by(f64::total_cmp);
+    let stat = |f: &dyn Fn(&[f64]) -> f64| (!errors.is_empty()).then(|| f(&errors));
+
+    Some(AccuracyReport {
+        patches: patches.len(),
+        instrument,
+        model: model.as_ref().map(Model::name),
+        mean: stat(&|e| e.iter().sum::<f64>() / e.len() as f64),
+        // Nearest rank
+        p95: stat(&|e| e[((e.len() as f64 * 0.95).ceil() as usize).clamp(1, e.len()) - 1]),
+        max: stat(&|e| e[e.len() - 1]),
+    })
+}
+
+// (device RGB 0..1, measured XYZ with white Y = 1) per patch.
+fn measurements(table: &Table) -> Option<Vec<([f64; 3], Xyz)>> {
+    let column = |name: &str| table.column(name);
+    let (r, g, b) = (column("RGB_R")?, column("RGB_G")?, column("RGB_B")?);
+    let (x, y, z) = (column("XYZ_X")?, column("XYZ_Y")?, column("XYZ_Z")?);
+    if r.is_empty() {
+        return None;
+    }
+    // Argyll writes device values as 0..100
+    let scale = match r.iter().chain(&g).chain(&b).fold(0.0, |m: f64, &v| m.max(v)) {
+        m if m <= 1.0 => 1.0,
+        m if m <= 100.0 => 100.0,
+        _ => 255.0,
+    };
+    let rgb: Vec<[f64; 3]> = (0..r.len()).map(|i| [r[i] / scale, g[i] / scale, b[i] / scale]).collect();
+
+    // The white patch, or the brightest one if there is none
+    let white = rgb
+        .iter()
+        .position(|c| c.iter().all(|&v| v >= 0.999))
+        .or_else(|| (0..y.len()).max_by(|&a, &b| y[a].total_cmp(&y[b])))?;
+    let white_y = y[white];
+    if white_y <= 0.0 {
+        return None;
+    }
+    Some((0..rgb.len()).map(|i| (rgb[i], Xyz { x: x[i] / white_y, y: y[i] / white_y, z: z[i] / white_y })).collect())
+}
+
+// Adaptation from the measured white to the D50 PCS.
+fn to_pcs(profile: &Profile, patches: &[([f64; 3], Xyz)]) -> Matrix3 {
+    if let Some(chad) = profile.matrix(b"chad") {
+        return chad;
+    }
+    let white = patches.iter().map(|(_, xyz)| *xyz).max_by(|a, b| a.y.total_cmp(&b.y)).unwrap_or(D50);
+    color::bradford(&white, &D50)
+}
+
+enum Model {
+    Matrix { matrix: Matrix3, curves: [Curve; 3] },
+    Lut(Lut),
+}
+
+impl Model {
+    fn of(profile: &Profile) -> Option<Model> {
+        if let Some(lut) = profile.tag(b"A2B0").and_then(|t| Lut::parse(t, &profile.bytes()[20..24] == b"Lab ")) {
+            return Some(Model::Lut(lut));
+        }
+        let (r, g, b) = (profile.xyz(b"rXYZ")?, profile.xyz(b"gXYZ")?, profile.xyz(b"bXYZ")?);
+        let curve = |sig: &[u8; 4]| profile.tag(sig).and_then(Curve::parse);
+        Some(Model::Matrix {
+            matrix: [[r.x, g.x, b.x], [r.y, g.y, b.y], [r.z, g.z, b.z]],
+            curves: [curve(b"rTRC")?, curve(b"gTRC")?, curve(b"bTRC")?],
+        })
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Model::Matrix { .. } => "matrix",
+            Model::Lut(_) => "lut",
+        }
+    }
+
+    // PCS XYZ (D50) for device RGB
+    fn eval(&self, rgb: [f64; 3]) -> Option<Xyz> {
+        match self {
+            Model::Matrix { matrix, curves } => {
+                let linear = Xyz { x: curves[0].eval(rgb[0]), y: curves[1].eval(rgb[1]), z: curves[2].eval(rgb[2]) };
+                Some(color::mul_vec(matrix, &linear))
+            }
+            Model::Lut(lut) => lut.eval(rgb),
+        }
+    }
+}
+
+// A TRC: `curv` (identity, gamma or table) or `para`.
+enum Curve {
+    Gamma(f64),
+    Table(Vec<f64>),
+    // g, a, b, c, d, e, f of the parametric function
+    Parametric(u16, [f64; 7]),
+}
+
+impl Curve {
+    fn parse(tag: &[u8]) -> Option<Curve> {
+        match tag.get(0..4)? {
+            b"curv" => match read_u32(tag, 8)? {
+                0 => Some(Curve::Gamma(1.0)),
+                1 => Some(Curve::Gamma(read_u16(tag, 12)? as f64 / 256.0)),
+                n => (0..n as usize).map(|i| Some(read_u16(tag, 12 + i * 2)? as f64 / 65535.0)).collect::<Option<_>>().map(Curve::Table),
+            },
+            b"para" => {
+                let kind = read_u16(tag, 8)?;
+                let count = [1, 3, 4, 5, 7].get(kind as usize)?;
+                let mut p = [0.0; 7];
+                for (i, value) in p.iter_mut().take(*count).enumerate() {
+                    *value = read_s15f16(tag, 12 + i * 4)?;
+                }
+                Some(Curve::Parametric(kind, p))
+            }
+            _ => None,
+        }
+    }
+
+    fn eval(&self, x: f64) -> f64 {
+        let x = x.clamp(0.0, 1.0);
+        match self {
+            Curve::Gamma(g) => x.powf(*g),
+            Curve::Table(t) => interpolate(t, x),
+            Curve::Parametric(kind, [g, a, b, c, d, e, f]) => {
+                let power = |x: f64| if x > 0.0 { x.powf(*g) } else { 0.0 };
+                match kind {
+                    0 => power(x),
+                    1 if x >= -b / a => power(a * x + b),
+                    2 if x >= -b / a => power(a * x + b) + c,
+                    2 => *c,
+                    3 if x >= *d => power(a * x + b),
+                    3 => c * x,
+                    4 if x >= *d => power(a * x + b) + e,
+                    4 => c * x + f,
+                    _ => 0.0,
+                }
+            }
+        }
+    }
+}
+
+// A `mft1` (8-bit) or `mft2` (16-bit) LUT with three inputs and three outputs.
+// The matrix only applies to XYZ input and is ignored for RGB devices.
+struct Lut {
+    input: Vec<Vec<f64>>,
+    grid: usize,
+    // Output values for every grid point, first input varying slowest
+    clut: Vec<[f64; 3]>,
+    output: Vec<Vec<f64>>,
+    lab: bool,
+    wide: bool,
+}
+
+impl Lut {
+    fn parse(tag: &[u8], lab: bool) -> Option<Lut> {
+        let wide = match tag.get(0..4)? {
+            b"mft1" => false,
+            b"mft2" => true,
+            _ => return None,
+        };
+        let (inputs, outputs, grid) = (*tag.get(8)? as usize, *tag.get(9)? as usize, *tag.get(10)? as usize);
+        if inputs != 3 || outputs != 3 || grid < 2 {
+            return None;
+        }
+        let (in_entries, out_entries, mut offset) =
+            if wide { (read_u16(tag, 48)? as usize, read_u16(tag, 50)? as usize, 52) } else { (256, 256, 48) };
+        let size = if wide { 2 } else { 1 };
+        let max = if wide { 65535.0 } else { 255.0 };
+        let mut values = |count: usize| -> Option<Vec<f64>> {
+            let read = (0..count)
+                .map(|i| {
+                    let at = offset + i * size;
+                    if wide { read_u16(tag, at).map(f64::from) } else { tag.get(at).map(|&b| b as f64) }
+                })
+                .map(|v| v.map(|v| v / max))
+                .collect();
+            offset += count * size;
+            read
+        };
+
+        let input = (0..3).map(|_| values(in_entries)).collect::<Option<Vec<_>>>()?;
+        let clut = values(grid.pow(3) * 3)?.chunks(3).map(|c| [c[0], c[1], c[2]]).collect();
+        let output = (0..3).map(|_| values(out_entries)).collect::<Option<Vec<_>>>()?;
+        Some(Lut { input, grid, clut, output, lab, wide })
+    }
+
+    fn eval(&self, rgb: [f64; 3]) -> Option<Xyz> {
+        let last = (self.grid - 1) as f64;
+        let position: Vec<f64> = (0..3).map(|c| interpolate(&self.input[c], rgb[c].clamp(0.0, 1.0)) * last).collect();
+        let base: Vec<usize> = position.iter().map(|p| (p.floor() as usize).min(self.grid - 2)).collect();
+        let frac: Vec<f64> = position.iter().zip(&base).map(|(p, &b)| p - b as f64).collect();
+
+        // Trilinear interpolation over the cell's eight corners
+        let mut value = [0.0; 3];
+        for corner in 0..8 {
+            let step = |axis: usize| (corner >> (2 - axis)) & 1;
+            let weight: f64 = (0..3).map(|a| if step(a) == 1 { frac[a] } else { 1.0 - frac[a] }).product();
+            let index = (0..3).fold(0, |i, a| i * self.grid + base[a] + step(a));
+            let entry = self.clut.get(index)?;
+            for (v, e) in value.iter_mut().zip(entry) {
+                *v += weight * e;
+            }
+        }
+        let pcs: Vec<f64> = (0..3).map(|c| interpolate(&self.output[c], value[c].clamp(0.0, 1.0))).collect();
+
+        if self.lab {
+            // Legacy 16-bit Lab (L 0..100 over 0..0xFF00) or 8-bit Lab
+            let scale = if self.wide { 65535.0 / 65280.0 } else { 1.0 };
+            let lab: Lab = [pcs[0] * scale * 100.0, pcs[1] * scale * 255.0 - 128.0, pcs[2] * scale * 255.0 - 128.0];
+            Some(color::lab_to_xyz(&lab, &D50))
+        } else {
+            // u1Fixed15: 0x8000 is 1.0
+            let scale = 65535.0 / 32768.0;
+            Some(Xyz { x: pcs[0] * scale, y: pcs[1] * scale, z: pcs[2] * scale })
+        }
+    }
+}
+
+// Linear interpolation in a table spread evenly over 0..1.
+fn interpolate(table: &[f64], x: f64) -> f64 {
+    match table.len() {
+        0 => x,
+        1 => table[0],
+        n => {
+            let position = x.clamp(0.0, 1.0) * (n - 1) as f64;
+            let i = (position.floor() as usize).min(n - 2);
+            let t = position - i as f64;
+            table[i] + (table[i + 1] - table[i]) * t
+        }
+    }
+}
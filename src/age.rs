@@ -0,0 +1,153 @@
+// --- Calibration Age ---
+//
+// Flags displays whose profile is older than the allowed re-calibration
+// interval. The age is taken from the newest date found in the profile: the
+// header creation date, or a date/time entry of DisplayCAL's `meta` tag.
+//
+// Limits are configured under `HKCU\Software\IccAutoReloader`:
+//
+//   MaxProfileAgeDays          DWORD, applies to every display (0 or missing: off)
+//   MaxProfileAgePerDisplay    REG_SZ, `<display>=<days>` pairs separated by `;`,
+//                              where <display> is the device name (\\.\DISPLAY2)
+//                              or the monitor ID shown by `profile ages`
+//
+// Each display/profile pair is reported once when it becomes overdue.
+
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use windows::core::w;
+
+use crate::dates;
+use crate::display::{self, Display};
+use crate::events::{self, Event};
+use crate::icc::{IccDate, Profile};
+use crate::json::Value;
+use crate::log;
+
+#[derive(Debug, Clone)]
+pub struct ProfileAge {
+    pub display: Display,
+    pub profile: Option<PathBuf>,
+    pub calibrated: Option<IccDate>,
+    pub age_days: Option<f64>,
+    pub max_days: Option<u32>,
+}
+
+impl ProfileAge {
+    pub fn is_overdue(&self) -> bool {
+        matches!((self.age_days, self.max_days), (Some(age), Some(max)) if age > max as f64)
+    }
+
+    pub fn to_json(&self) -> Value {
+        Value::object(vec![
+            ("display", Value::from(self.display.device.as_str())),
+            ("id", Value::from(self.display.id.as_str())),
+            ("profile", Value::from(self.profile.as_ref().map(|p| p.to_string_lossy().to_string()))),
+            ("calibrated", Value::from(self.calibrated.map(|d| d.to_iso8601()))),
+            ("age_days", Value::from(self.age_days.map(|a| (a * 10.0).round() / 10.0))),
+            ("max_age_days", Value::from(self.max_days)),
+            ("overdue", Value::from(self.is_overdue())),
+        ])
+    }
+}
+
+// Newest calibration date recorded in the profile.
+pub fn calibration_date(profile: &Profile) -> Option<IccDate> {
+    profile
+        .dict(b"meta")
+        .into_iter()
+        .filter(|(name, _)| {
+            let name = name.to_ascii_lowercase();
+            name.contains("date") || name.contains("time")
+        })
+        .filter_map(|(_, value)| IccDate::parse(&value))
+        .chain(profile.created())
+        .max()
+}
+
+pub fn age_of(display: &Display, now: SystemTime) -> ProfileAge {
+    let profile = display::profile_path(display);
+    let calibrated = profile.as_ref().and_then(|p| Profile::load(p).ok()).and_then(|p| calibration_date(&p));
+    let age_days = calibrated.map(|d| (dates::unix_seconds(now) - d.unix_seconds()) as f64 / 86400.0);
+    ProfileAge { display: display.clone(), profile, calibrated, age_days, max_days: max_age_days(display) }
+}
+
+pub fn check_all() -> Vec<ProfileAge> {
+    let now = SystemTime::now();
+    display::enumerate().iter().map(|d| age_of(d, now)).collect()
+}
+
+fn max_age_days(display: &Display) -> Option<u32> {
+    let per_display = crate::get_reg_string(w!("MaxProfileAgePerDisplay")).unwrap_or_default();
+    let limit = parse_overrides(&per_display)
+        .into_iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(&display.device) || (!display.id.is_empty() && key.eq_ignore_ascii_case(&display.id)))
+        .map(|(_, days)| days)
+        .or_else(|| crate::get_reg_dword(w!("MaxProfileAgeDays")));
+    limit.filter(|&d| d > 0)
+}
+
+fn parse_overrides(text: &str) -> Vec<(String, u32)> {
+    text.split(';')
+        .filter_map(|pair| pair.rsplit_once('='))
+        .filter_map(|(key, days)| Some((key.trim().to_string(), days.trim().parse().ok()?)))
+        .collect()
+}
+
+// The entries of `ages` that went past their limit since the last call.
+// Logs them and emits `ProfileOverdue`; callers decide how to tell the user.
+pub fn newly_overdue(ages: &[ProfileAge]) -> Vec<ProfileAge> {
+    static NOTIFIED: Mutex<Vec<(String, PathBuf)>> = Mutex::new(Vec::new());
+
+    let Ok(mut notified) = NOTIFIED.lock() else { return Vec::new() };
+    // Forget displays that were re-calibrated or got another profile
+    notified.retain(|(device, path)| {
+        ages.iter().any(|a| a.is_overdue() && &a.display.device == device && a.profile.as_ref() == Some(path))
+    });
+
+    let mut fresh = Vec::new();
+    for age in ages.iter().filter(|a| a.is_overdue()) {
+        let Some(path) = age.profile.clone() else { continue };
+        if notified.iter().any(|(device, p)| *device == age.display.device && *p == path) {
+            continue;
+        }
+        notified.push((age.display.device.clone(), path.clone()));
+        let days = age.age_days.unwrap_or_default() as u32;
+        log::warn(&format!(
+            "Profile {} on {} is {} days old (limit {} days)",
+            path.display(),
+            age.display.device,
+            days,
+            age.max_days.unwrap_or_default()
+        ));
+        events::emit(Event::ProfileOverdue { display: age.display.device.clone(), age_days: days });
+        fresh.push(age.clone());
+    }
+    fresh
+}
+
+// Text table for `profile ages`.
+pub fn format_text(ages: &[ProfileAge]) -> String {
+    let mut out = String::new();
+    for age in ages {
+        let file = age
+            .profile
+            .as_ref()
+            .and_then(|p| p.file_name())
+            .map_or("-".to_string(), |f| f.to_string_lossy().to_string());
+        let date = age.calibrated.map_or("-".to_string(), |d| d.to_string());
+        let days = age.age_days.map_or("-".to_string(), |a| format!("{:.0} days", a.floor()));
+        let limit = match age.max_days {
+            Some(max) if age.is_overdue() => format!("limit {}, OVERDUE", max),
+            Some(max) => format!("limit {}", max),
+            None => "no limit".to_string(),
+        };
+        out.push_str(&format!("{}  {}  {}  {}  ({})\n", age.display.device, file, date, days, limit));
+        if !age.display.id.is_empty() {
+            out.push_str(&format!("  id: {}\n", age.display.id));
+        }
+    }
+    out
+}
@@ -0,0 +1,408 @@
+// --- Application Rules ---
+//
+// Rules keyed on the foreground program, kept in `<app data>\app_rules.json`:
+//
+//   {
+//     "rules": [
+//       {"match": "eldenring.exe", "action": "suspend"},
+//       {"match": "C:\\Games\\Old\\game.exe", "action": "reload_on_exit"},
+//       {"match": "Photoshop.exe", "action": "preset", "preset": "Print D50"}
+//     ]
+//   }
+//
+// `match` is an executable file name, or a full path when it contains a
+// backslash; both ignore case. The first matching rule applies:
+//
+//   suspend         no drift reloads while the program has the focus
+//   reload_on_exit  reload once the program has exited, for programs that
+//                   leave their own gamma ramp behind
+//   preset          switch to a preset while the program has the focus, and
+//                   back to the previous one afterwards (unless the preset was
+//                   changed in the meantime)
+//
+// The foreground comes from a `ProcessWatcher` so `RuleEngine` can be driven
+// by simulated focus changes.
+
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use windows::core::PWSTR;
+use windows::Win32::Foundation::{CloseHandle, HANDLE};
+use windows::Win32::System::Threading::{
+    GetExitCodeProcess, OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION,
+};
+use windows::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, GetWindowThreadProcessId};
+
+use crate::json::{self, Value};
+
+// GetExitCodeProcess result while the process runs
+const STILL_ACTIVE: u32 = 259;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Process {
+    pub pid: u32,
+    pub path: PathBuf,
+}
+
+impl Process {
+    pub fn name(&self) -> String {
+        file_name(&self.path.to_string_lossy()).to_string()
+    }
+}
+
+// Tells the rules which process has the focus and which are still running.
+pub trait ProcessWatcher {
+    fn foreground(&mut self) -> Option<Process>;
+    fn is_running(&mut self, pid: u32) -> bool;
+}
+
+// The real thing: the process owning the foreground window.
+pub struct ForegroundWatcher;
+
+impl ProcessWatcher for ForegroundWatcher {
+    fn foreground(&mut self) -> Option<Process> {
+        let mut pid = 0u32;
+        unsafe {
+            let hwnd = GetForegroundWindow();
+            if hwnd.0 == 0 {
+                return None;
+            }
+            GetWindowThreadProcessId(hwnd, Some(&mut pid as *mut u32));
+        }
+        if pid == 0 {
+            return None;
+        }
+        let handle = open_process(pid)?;
+        let mut buffer = [0u16; 1024];
+        let mut len = buffer.len() as u32;
+        let result = unsafe { QueryFullProcessImageNameW(handle, PROCESS_NAME_WIN32, PWSTR(buffer.as_mut_ptr()), &mut len) };
+        unsafe {
+            let _ = CloseHandle(handle);
+        }
+        result.ok()?;
+        Some(Process { pid, path: PathBuf::from(String::from_utf16_lossy(&buffer[..len as usize])) })
+    }
+
+    fn is_running(&mut self, pid: u32) -> bool {
+        let Some(handle) = open_process(pid) else { return false };
+        let mut code = 0u32;
+        let result = unsafe { GetExitCodeProcess(handle, &mut code) };
+        unsafe {
+            let _ = CloseHandle(handle);
+        }
+        result.is_ok() && code == STILL_ACTIVE
+    }
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit(['\\', '/']).next().unwrap_or(path)
+}
+
+fn open_process(pid: u32) -> Option<HANDLE> {
+    unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid).ok() }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AppAction {
+    Suspend,
+    ReloadOnExit,
+    Preset(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AppRule {
+    // File name or full path, see the top of the file
+    pub pattern: String,
+    pub action: AppAction,
+}
+
+impl AppRule {
+    fn matches(&self, path: &Path) -> bool {
+        let path = path.to_string_lossy().replace('/', "\\");
+        if self.pattern.contains('\\') {
+            path.eq_ignore_ascii_case(&self.pattern)
+        } else {
+            file_name(&path).eq_ignore_ascii_case(&self.pattern)
+        }
+    }
+}
+
+// What the caller has to do after a focus change or a process exit.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Effect {
+    ApplyPreset(String),
+    // Hold drift reloads back for the named program
+    Suspend(String),
+    Resume,
+    Reload,
+}
+
+#[derive(Debug, Default)]
+pub struct RuleEngine {
+    rules: Vec<AppRule>,
+    // Rule of the program that has the focus
+    focused: Option<usize>,
+    // Preset active before a preset rule switched away from it
+    restore: Option<String>,
+    // Processes with a reload_on_exit rule that were seen running
+    watched: Vec<u32>,
+}
+
+impl RuleEngine {
+    pub fn new(rules: Vec<AppRule>) -> RuleEngine {
+        RuleEngine { rules, ..Default::default() }
+    }
+
+    // Looks at the foreground and the watched processes. `active_preset` is
+    // the preset in use right now.
+    pub fn update(&mut self, watcher: &mut dyn ProcessWatcher, active_preset: Option<&str>) -> Vec<Effect> {
+        let mut effects = Vec::new();
+        let foreground = watcher.foreground();
+        let focused = foreground.as_ref().and_then(|p| self.rules.iter().position(|r| r.matches(&p.path)));
+
+        if let (Some(process), Some(index)) = (&foreground, focused) {
+            if self.rules[index].action == AppAction::ReloadOnExit && !self.watched.contains(&process.pid) {
+                self.watched.push(process.pid);
+            }
+        }
+        let watched = self.watched.len();
+        self.watched.retain(|&pid| watcher.is_running(pid));
+        if self.watched.len() < watched {
+            effects.push(Effect::Reload);
+        }
+
+        if focused == self.focused {
+            return effects;
+        }
+        let old = self.focused.map(|i| self.rules[i].action.clone());
+        let new = focused.map(|i| self.rules[i].action.clone());
+        self.focused = focused;
+
+        // Preset to return to, when going from one preset rule to another
+        let mut carried = None;
+        match old {
+            Some(AppAction::Suspend) => effects.push(Effect::Resume),
+            Some(AppAction::Preset(name)) => {
+                // A preset chosen while the program had the focus stays
+                let unchanged = active_preset.is_some_and(|a| a.eq_ignore_ascii_case(&name));
+                let restore = self.restore.take().filter(|_| unchanged);
+                if matches!(new, Some(AppAction::Preset(_))) {
+                    carried = Some(restore);
+                } else if let Some(previous) = restore {
+                    effects.push(Effect::ApplyPreset(previous));
+                }
+            }
+            Some(AppAction::ReloadOnExit) | None => {}
+        }
+        match new {
+            Some(AppAction::Suspend) => effects.push(Effect::Suspend(foreground.map(|p| p.name()).unwrap_or_default())),
+            Some(AppAction::Preset(name)) => {
+                let previous = match carried {
+                    Some(previous) => previous,
+                    None => active_preset.map(str::to_string),
+                };
+                self.restore = previous.filter(|p| !p.eq_ignore_ascii_case(&name));
+                if !active_preset.is_some_and(|a| a.eq_ignore_ascii_case(&name)) {
+                    effects.push(Effect::ApplyPreset(name));
+                }
+            }
+            Some(AppAction::ReloadOnExit) | None => {}
+        }
+        effects
+    }
+
+    // Undoes a running suspension before the rules are replaced.
+    fn release(&mut self) -> Vec<Effect> {
+        let effects = match self.focused.map(|i| &self.rules[i].action) {
+            Some(AppAction::Suspend) => vec![Effect::Resume],
+            _ => Vec::new(),
+        };
+        self.focused = None;
+        self.restore = None;
+        effects
+    }
+}
+
+pub fn path() -> PathBuf {
+    crate::app_data_dir().join("app_rules.json")
+}
+
+pub fn parse(text: &str) -> Result<Vec<AppRule>, String> {
+    let value = json::parse(text)?;
+    let mut rules = Vec::new();
+    for (i, rule) in value.get("rules").and_then(Value::as_array).into_iter().flatten().enumerate() {
+        let text = |key: &str| rule.get(key).and_then(Value::as_str).map(str::trim).filter(|t| !t.is_empty());
+        let pattern = text("match").ok_or_else(|| format!("rule {}: missing match", i + 1))?;
+        let action = match text("action") {
+            Some("suspend") => AppAction::Suspend,
+            Some("reload_on_exit") => AppAction::ReloadOnExit,
+            Some("preset") => AppAction::Preset(text("preset").ok_or_else(|| format!("rule {}: missing preset", i + 1))?.to_string()),
+            Some(other) => return Err(format!("rule {}: unknown action: {}", i + 1, other)),
+            None => return Err(format!("rule {}: missing action", i + 1)),
+        };
+        rules.push(AppRule { pattern: pattern.replace('/', "\\"), action });
+    }
+    Ok(rules)
+}
+
+// The rules file as last read, with its modification time
+struct Loaded {
+    modified: Option<SystemTime>,
+    engine: RuleEngine,
+}
+
+static LOADED: Mutex<Option<Loaded>> = Mutex::new(None);
+
+// Runs the rules against the real foreground, re-reading the rules file when
+// it changed. A broken file is logged and leaves no rules.
+pub fn check(active_preset: Option<&str>) -> Vec<Effect> {
+    let Ok(mut loaded) = LOADED.lock() else { return Vec::new() };
+    let modified = std::fs::metadata(path()).and_then(|m| m.modified()).ok();
+    let mut effects = Vec::new();
+    if loaded.as_ref().is_none_or(|l| l.modified != modified) {
+        let rules = match std::fs::read_to_string(path()) {
+            Ok(text) => parse(&text).unwrap_or_else(|e| {
+                crate::log::warn(&format!("Application rules ignored: {}: {}", path().display(), e));
+                Vec::new()
+            }),
+            Err(_) => Vec::new(),
+        };
+        if let Some(old) = loaded.as_mut() {
+            effects.extend(old.engine.release());
+        }
+        *loaded = Some(Loaded { modified, engine: RuleEngine::new(rules) });
+    }
+    let Some(l) = loaded.as_mut() else { return effects };
+    if !l.engine.rules.is_empty() {
+        effects.extend(l.engine.update(&mut ForegroundWatcher, active_preset));
+    }
+    effects
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Focus and running processes set by the test
+    #[derive(Default)]
+    struct FakeWatcher {
+        foreground: Option<Process>,
+        running: Vec<u32>,
+    }
+
+    impl FakeWatcher {
+        fn focus(&mut self, pid: u32, path: &str) {
+            self.foreground = Some(Process { pid, path: PathBuf::from(path) });
+            if !self.running.contains(&pid) {
+                self.running.push(pid);
+            }
+        }
+
+        fn exit(&mut self, pid: u32) {
+            self.running.retain(|&p| p != pid);
+            if self.foreground.as_ref().is_some_and(|p| p.pid == pid) {
+                self.foreground = None;
+            }
+        }
+    }
+
+    impl ProcessWatcher for FakeWatcher {
+        fn foreground(&mut self) -> Option<Process> {
+            self.foreground.clone()
+        }
+
+        fn is_running(&mut self, pid: u32) -> bool {
+            self.running.contains(&pid)
+        }
+    }
+
+    const RULES: &str = r#"{"rules": [
+        {"match": "game.exe", "action": "suspend"},
+        {"match": "C:/Players/video.exe", "action": "reload_on_exit"},
+        {"match": "photoshop.exe", "action": "preset", "preset": "Print"},
+        {"match": "lightroom.exe", "action": "preset", "preset": "Photo"}
+    ]}"#;
+
+    fn engine() -> RuleEngine {
+        RuleEngine::new(parse(RULES).unwrap())
+    }
+
+    #[test]
+    fn parses_rules() {
+        let rules = parse(RULES).unwrap();
+        assert_eq!(rules.len(), 4);
+        assert_eq!(rules[1].pattern, "C:\\Players\\video.exe");
+        assert_eq!(rules[2].action, AppAction::Preset("Print".to_string()));
+        assert!(parse(r#"{"rules": [{"match": "x.exe", "action": "explode"}]}"#).is_err());
+        assert!(parse(r#"{"rules": [{"match": "x.exe", "action": "preset"}]}"#).is_err());
+    }
+
+    #[test]
+    fn matches_name_or_full_path() {
+        let rules = parse(RULES).unwrap();
+        assert!(rules[0].matches(Path::new("D:\\Steam\\GAME.EXE")));
+        assert!(rules[1].matches(Path::new("c:\\players\\Video.exe")));
+        assert!(!rules[1].matches(Path::new("D:\\Other\\video.exe")));
+    }
+
+    #[test]
+    fn suspends_while_focused() {
+        let mut engine = engine();
+        let mut watcher = FakeWatcher::default();
+        watcher.focus(1, "C:\\Windows\\explorer.exe");
+        assert!(engine.update(&mut watcher, None).is_empty());
+        watcher.focus(2, "D:\\Steam\\game.exe");
+        assert_eq!(engine.update(&mut watcher, None), vec![Effect::Suspend("game.exe".to_string())]);
+        assert!(engine.update(&mut watcher, None).is_empty());
+        watcher.focus(1, "C:\\Windows\\explorer.exe");
+        assert_eq!(engine.update(&mut watcher, None), vec![Effect::Resume]);
+    }
+
+    #[test]
+    fn reloads_after_exit_not_focus_loss() {
+        let mut engine = engine();
+        let mut watcher = FakeWatcher::default();
+        watcher.focus(7, "C:\\Players\\video.exe");
+        assert!(engine.update(&mut watcher, None).is_empty());
+        watcher.focus(1, "C:\\Windows\\explorer.exe");
+        assert!(engine.update(&mut watcher, None).is_empty());
+        watcher.exit(7);
+        assert_eq!(engine.update(&mut watcher, None), vec![Effect::Reload]);
+        assert!(engine.update(&mut watcher, None).is_empty());
+    }
+
+    #[test]
+    fn switches_preset_and_back() {
+        let mut engine = engine();
+        let mut watcher = FakeWatcher::default();
+        watcher.focus(3, "C:\\Adobe\\Photoshop.exe");
+        assert_eq!(engine.update(&mut watcher, Some("Screen")), vec![Effect::ApplyPreset("Print".to_string())]);
+        // From one preset program to another, then back to the desktop
+        watcher.focus(4, "C:\\Adobe\\Lightroom.exe");
+        assert_eq!(engine.update(&mut watcher, Some("Print")), vec![Effect::ApplyPreset("Photo".to_string())]);
+        watcher.focus(1, "C:\\Windows\\explorer.exe");
+        assert_eq!(engine.update(&mut watcher, Some("Photo")), vec![Effect::ApplyPreset("Screen".to_string())]);
+    }
+
+    #[test]
+    fn keeps_a_preset_chosen_meanwhile() {
+        let mut engine = engine();
+        let mut watcher = FakeWatcher::default();
+        watcher.focus(3, "C:\\Adobe\\Photoshop.exe");
+        assert_eq!(engine.update(&mut watcher, Some("Screen")), vec![Effect::ApplyPreset("Print".to_string())]);
+        watcher.focus(1, "C:\\Windows\\explorer.exe");
+        // Someone picked "Night" while Photoshop had the focus
+        assert!(engine.update(&mut watcher, Some("Night")).is_empty());
+    }
+
+    #[test]
+    fn already_active_preset_is_not_reapplied() {
+        let mut engine = engine();
+        let mut watcher = FakeWatcher::default();
+        watcher.focus(3, "C:\\Adobe\\Photoshop.exe");
+        assert!(engine.update(&mut watcher, Some("print")).is_empty());
+        watcher.focus(1, "C:\\Windows\\explorer.exe");
+        assert!(engine.update(&mut watcher, Some("Print")).is_empty());
+    }
+}
@@ -0,0 +1,95 @@
+// --- ArgyllCMS Calibration Files ---
+//
+// `dispcal` writes the video LUT it computed as a `.cal` file: a CGATS table
+// with the input in RGB_I and one output column per channel, all 0..1. The
+// same table is appended to `.ti3` files, so the first table with these
+// columns is used.
+
+use std::path::Path;
+
+use crate::cgats::{self, Table};
+use crate::display::Ramp;
+use crate::ramp;
+
+const COLUMNS: [&str; 3] = ["RGB_R", "RGB_G", "RGB_B"];
+
+pub fn load(path: &Path) -> Result<Ramp, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    parse(&text)
+}
+
+pub fn parse(text: &str) -> Result<Ramp, String> {
+    let tables = cgats::parse(text)?;
+    let table = tables
+        .iter()
+        .find(|t| t.field_index("RGB_I").is_some() && COLUMNS.iter().all(|c| t.field_index(c).is_some()))
+        .ok_or_else(|| "no table with RGB_I, RGB_R, RGB_G and RGB_B columns".to_string())?;
+    if table.keyword("DEVICE_CLASS").is_some_and(|c| !c.eq_ignore_ascii_case("DISPLAY")) {
+        return Err("calibration is not for a display".to_string());
+    }
+    to_ramp(table)
+}
+
+fn to_ramp(table: &Table) -> Result<Ramp, String> {
+    let number = |name: &str| table.column(name).ok_or_else(|| format!("{} contains a value that is not a number", name));
+    let inputs = number("RGB_I")?;
+    if inputs.len() < 2 {
+        return Err("calibration needs at least two entries".to_string());
+    }
+    if inputs.windows(2).any(|w| w[1] <= w[0]) {
+        return Err("RGB_I is not strictly increasing".to_string());
+    }
+
+    let mut curves = Vec::new();
+    for column in COLUMNS {
+        let outputs = number(column)?;
+        if outputs.iter().any(|v| !(-0.001..=1.001).contains(v)) {
+            return Err(format!("{} has values outside 0..1", column));
+        }
+        curves.push((0..256).map(|i| ramp::interpolate(&inputs, &outputs, i as f64 / 255.0)).collect());
+    }
+    ramp::from_curves(&curves).ok_or_else(|| "calibration is empty".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cal(header: &str, fields: &str, rows: &str) -> String {
+        format!("CAL\n{}BEGIN_DATA_FORMAT\n{}\nEND_DATA_FORMAT\nBEGIN_DATA\n{}END_DATA\n", header, fields, rows)
+    }
+
+    #[test]
+    fn interpolates_to_256_entries() {
+        let text = cal("DEVICE_CLASS \"DISPLAY\"\n", "RGB_I RGB_R RGB_G RGB_B", "0 0 0 0\n0.5 0.25 0.5 0.5\n1 1 1 0.8\n");
+        let ramp = parse(&text).unwrap();
+        assert_eq!(ramp[0][0], 0);
+        assert_eq!(ramp[1][255], 65535);
+        assert_eq!(ramp[2][255], 52428);
+        // Halfway between the first two entries
+        assert_eq!(ramp[0][64], (0.25 * 64.0 / 127.5 * 65535.0_f64).round() as u16);
+    }
+
+    #[test]
+    fn uses_the_first_calibration_table() {
+        let ti3 = "CTI3\nBEGIN_DATA_FORMAT\nSAMPLE_ID RGB_R\nEND_DATA_FORMAT\nBEGIN_DATA\n1 0.5\nEND_DATA\n";
+        let text = format!("{}{}", ti3, cal("", "RGB_I RGB_R RGB_G RGB_B", "0 0 0 0\n1 0.5 0.5 0.5\n"));
+        assert_eq!(parse(&text).unwrap()[0][255], 32768);
+    }
+
+    #[test]
+    fn rejects_unusable_calibrations() {
+        let fields = "RGB_I RGB_R RGB_G RGB_B";
+        let cases = [
+            (cal("", "RGB_I RGB_R RGB_G", "0 0 0\n1 1 1\n"), "no table with RGB_I, RGB_R, RGB_G and RGB_B columns"),
+            (cal("DEVICE_CLASS \"OUTPUT\"\n", fields, "0 0 0 0\n1 1 1 1\n"), "calibration is not for a display"),
+            (cal("", fields, "0 0 0 0\n"), "calibration needs at least two entries"),
+            (cal("", fields, "0 0 0 0\n0.6 1 1 1\n0.5 1 1 1\n"), "RGB_I is not strictly increasing"),
+            (cal("", fields, "0 0 0 0\n1 1 x 1\n"), "RGB_G contains a value that is not a number"),
+            (cal("", fields, "0 0 0 0\n1 1 1 1.2\n"), "RGB_B has values outside 0..1"),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(parse(&text).map(|_| ()), Err(expected.to_string()), "{:?}", text);
+        }
+    }
+}
@@ -0,0 +1,98 @@
+// --- Calibration Sources ---
+//
+// A display's calibration normally comes from the `vcgt` tag of the profile
+// Windows associates with it, and reloads use `dispwin -L`. A calibration
+// file can take that role instead: an ArgyllCMS `.cal`, or a 1D `.cube` or
+// CSV curve from a hardware calibration tool. It is loaded with
+// `dispwin -d <n> <file>` on every reload until a profile is installed for
+// the same display again. dispwin only reads `.cal`, so the other formats are
+// converted to one in the app data folder first.
+//
+// Sources are stored under `HKCU\Software\IccAutoReloader` as
+//
+//   CalibrationSources    REG_SZ, `<display>=<file>` pairs separated by `|`
+//                         (which cannot occur in a path)
+
+use std::path::{Path, PathBuf};
+
+use windows::core::w;
+
+use crate::cal;
+use crate::display::{self, Display, Ramp};
+use crate::export;
+use crate::icc::Profile;
+use crate::lut;
+
+// Files that carry calibration curves only, as opposed to ICC profiles.
+pub fn is_calibration_file(path: &Path) -> bool {
+    let ext = path.extension().and_then(|e| e.to_str()).map(|e| e.to_ascii_lowercase());
+    matches!(ext.as_deref(), Some("cal") | Some("cube") | Some("csv"))
+}
+
+pub fn load(path: &Path) -> Result<Ramp, String> {
+    let ext = path.extension().and_then(|e| e.to_str()).map(|e| e.to_ascii_lowercase());
+    match ext.as_deref() {
+        Some("cube") => lut::load_cube(path),
+        Some("csv") => lut::load_csv(path),
+        _ => cal::load(path),
+    }
+}
+
+// A `.cal` file dispwin can load for `source` on `display`, converted from
+// the source's current content when it is in another format. Converted files
+// are named after the display too, so two displays' `calibration.cube` files
+// from different folders do not overwrite each other.
+pub fn dispwin_file(source: &Path, display: &Display) -> Result<PathBuf, String> {
+    if source.extension().is_some_and(|e| e.eq_ignore_ascii_case("cal")) {
+        return Ok(source.to_path_buf());
+    }
+    let ramp = load(source)?;
+    let dir = crate::app_data_dir().join("calibration");
+    std::fs::create_dir_all(&dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    let name = source.file_name().map_or("calibration".into(), |n| n.to_string_lossy().to_string());
+    // `\\.\DISPLAY1` -> `DISPLAY1`
+    let key: String = display.device.chars().filter(char::is_ascii_alphanumeric).collect();
+    let target = dir.join(format!("{}-{}.cal", key, name));
+    export::save(&target, &ramp, export::SIZES[0])?;
+    Ok(target)
+}
+
+// The ramp a reload should leave on `display`: its calibration file, or the
+// `vcgt` of its profile.
+pub fn expected_ramp(display: &Display) -> Result<Ramp, String> {
+    if let Some(file) = source_for(display) {
+        return load(&file);
+    }
+    let profile = display::profile_path(display).ok_or_else(|| format!("{} has no profile", display.device))?;
+    Profile::load(&profile)?.vcgt().ok_or_else(|| format!("{} has no usable vcgt tag", profile.display()))
+}
+
+// The calibration file that replaces the profile's `vcgt` on `display`.
+pub fn source_for(display: &Display) -> Option<PathBuf> {
+    sources()
+        .into_iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(&display.device) || (!display.id.is_empty() && key.eq_ignore_ascii_case(&display.id)))
+        .map(|(_, path)| path)
+}
+
+// Makes `path` the calibration source of `display`, or goes back to the
+// profile with None.
+pub fn set_source(display: &Display, path: Option<&Path>) {
+    let mut sources = sources();
+    sources.retain(|(key, _)| !key.eq_ignore_ascii_case(&display.device) && !key.eq_ignore_ascii_case(&display.id));
+    if let Some(path) = path {
+        sources.push((display.device.clone(), path.to_path_buf()));
+    }
+    let value: Vec<String> = sources.iter().map(|(key, path)| format!("{}={}", key, path.display())).collect();
+    crate::set_reg_string(w!("CalibrationSources"), &value.join("|"));
+}
+
+fn sources() -> Vec<(String, PathBuf)> {
+    crate::get_reg_string(w!("CalibrationSources"))
+        .unwrap_or_default()
+        .split('|')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(key, path)| (key.trim().to_string(), PathBuf::from(path.trim())))
+        .filter(|(key, path)| !key.is_empty() && !path.as_os_str().is_empty())
+        .collect()
+}
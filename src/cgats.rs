@@ -0,0 +1,228 @@
+// --- CGATS ---
+//
+// Reader for the CGATS.17 text format ArgyllCMS uses for `.cal`, `.ti1` and
+// `.ti3` files:
+//
+//   CAL
+//   DESCRIPTOR "Argyll Device Calibration State"
+//   KEYWORD "DEVICE_CLASS"
+//   DEVICE_CLASS "DISPLAY"
+//   NUMBER_OF_FIELDS 4
+//   BEGIN_DATA_FORMAT
+//   RGB_I RGB_R RGB_G RGB_B
+//   END_DATA_FORMAT
+//   NUMBER_OF_SETS 256
+//   BEGIN_DATA
+//   0.0 0.0 0.0 0.0
+//   ...
+//   END_DATA
+//
+// A file can hold several tables, each starting with its own file type line.
+// `#` starts a comment; values may be quoted.
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Table {
+    // First line of the table, e.g. `CAL` or `CTI3`
+    pub file_type: String,
+    pub keywords: Vec<(String, String)>,
+    pub fields: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn keyword(&self, name: &str) -> Option<&str> {
+        self.keywords.iter().find(|(k, _)| k.eq_ignore_ascii_case(name)).map(|(_, v)| v.as_str())
+    }
+
+    pub fn field_index(&self, name: &str) -> Option<usize> {
+        self.fields.iter().position(|f| f.eq_ignore_ascii_case(name))
+    }
+
+    // A numeric column. None if the field is missing or a value is not a number.
+    pub fn column(&self, name: &str) -> Option<Vec<f64>> {
+        let index = self.field_index(name)?;
+        self.rows.iter().map(|row| row.get(index)?.parse().ok()).collect()
+    }
+}
+
+pub fn parse(text: &str) -> Result<Vec<Table>, String> {
+    enum Section {
+        Header,
+        Format,
+        Data,
+    }
+
+    let mut tables: Vec<Table> = Vec::new();
+    let mut section = Section::Header;
+    let mut row: Vec<String> = Vec::new();
+
+    for (number, line) in text.lines().enumerate() {
+        let tokens = tokenize(line);
+        let Some(first) = tokens.first() else { continue };
+        let error = |message: &str| format!("line {}: {}", number + 1, message);
+
+        match section {
+            Section::Format => {
+                if first == "END_DATA_FORMAT" {
+                    section = Section::Header;
+                } else if let Some(table) = tables.last_mut() {
+                    table.fields.extend(tokens);
+                }
+            }
+            Section::Data => {
+                if first == "END_DATA" {
+                    if !row.is_empty() {
+                        return Err(error("incomplete data set"));
+                    }
+                    section = Section::Header;
+                    continue;
+                }
+                let Some(table) = tables.last_mut() else { continue };
+                if table.fields.is_empty() {
+                    return Err(error("data before BEGIN_DATA_FORMAT"));
+                }
+                // Sets may be wrapped over several lines
+                for token in tokens {
+                    row.push(token);
+                    if row.len() == table.fields.len() {
+                        table.rows.push(std::mem::take(&mut row));
+                    }
+                }
+            }
+            Section::Header => match first.as_str() {
+                "BEGIN_DATA_FORMAT" => section = Section::Format,
+                "BEGIN_DATA" => section = Section::Data,
+                // Declares a custom keyword; the value follows on its own line
+                "KEYWORD" => {}
+                _ if tokens.len() == 1 && !line.trim_start().starts_with('"') => {
+                    tables.push(Table { file_type: first.clone(), ..Default::default() });
+                }
+                _ => {
+                    let Some(table) = tables.last_mut() else { return Err(error("keyword before the file type line")) };
+                    table.keywords.push((first.clone(), tokens[1..].join(" ")));
+                }
+            },
+        }
+    }
+
+    if !matches!(section, Section::Header) {
+        return Err("unexpected end of file".to_string());
+    }
+    for table in &tables {
+        let expected = table.keyword("NUMBER_OF_SETS").and_then(|n| n.parse::<usize>().ok());
+        if matches!(expected, Some(n) if n != table.rows.len()) {
+            return Err(format!("{}: NUMBER_OF_SETS does not match the data", table.file_type));
+        }
+    }
+    if tables.is_empty() {
+        return Err("not a CGATS file".to_string());
+    }
+    Ok(tables)
+}
+
+// Splits on whitespace, keeping quoted strings together without the quotes
+// and dropping comments.
+fn tokenize(line: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut chars = line.trim().chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '#' {
+            break;
+        } else if c == '"' {
+            chars.next();
+            tokens.push(chars.by_ref().take_while(|&c| c != '"').collect());
+        } else {
+            let mut token = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                token.push(c);
+                chars.next();
+            }
+            tokens.push(token);
+        }
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CAL: &str = "CAL\n\
+        DESCRIPTOR \"Argyll Device Calibration State\" # written by dispcal\n\
+        KEYWORD \"DEVICE_CLASS\"\n\
+        DEVICE_CLASS \"DISPLAY\"\n\
+        BEGIN_DATA_FORMAT\n\
+        RGB_I RGB_R RGB_G RGB_B\n\
+        END_DATA_FORMAT\n\
+        NUMBER_OF_SETS 3\n\
+        BEGIN_DATA\n\
+        0 0 0 0\n\
+        0.5 0.4 0.45 0.5\n\
+        1 1 1 1\n\
+        END_DATA\n";
+
+    #[test]
+    fn reads_keywords_fields_and_rows() {
+        let tables = parse(CAL).unwrap();
+        assert_eq!(tables.len(), 1);
+        let table = &tables[0];
+        assert_eq!(table.file_type, "CAL");
+        assert_eq!(table.keyword("descriptor"), Some("Argyll Device Calibration State"));
+        assert_eq!(table.keyword("DEVICE_CLASS"), Some("DISPLAY"));
+        assert_eq!(table.fields, ["RGB_I", "RGB_R", "RGB_G", "RGB_B"]);
+        assert_eq!(table.column("RGB_G"), Some(vec![0.0, 0.45, 1.0]));
+        assert_eq!(table.column("XYZ_X"), None);
+    }
+
+    #[test]
+    fn wrapped_sets_and_several_tables() {
+        let text = "CTI3\n\
+            BEGIN_DATA_FORMAT\n\
+            A B\n\
+            C\n\
+            END_DATA_FORMAT\n\
+            NUMBER_OF_SETS 2\n\
+            BEGIN_DATA\n\
+            1 2\n\
+            3 4\n\
+            5\n\
+            6\n\
+            END_DATA\n\
+            CAL\n\
+            BEGIN_DATA_FORMAT\n\
+            X\n\
+            END_DATA_FORMAT\n\
+            BEGIN_DATA\n\
+            \"a b\"\n\
+            END_DATA\n";
+        let tables = parse(text).unwrap();
+        assert_eq!(tables.len(), 2);
+        assert_eq!(tables[0].fields, ["A", "B", "C"]);
+        assert_eq!(tables[0].rows, [["1", "2", "3"], ["4", "5", "6"]]);
+        assert_eq!(tables[1].file_type, "CAL");
+        assert_eq!(tables[1].rows, [["a b"]]);
+    }
+
+    #[test]
+    fn rejects_malformed_files() {
+        let cases = [
+            ("", "not a CGATS file"),
+            ("DESCRIPTOR \"x\"\n", "line 1: keyword before the file type line"),
+            ("CAL\nBEGIN_DATA\n1 2\nEND_DATA\n", "line 3: data before BEGIN_DATA_FORMAT"),
+            ("CAL\nBEGIN_DATA_FORMAT\nA B\nEND_DATA_FORMAT\nBEGIN_DATA\n1 2 3\nEND_DATA\n", "line 7: incomplete data set"),
+            ("CAL\nBEGIN_DATA_FORMAT\nA\nEND_DATA_FORMAT\nBEGIN_DATA\n1\n", "unexpected end of file"),
+            (
+                "CAL\nBEGIN_DATA_FORMAT\nA\nEND_DATA_FORMAT\nNUMBER_OF_SETS 3\nBEGIN_DATA\n1\n2\nEND_DATA\n",
+                "CAL: NUMBER_OF_SETS does not match the data",
+            ),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(parse(text), Err(expected.to_string()), "{:?}", text);
+        }
+    }
+}
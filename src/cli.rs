@@ -0,0 +1,455 @@
+// --- Command Line ---
+//
+// The executable doubles as a small remote control for the running instance:
+//
+//   icc_auto_reloader.exe                 start normally (tray)
+//   icc_auto_reloader.exe reload          re-apply the current calibration
+//   icc_auto_reloader.exe install <file>  install and load an ICC profile or
+//                                         keep a .cal/.cube/.csv curve loaded
+//   icc_auto_reloader.exe pause|resume    switch auto-reload off or on
+//   icc_auto_reloader.exe pause <15m|1h|restart>
+//                                         pause auto-reload for a while, not saved
+//   icc_auto_reloader.exe status [--json] report what the reloader is doing
+//   icc_auto_reloader.exe settings        open the Settings window
+//   icc_auto_reloader.exe quit            stop the running instance
+//   icc_auto_reloader.exe profile info <file> [--json]
+//                                         describe an ICC profile or curve file
+//   icc_auto_reloader.exe profile ages [--json]
+//                                         age of every display's profile
+//   icc_auto_reloader.exe profile export <file.cal|.cube|.csv>
+//                [--from live|expected|<file>] [--display <n>] [--size 256|1024|4096]
+//                                         write a display's ramp or a file's curves
+//   icc_auto_reloader.exe profile merge <profile> <curves> [--output <file>]
+//                                         copy a profile with its vcgt taken from curves
+//   icc_auto_reloader.exe profile lint <profile> [--json] [--repair [--output <file>]]
+//                                         check a profile's structure, optionally
+//                                         writing a repaired copy
+//   icc_auto_reloader.exe library list [--json]
+//   icc_auto_reloader.exe library search <text> [--json]
+//                                         show the profile library
+//   icc_auto_reloader.exe library import <file>
+//                                         add a profile or curve file to the library
+//   icc_auto_reloader.exe library delete <entry>
+//                                         remove an entry (hash, hash prefix or file name)
+//   icc_auto_reloader.exe library assign <entry> [--display <n>]
+//                                         install an entry on a display
+//   icc_auto_reloader.exe preset list [--json]
+//                                         show the presets, the active one marked
+//   icc_auto_reloader.exe preset save <name>
+//                                         make a preset of what the displays use now
+//   icc_auto_reloader.exe preset set <name> <entry> [--display <n>]
+//                                         put a library entry into a preset
+//   icc_auto_reloader.exe preset delete <name>
+//   icc_auto_reloader.exe preset apply <name>
+//                                         switch every display to a preset
+//   icc_auto_reloader.exe schedule [--json]
+//                                         show the schedule rules and the next switch
+//   icc_auto_reloader.exe --headless [--interval <seconds>]
+//                                         run without tray or windows
+//
+// A bare profile or curve file path is treated as `install <file>` so the exe can be used
+// with "Open with". Leading dashes or slashes (`--reload`, `/reload`) are accepted.
+
+use std::path::Path;
+
+use crate::engine;
+use crate::export::{RampSource, SIZES};
+use crate::headless::HeadlessOptions;
+use crate::ipc::Request;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CliCommand {
+    Run,
+    Headless(HeadlessOptions),
+    Status { json: bool },
+    Send(Request),
+    // Works on files directly, whether or not an instance is running
+    Profile(ProfileCommand),
+    // Works on the library directory directly, like `Profile`
+    Library(LibraryCommand),
+    Preset(PresetCommand),
+    // Reads the schedule file directly, like `Library`
+    Schedule { json: bool },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProfileCommand {
+    Info { path: String, json: bool },
+    Ages { json: bool },
+    Export { output: String, source: RampSource, display: usize, size: usize },
+    Merge { profile: String, curves: String, output: Option<String> },
+    Lint { path: String, json: bool, repair: bool, output: Option<String> },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LibraryCommand {
+    List { json: bool },
+    Search { query: String, json: bool },
+    Import { path: String },
+    Delete { entry: String },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PresetCommand {
+    List { json: bool },
+    Save { name: String },
+    Set { name: String, entry: String, display: usize },
+    Delete { name: String },
+}
+
+pub fn parse_args<I: IntoIterator<Item = String>>(args: I) -> Result<CliCommand, String> {
+    let mut args = args.into_iter();
+    let first = match args.next() {
+        Some(a) => a,
+        None => return Ok(CliCommand::Run),
+    };
+
+    let verb = first.trim_start_matches(['-', '/']).to_ascii_lowercase();
+    let command = match verb.as_str() {
+        "reload" => CliCommand::Send(Request::Reload),
+        "install" | "import" => match args.next() {
+            Some(path) => CliCommand::Send(Request::Install(absolute_path(&path))),
+            None => return Err("install: missing profile path".to_string()),
+        },
+        "pause" => match args.next() {
+            None => CliCommand::Send(Request::Pause),
+            Some(duration) => CliCommand::Send(Request::PauseFor(parse_pause(&duration)?)),
+        },
+        "resume" => CliCommand::Send(Request::Resume),
+        "status" => match args.next().as_deref() {
+            None => CliCommand::Status { json: false },
+            Some("--json") | Some("/json") => CliCommand::Status { json: true },
+            Some(other) => return Err(format!("unexpected argument: {}", other)),
+        },
+        "settings" => CliCommand::Send(Request::OpenSettings),
+        "quit" | "exit" => CliCommand::Send(Request::Quit),
+        "headless" | "daemon" => return parse_headless(args),
+        "profile" => return parse_profile(args),
+        "library" => return parse_library(args),
+        "preset" => return parse_preset(args),
+        "schedule" => match args.next().as_deref() {
+            None => CliCommand::Schedule { json: false },
+            Some("--json") | Some("/json") => CliCommand::Schedule { json: true },
+            Some(other) => return Err(format!("unexpected argument: {}", other)),
+        },
+        _ if is_profile_path(&first) => CliCommand::Send(Request::Install(absolute_path(&first))),
+        _ => return Err(format!("unknown command: {}", first)),
+    };
+
+    if let Some(extra) = args.next() {
+        return Err(format!("unexpected argument: {}", extra));
+    }
+    Ok(command)
+}
+
+fn parse_headless<I: Iterator<Item = String>>(mut args: I) -> Result<CliCommand, String> {
+    let mut options = HeadlessOptions::default();
+    while let Some(arg) = args.next() {
+        match arg.trim_start_matches(['-', '/']).to_ascii_lowercase().as_str() {
+            "interval" => {
+                let value = args.next().ok_or_else(|| "--interval: missing value".to_string())?;
+                let sec: u32 = value.parse().map_err(|_| format!("--interval: invalid number: {}", value))?;
+                if !(1..=3600).contains(&sec) {
+                    return Err("--interval: expected 1 to 3600 seconds".to_string());
+                }
+                options.interval_ms = Some(sec * 1000);
+            }
+            _ => return Err(format!("unexpected argument: {}", arg)),
+        }
+    }
+    Ok(CliCommand::Headless(options))
+}
+
+fn parse_profile<I: Iterator<Item = String>>(mut args: I) -> Result<CliCommand, String> {
+    let sub = args.next().ok_or_else(|| "profile: missing subcommand (info, ages, export, merge, lint)".to_string())?;
+    if sub.eq_ignore_ascii_case("export") {
+        return parse_export(args);
+    }
+    if sub.eq_ignore_ascii_case("merge") {
+        return parse_merge(args);
+    }
+    if sub.eq_ignore_ascii_case("lint") {
+        return parse_lint(args);
+    }
+    let mut path = None;
+    let mut json = false;
+    for arg in args {
+        match arg.as_str() {
+            "--json" | "/json" => json = true,
+            _ if path.is_none() => path = Some(absolute_path(&arg)),
+            _ => return Err(format!("unexpected argument: {}", arg)),
+        }
+    }
+
+    match sub.to_ascii_lowercase().as_str() {
+        "info" => {
+            let path = path.ok_or_else(|| "profile info: missing profile path".to_string())?;
+            Ok(CliCommand::Profile(ProfileCommand::Info { path, json }))
+        }
+        "ages" => match path {
+            Some(extra) => Err(format!("unexpected argument: {}", extra)),
+            None => Ok(CliCommand::Profile(ProfileCommand::Ages { json })),
+        },
+        _ => Err(format!("profile: unknown subcommand: {}", sub)),
+    }
+}
+
+fn parse_export<I: Iterator<Item = String>>(mut args: I) -> Result<CliCommand, String> {
+    let mut output = None;
+    let mut source = RampSource::Live;
+    let mut display = 1;
+    let mut size = SIZES[0];
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or_else(|| format!("{}: missing value", name));
+        match arg.as_str() {
+            "--from" | "/from" => {
+                source = match value("--from")?.as_str() {
+                    "live" => RampSource::Live,
+                    "expected" => RampSource::Expected,
+                    file => RampSource::File(absolute_path(file)),
+                }
+            }
+            "--display" | "/display" => {
+                let text = value("--display")?;
+                display = text.parse().ok().filter(|&n| n >= 1).ok_or_else(|| format!("--display: invalid display number: {}", text))?;
+            }
+            "--size" | "/size" => {
+                let text = value("--size")?;
+                size = text.parse().ok().filter(|n| SIZES.contains(n)).ok_or_else(|| format!("--size: expected 256, 1024 or 4096, got {}", text))?;
+            }
+            _ if output.is_none() => output = Some(absolute_path(&arg)),
+            _ => return Err(format!("unexpected argument: {}", arg)),
+        }
+    }
+    let output = output.ok_or_else(|| "profile export: missing output file".to_string())?;
+    Ok(CliCommand::Profile(ProfileCommand::Export { output, source, display, size }))
+}
+
+fn parse_merge<I: Iterator<Item = String>>(mut args: I) -> Result<CliCommand, String> {
+    let mut files = Vec::new();
+    let mut output = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--output" | "/output" | "-o" => {
+                output = Some(absolute_path(&args.next().ok_or_else(|| "--output: missing value".to_string())?));
+            }
+            _ if files.len() < 2 => files.push(absolute_path(&arg)),
+            _ => return Err(format!("unexpected argument: {}", arg)),
+        }
+    }
+    let [profile, curves]: [String; 2] =
+        files.try_into().map_err(|_| "profile merge: expected a profile and a calibration file".to_string())?;
+    Ok(CliCommand::Profile(ProfileCommand::Merge { profile, curves, output }))
+}
+
+fn parse_lint<I: Iterator<Item = String>>(mut args: I) -> Result<CliCommand, String> {
+    let mut path = None;
+    let (mut json, mut repair) = (false, false);
+    let mut output = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" | "/json" => json = true,
+            "--repair" | "/repair" => repair = true,
+            "--output" | "/output" | "-o" => {
+                output = Some(absolute_path(&args.next().ok_or_else(|| "--output: missing value".to_string())?));
+            }
+            _ if path.is_none() => path = Some(absolute_path(&arg)),
+            _ => return Err(format!("unexpected argument: {}", arg)),
+        }
+    }
+    let path = path.ok_or_else(|| "profile lint: missing profile path".to_string())?;
+    if output.is_some() && !repair {
+        return Err("profile lint: --output needs --repair".to_string());
+    }
+    Ok(CliCommand::Profile(ProfileCommand::Lint { path, json, repair, output }))
+}
+
+fn parse_library<I: Iterator<Item = String>>(mut args: I) -> Result<CliCommand, String> {
+    let sub = args.next().ok_or_else(|| "library: missing subcommand (list, search, import, delete, assign)".to_string())?;
+    let mut value = None;
+    let mut json = false;
+    let mut display = 1;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" | "/json" => json = true,
+            "--display" | "/display" => {
+                let text = args.next().ok_or_else(|| "--display: missing value".to_string())?;
+                display = text.parse().ok().filter(|&n| n >= 1).ok_or_else(|| format!("--display: invalid display number: {}", text))?;
+            }
+            _ if value.is_none() => value = Some(arg),
+            _ => return Err(format!("unexpected argument: {}", arg)),
+        }
+    }
+
+    let sub = sub.to_ascii_lowercase();
+    let mut needs = |what: &str| value.take().ok_or_else(|| format!("library {}: missing {}", sub, what));
+    let command = match sub.as_str() {
+        "list" => LibraryCommand::List { json },
+        "search" => LibraryCommand::Search { query: needs("search text")?, json },
+        "import" => LibraryCommand::Import { path: absolute_path(&needs("file")?) },
+        "delete" => LibraryCommand::Delete { entry: needs("entry")? },
+        // Installing needs the running instance, like `install`
+        "assign" => return Ok(CliCommand::Send(Request::Assign { display, entry: needs("entry")? })),
+        _ => return Err(format!("library: unknown subcommand: {}", sub)),
+    };
+    match value {
+        Some(extra) => Err(format!("unexpected argument: {}", extra)),
+        None => Ok(CliCommand::Library(command)),
+    }
+}
+
+fn parse_preset<I: Iterator<Item = String>>(mut args: I) -> Result<CliCommand, String> {
+    let sub = args.next().ok_or_else(|| "preset: missing subcommand (list, save, set, delete, apply)".to_string())?;
+    let mut values = Vec::new();
+    let mut json = false;
+    let mut display = 1;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" | "/json" => json = true,
+            "--display" | "/display" => {
+                let text = args.next().ok_or_else(|| "--display: missing value".to_string())?;
+                display = text.parse().ok().filter(|&n| n >= 1).ok_or_else(|| format!("--display: invalid display number: {}", text))?;
+            }
+            _ => values.push(arg),
+        }
+    }
+
+    let sub = sub.to_ascii_lowercase();
+    let expected = match sub.as_str() {
+        "list" => 0,
+        "set" => 2,
+        "save" | "delete" | "apply" => 1,
+        _ => return Err(format!("preset: unknown subcommand: {}", sub)),
+    };
+    if values.len() < expected {
+        return Err(format!("preset {}: missing {}", sub, if values.is_empty() { "name" } else { "entry" }));
+    }
+    if let Some(extra) = values.get(expected) {
+        return Err(format!("unexpected argument: {}", extra));
+    }
+    let mut values = values.into_iter();
+    let mut next = || values.next().unwrap_or_default();
+    let command = match sub.as_str() {
+        "list" => PresetCommand::List { json },
+        "save" => PresetCommand::Save { name: next() },
+        "set" => PresetCommand::Set { name: next(), entry: next(), display },
+        "delete" => PresetCommand::Delete { name: next() },
+        // Switching needs the running instance, like `library assign`
+        _ => return Ok(CliCommand::Send(Request::ApplyPreset(next()))),
+    };
+    Ok(CliCommand::Preset(command))
+}
+
+// `restart`, or a duration like `15m`, `1h`, `90s`; plain numbers are minutes.
+// Seconds, None until restart.
+fn parse_pause(text: &str) -> Result<Option<u64>, String> {
+    let text = text.trim().to_ascii_lowercase();
+    if text == "restart" {
+        return Ok(None);
+    }
+    let (number, unit) = match text.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => text.split_at(i),
+        None => (text.as_str(), "m"),
+    };
+    let scale = match unit {
+        "s" => 1,
+        "m" | "min" => 60,
+        "h" => 3600,
+        _ => return Err(format!("pause: invalid duration: {}", text)),
+    };
+    match number.parse::<u64>().ok().and_then(|n| n.checked_mul(scale)) {
+        Some(seconds) if (1..=engine::MAX_PAUSE.as_secs()).contains(&seconds) => Ok(Some(seconds)),
+        _ => Err(format!("pause: invalid duration: {}", text)),
+    }
+}
+
+fn is_profile_path(arg: &str) -> bool {
+    let ext = Path::new(arg)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase());
+    matches!(ext.as_deref(), Some("icc") | Some("icm") | Some("cal") | Some("cube") | Some("csv"))
+}
+
+// The primary instance may run with a different working directory, so paths
+// are resolved here before they are forwarded.
+fn absolute_path(arg: &str) -> String {
+    let path = Path::new(arg);
+    if path.is_absolute() {
+        return arg.to_string();
+    }
+    std::env::current_dir()
+        .map(|dir| dir.join(path).to_string_lossy().to_string())
+        .unwrap_or_else(|_| arg.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(line: &str) -> Result<CliCommand, String> {
+        parse_args(line.split_whitespace().map(String::from))
+    }
+
+    #[test]
+    fn remote_commands() {
+        assert_eq!(parse(""), Ok(CliCommand::Run));
+        assert_eq!(parse("--reload"), Ok(CliCommand::Send(Request::Reload)));
+        assert_eq!(parse("/QUIT"), Ok(CliCommand::Send(Request::Quit)));
+        assert_eq!(parse("status --json"), Ok(CliCommand::Status { json: true }));
+        assert_eq!(parse("preset apply Print"), Ok(CliCommand::Send(Request::ApplyPreset("Print".to_string()))));
+        assert_eq!(
+            parse("library assign 3f2a91 --display 2"),
+            Ok(CliCommand::Send(Request::Assign { display: 2, entry: "3f2a91".to_string() }))
+        );
+    }
+
+    #[test]
+    fn pause_durations() {
+        assert_eq!(parse("pause"), Ok(CliCommand::Send(Request::Pause)));
+        assert_eq!(parse("pause 15m"), Ok(CliCommand::Send(Request::PauseFor(Some(900)))));
+        assert_eq!(parse("pause 1h"), Ok(CliCommand::Send(Request::PauseFor(Some(3600)))));
+        assert_eq!(parse("pause 90s"), Ok(CliCommand::Send(Request::PauseFor(Some(90)))));
+        assert_eq!(parse("pause 30"), Ok(CliCommand::Send(Request::PauseFor(Some(1800)))));
+        assert_eq!(parse("pause restart"), Ok(CliCommand::Send(Request::PauseFor(None))));
+        assert_eq!(parse("pause 168h"), Ok(CliCommand::Send(Request::PauseFor(Some(7 * 24 * 3600)))));
+        assert!(parse("pause 0").is_err());
+        assert!(parse("pause 5x").is_err());
+        assert_eq!(parse("pause 169h"), Err("pause: invalid duration: 169h".to_string()));
+        // Overflows u64 when scaled to seconds
+        assert_eq!(parse("pause 5124095576030432h"), Err("pause: invalid duration: 5124095576030432h".to_string()));
+    }
+
+    #[test]
+    fn headless_interval() {
+        assert_eq!(parse("--headless --interval 5"), Ok(CliCommand::Headless(HeadlessOptions { interval_ms: Some(5000) })));
+        assert!(parse("--headless --interval").is_err());
+        assert!(parse("--headless --interval 0").is_err());
+        assert!(parse("--headless --interval soon").is_err());
+    }
+
+    #[test]
+    fn argument_errors() {
+        let errors = [
+            ("frobnicate", "unknown command"),
+            ("reload now", "unexpected argument"),
+            ("install", "missing profile path"),
+            ("status --xml", "unexpected argument"),
+            ("profile", "missing subcommand"),
+            ("profile info", "missing profile path"),
+            ("profile export out.cal --size 512", "expected 256, 1024 or 4096"),
+            ("profile export out.cal --display 0", "invalid display number"),
+            ("profile merge a.icc", "expected a profile and a calibration file"),
+            ("profile lint a.icc --output b.icc", "--output needs --repair"),
+            ("library search", "missing search text"),
+            ("library frob", "unknown subcommand"),
+            ("preset set Print", "missing entry"),
+            ("preset list extra", "unexpected argument"),
+            ("schedule now", "unexpected argument"),
+        ];
+        for (line, expected) in errors {
+            let error = parse(line).unwrap_err();
+            assert!(error.contains(expected), "`{}` gave `{}`", line, error);
+        }
+    }
+}
@@ -0,0 +1,165 @@
+// --- Displays ---
+//
+// Enumerates monitors in the same order dispwin numbers them (`-d 1`, `-d 2`,
+// ...), reads their gamma ramps and asks Windows which profile is associated
+// with each one.
+
+use std::ffi::c_void;
+use std::path::PathBuf;
+
+use windows::core::{w, PCSTR, PCWSTR, PWSTR};
+use windows::Win32::Foundation::{BOOL, LPARAM, RECT, TRUE};
+use windows::Win32::Graphics::Gdi::{
+    CreateDCW, DeleteDC, EnumDisplayDevicesW, EnumDisplayMonitors, GetMonitorInfoW, DISPLAY_DEVICEW, HDC, HMONITOR,
+    MONITORINFO, MONITORINFOEXW,
+};
+use windows::Win32::System::LibraryLoader::{GetModuleHandleW, GetProcAddress};
+use windows::Win32::UI::ColorSystem::GetICMProfileW;
+
+pub type Ramp = [[u16; 256]; 3];
+
+#[derive(Debug, Clone)]
+pub struct Display {
+    // Zero-based; dispwin's `-d` number is `index + 1`
+    pub index: usize,
+    // GDI device name, e.g. `\\.\DISPLAY1`
+    pub device: String,
+    // Monitor name as shown in Device Manager
+    pub name: String,
+    // Monitor device ID (PnP ID plus instance), stable across reboots
+    pub id: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RampClass {
+    // Identity ramp: no calibration loaded
+    Linear,
+    // Anything else: some calibration is loaded
+    Calibrated,
+    // The ramp could not be read
+    Unreadable,
+}
+
+impl RampClass {
+    pub fn name(&self) -> &'static str {
+        match self {
+            RampClass::Linear => "linear",
+            RampClass::Calibrated => "calibrated",
+            RampClass::Unreadable => "unreadable",
+        }
+    }
+}
+
+pub fn enumerate() -> Vec<Display> {
+    let mut devices: Vec<String> = Vec::new();
+    unsafe {
+        let _ = EnumDisplayMonitors(
+            HDC::default(),
+            None,
+            Some(collect_monitor),
+            LPARAM(&mut devices as *mut Vec<String> as isize),
+        );
+    }
+
+    devices
+        .into_iter()
+        .enumerate()
+        .map(|(index, device)| {
+            let (name, id) = monitor_details(&device);
+            Display { index, device, name, id }
+        })
+        .collect()
+}
+
+unsafe extern "system" fn collect_monitor(monitor: HMONITOR, _hdc: HDC, _rect: *mut RECT, data: LPARAM) -> BOOL {
+    let devices = &mut *(data.0 as *mut Vec<String>);
+    let mut info = MONITORINFOEXW::default();
+    info.monitorInfo.cbSize = std::mem::size_of::<MONITORINFOEXW>() as u32;
+    if GetMonitorInfoW(monitor, &mut info as *mut MONITORINFOEXW as *mut MONITORINFO).as_bool() {
+        devices.push(from_wide(&info.szDevice));
+    }
+    TRUE
+}
+
+fn monitor_details(device: &str) -> (String, String) {
+    let device_wide = to_wide(device);
+    let mut dd = DISPLAY_DEVICEW { cb: std::mem::size_of::<DISPLAY_DEVICEW>() as u32, ..Default::default() };
+    unsafe {
+        if EnumDisplayDevicesW(PCWSTR(device_wide.as_ptr()), 0, &mut dd, 0).as_bool() {
+            return (from_wide(&dd.DeviceString), from_wide(&dd.DeviceID));
+        }
+    }
+    (device.to_string(), String::new())
+}
+
+pub fn read_ramp(display: &Display) -> Option<Ramp> {
+    with_dc(display, |hdc| unsafe {
+        let mut ramp: Ramp = [[0u16; 256]; 3];
+
+        // Dynamic load GetDeviceGammaRamp to avoid link errors
+        let lib = GetModuleHandleW(w!("gdi32.dll")).ok()?;
+        let proc = GetProcAddress(lib, PCSTR(b"GetDeviceGammaRamp\0".as_ptr() as _))?;
+        let func: unsafe extern "system" fn(HDC, *mut c_void) -> BOOL = std::mem::transmute(proc);
+        if func(hdc, &mut ramp as *mut _ as *mut c_void).as_bool() {
+            Some(ramp)
+        } else {
+            None
+        }
+    })
+}
+
+// The profile Windows associates with this display
+pub fn profile_path(display: &Display) -> Option<PathBuf> {
+    with_dc(display, |hdc| unsafe {
+        let mut buf = [0u16; 260];
+        let mut size = buf.len() as u32;
+        if !GetICMProfileW(hdc, &mut size, PWSTR(buf.as_mut_ptr())).as_bool() {
+            return None;
+        }
+        Some(PathBuf::from(from_wide(&buf)))
+    })
+}
+
+pub fn is_linear(ramp: &Ramp) -> bool {
+    let tolerance = 256;
+
+    for channel in ramp.iter() {
+        for (i, &actual) in channel.iter().enumerate() {
+            let expected = (i as u32 * 65535 / 255) as i32;
+            if (actual as i32 - expected).abs() > tolerance {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+pub fn classify(ramp: Option<&Ramp>) -> RampClass {
+    match ramp {
+        None => RampClass::Unreadable,
+        Some(r) if is_linear(r) => RampClass::Linear,
+        Some(_) => RampClass::Calibrated,
+    }
+}
+
+fn with_dc<T>(display: &Display, f: impl FnOnce(HDC) -> Option<T>) -> Option<T> {
+    let device_wide = to_wide(&display.device);
+    unsafe {
+        let hdc = CreateDCW(w!("DISPLAY"), PCWSTR(device_wide.as_ptr()), PCWSTR::null(), None);
+        if hdc.is_invalid() {
+            return None;
+        }
+        let result = f(hdc);
+        let _ = DeleteDC(hdc);
+        result
+    }
+}
+
+fn to_wide(s: &str) -> Vec<u16> {
+    s.encode_utf16().chain(std::iter::once(0)).collect()
+}
+
+fn from_wide(buf: &[u16]) -> String {
+    let len = buf.iter().position(|&c| c == 0).unwrap_or(buf.len());
+    String::from_utf16_lossy(&buf[..len])
+}
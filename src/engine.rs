@@ -0,0 +1,376 @@
+// --- Polling Engine ---
+//
+// The drift check shared by the tray timer and headless mode, plus the worker
+// thread that runs dispwin so the UI thread never waits on a child process.
+// What happened recently is kept in `STATE` for the status report.
+
+use std::collections::VecDeque;
+use std::os::windows::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{mpsc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::calibration;
+use crate::display::{self, RampClass};
+use crate::events::{self, Event};
+use crate::fullscreen::{self, Decision};
+use crate::hooks::{self, HookContext, HookEvent};
+use crate::icc;
+use crate::log;
+
+// How many failures the status report keeps
+const MAX_FAILURES: usize = 10;
+// Failed reloads in a row before drift stops triggering new attempts
+const MAX_CONSECUTIVE_FAILURES: u32 = 3;
+// Longest timed pause; requests asking for more are rejected when decoded
+pub const MAX_PAUSE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+#[derive(Debug, Clone)]
+pub struct DisplayState {
+    pub display: display::Display,
+    pub class: RampClass,
+    // The ramp as read back from the device
+    pub ramp: Option<display::Ramp>,
+    pub checked: SystemTime,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReloadRecord {
+    pub time: SystemTime,
+    pub ok: bool,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Failure {
+    pub time: SystemTime,
+    pub message: String,
+}
+
+pub struct EngineState {
+    pub displays: Vec<DisplayState>,
+    pub last_reload: Option<ReloadRecord>,
+    pub failures: VecDeque<Failure>,
+}
+
+static STATE: Mutex<EngineState> = Mutex::new(EngineState {
+    displays: Vec::new(),
+    last_reload: None,
+    failures: VecDeque::new(),
+});
+
+type Job = Box<dyn FnOnce() + Send>;
+
+static WORKER: Mutex<Option<mpsc::Sender<Job>>> = Mutex::new(None);
+// Set while a reload is queued or running, so drift on every tick does not
+// pile up dispwin processes
+static RELOAD_PENDING: AtomicBool = AtomicBool::new(false);
+// Reset by any successful reload and by manual reloads
+static CONSECUTIVE_FAILURES: AtomicU32 = AtomicU32::new(0);
+// Why drift is not reloading right now: (source, reason), e.g.
+// ("app_rules", "game.exe")
+static HOLDS: Mutex<Vec<(&str, String)>> = Mutex::new(Vec::new());
+// Set by "Pause for ..."; unlike switching polling off it is not saved
+static PAUSE: Mutex<Option<Pause>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Pause {
+    Until(Instant),
+    UntilRestart,
+}
+
+impl Pause {
+    // Time left, None when paused until restart
+    pub fn remaining(&self) -> Option<Duration> {
+        match self {
+            Pause::Until(deadline) => Some(deadline.saturating_duration_since(Instant::now())),
+            Pause::UntilRestart => None,
+        }
+    }
+}
+
+// Runs `job` on the worker thread, starting it on first use.
+pub fn run_on_worker<F: FnOnce() + Send + 'static>(job: F) {
+    let Ok(mut worker) = WORKER.lock() else { return };
+    let tx = worker.get_or_insert_with(|| {
+        let (tx, rx) = mpsc::channel::<Job>();
+        std::thread::spawn(move || {
+            for job in rx {
+                job();
+            }
+        });
+        tx
+    });
+    let _ = tx.send(Box::new(job));
+}
+
+// Copy of the current state for reporting.
+pub fn snapshot() -> (Vec<DisplayState>, Option<ReloadRecord>, Vec<Failure>) {
+    match STATE.lock() {
+        Ok(s) => (s.displays.clone(), s.last_reload.clone(), s.failures.iter().cloned().collect()),
+        Err(_) => (Vec::new(), None, Vec::new()),
+    }
+}
+
+pub fn record_failure(message: &str) {
+    log::error(message);
+    if let Ok(mut s) = STATE.lock() {
+        s.failures.push_back(Failure { time: SystemTime::now(), message: message.to_string() });
+        while s.failures.len() > MAX_FAILURES {
+            s.failures.pop_front();
+        }
+    }
+}
+
+// True once reloads kept failing; drift is then only logged until a manual
+// reload or a successful one.
+pub fn has_given_up() -> bool {
+    CONSECUTIVE_FAILURES.load(Ordering::SeqCst) >= MAX_CONSECUTIVE_FAILURES
+}
+
+// Keeps drift from triggering reloads until `source` calls again with None.
+// Ramps are still read for the status report, and explicit reloads still run.
+pub fn hold(source: &'static str, reason: Option<&str>) {
+    let Ok(mut holds) = HOLDS.lock() else { return };
+    let current = holds.iter().position(|(s, _)| *s == source);
+    if current.map(|i| holds[i].1.as_str()) == reason {
+        return;
+    }
+    if let Some(i) = current {
+        holds.remove(i);
+    }
+    match reason {
+        Some(r) => {
+            log::info(&format!("Drift reloads on hold: {}", r));
+            holds.push((source, r.to_string()));
+        }
+        None if holds.is_empty() => log::info("Drift reloads resumed"),
+        None => {}
+    }
+}
+
+// Everything holding drift reloads back, comma-separated.
+pub fn held_by() -> Option<String> {
+    let holds = HOLDS.lock().ok()?;
+    let reasons: Vec<&str> = holds.iter().map(|(_, r)| r.as_str()).collect();
+    if reasons.is_empty() { None } else { Some(reasons.join(", ")) }
+}
+
+// Stops drift checks for `duration` (at most `MAX_PAUSE`), or until the app
+// exits when None. The saved `PollingEnabled` is left as it is.
+pub fn pause_for(duration: Option<Duration>) {
+    let duration = duration.map(|d| d.min(MAX_PAUSE));
+    let pause = match duration.and_then(|d| Instant::now().checked_add(d)) {
+        Some(until) => Pause::Until(until),
+        None => Pause::UntilRestart,
+    };
+    if let Ok(mut current) = PAUSE.lock() {
+        *current = Some(pause);
+    }
+    match duration {
+        Some(d) => log::info(&format!("Auto-reload paused for {} min", d.as_secs().div_ceil(60))),
+        None => log::info("Auto-reload paused until restart"),
+    }
+    events::emit(Event::Paused(duration.map(|d| d.as_secs())));
+}
+
+// Ends a pause early. Returns whether there was one.
+pub fn end_pause() -> bool {
+    let ended = PAUSE.lock().ok().and_then(|mut p| p.take()).is_some();
+    if ended {
+        log::info("Auto-reload pause ended");
+        events::emit(Event::PauseEnded);
+    }
+    ended
+}
+
+// The running pause. One whose time is up ends here, so the next drift check
+// happens as soon as anything asks.
+pub fn paused() -> Option<Pause> {
+    let pause = PAUSE.lock().ok().and_then(|p| *p)?;
+    if pause.remaining().is_some_and(|left| left.is_zero()) {
+        end_pause();
+        return None;
+    }
+    Some(pause)
+}
+
+// Classifies every display's ramp and reloads if any of them has been reset
+// to linear. Does nothing while paused; see `fullscreen` for what happens
+// while a program runs fullscreen.
+pub fn poll_once() {
+    if paused().is_some() {
+        return;
+    }
+    match fullscreen::check(&mut fullscreen::ShellDetector) {
+        Decision::Skip => return,
+        // Only once no application rule holds reloads back; see `Tracker::update`
+        Decision::Reload => {
+            if !has_given_up() {
+                log::info("Fullscreen ended, reloading calibration");
+                start_reload();
+            }
+            return;
+        }
+        Decision::Poll | Decision::Hold => {}
+    }
+    let displays = check_displays();
+    let drifted: Vec<&DisplayState> = displays.iter().filter(|d| d.class == RampClass::Linear).collect();
+    if drifted.is_empty() || has_given_up() || held_by().is_some() {
+        return;
+    }
+
+    let names: Vec<&str> = drifted.iter().map(|d| d.display.device.as_str()).collect();
+    log::warn(&format!("Calibration lost on {}", names.join(", ")));
+    events::emit(Event::DriftDetected);
+    start_reload();
+
+    for d in drifted {
+        hooks::fire(
+            HookEvent::Drift,
+            HookContext {
+                display: d.display.device.clone(),
+                profile: profile_string(&d.display),
+                classification: d.class.name().to_string(),
+                error: String::new(),
+            },
+        );
+    }
+}
+
+// Reads and classifies all display ramps and stores the result.
+pub fn check_displays() -> Vec<DisplayState> {
+    let now = SystemTime::now();
+    let displays: Vec<DisplayState> = display::enumerate()
+        .into_iter()
+        .map(|d| {
+            let ramp = display::read_ramp(&d);
+            let class = display::classify(ramp.as_ref());
+            DisplayState { display: d, class, ramp, checked: now }
+        })
+        .collect();
+
+    if let Ok(mut s) = STATE.lock() {
+        s.displays = displays.clone();
+    }
+    displays
+}
+
+// Re-applies the installed calibration on every display via `dispwin -L`, or
+// the display's calibration file when it has one.
+// Explicit requests (tray, command line, control API) also clear a previous
+// give-up. Returns immediately; the result is recorded by the worker.
+pub fn reload_profile() {
+    CONSECUTIVE_FAILURES.store(0, Ordering::SeqCst);
+    start_reload();
+}
+
+fn start_reload() {
+    let Some(dispwin) = crate::find_dispwin() else {
+        record_failure("dispwin.exe not found, cannot reload calibration");
+        return;
+    };
+    if RELOAD_PENDING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    run_on_worker(move || {
+        let displays = display::enumerate();
+        let count = displays.len().max(1);
+
+        let mut errors = Vec::new();
+        for n in 1..=count {
+            let mut cmd = Command::new(&dispwin);
+            // dispwin defaults to the first display; address each one
+            // explicitly when there are several
+            if count > 1 {
+                cmd.arg("-d").arg(n.to_string());
+            }
+            // A calibration file replaces the profile's vcgt as the source
+            let target = displays.get(n - 1);
+            let source = match target.and_then(|d| Some((d, calibration::source_for(d)?))) {
+                Some((d, file)) => match calibration::dispwin_file(&file, d) {
+                    Ok(cal) => Some(cal),
+                    Err(e) => {
+                        errors.push(format!("Cannot load {} (display {}): {}", file.display(), n, e));
+                        continue;
+                    }
+                },
+                None => {
+                    if let Some(path) = target.and_then(display::profile_path) {
+                        warn_if_mhc2_only(&path);
+                    }
+                    None
+                }
+            };
+            match &source {
+                Some(file) => cmd.arg(file),
+                None => cmd.arg("-L"),
+            };
+            let what = source.as_ref().map_or("-L".to_string(), |f| f.display().to_string());
+            match cmd.creation_flags(0x08000000).status() {
+                Ok(s) if s.success() => {}
+                Ok(s) => errors.push(format!("dispwin {} exited with {} (display {})", what, s, n)),
+                Err(e) => errors.push(format!("Failed to start dispwin: {}", e)),
+            }
+        }
+
+        let ok = errors.is_empty();
+        let detail = if ok { format!("Reloaded calibration on {} display(s)", count) } else { errors.join("; ") };
+        if let Ok(mut s) = STATE.lock() {
+            s.last_reload = Some(ReloadRecord { time: SystemTime::now(), ok, detail: detail.clone() });
+        }
+        RELOAD_PENDING.store(false, Ordering::SeqCst);
+
+        let primary = display::enumerate().into_iter().next();
+        let mut context = HookContext {
+            display: primary.as_ref().map(|d| d.device.clone()).unwrap_or_default(),
+            profile: primary.as_ref().map(profile_string).unwrap_or_default(),
+            ..Default::default()
+        };
+
+        if ok {
+            CONSECUTIVE_FAILURES.store(0, Ordering::SeqCst);
+            log::info(&detail);
+            events::emit(Event::Reloaded);
+            hooks::fire(HookEvent::Reload, context);
+        } else {
+            record_failure(&detail);
+            events::emit(Event::ReloadFailed(detail.clone()));
+            if CONSECUTIVE_FAILURES.fetch_add(1, Ordering::SeqCst) + 1 == MAX_CONSECUTIVE_FAILURES {
+                log::error(&format!("Giving up after {} failed reloads", MAX_CONSECUTIVE_FAILURES));
+                events::emit(Event::GaveUp);
+                context.error = detail;
+                hooks::fire(HookEvent::Failure, context);
+            }
+        }
+    });
+}
+
+// `dispwin -L` only loads the `vcgt`, so a profile calibrated through MHC2
+// gains nothing from the reload. Logged once per profile file; returns
+// whether `path` is such a profile.
+pub fn warn_if_mhc2_only(path: &Path) -> bool {
+    static WARNED: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+    if !icc::inspect(path).is_ok_and(|info| info.relies_on_mhc2) {
+        return false;
+    }
+    if let Ok(mut warned) = WARNED.lock() {
+        if !warned.iter().any(|p| p == path) {
+            log::warn(&mhc2_warning(path));
+            warned.push(path.to_path_buf());
+        }
+    }
+    true
+}
+
+// What `warn_if_mhc2_only` logs; `status` shows the same line.
+pub fn mhc2_warning(path: &Path) -> String {
+    format!("{} keeps its calibration in MHC2, not vcgt; reloads will not restore it", path.display())
+}
+
+fn profile_string(d: &display::Display) -> String {
+    display::profile_path(d).map(|p| p.to_string_lossy().to_string()).unwrap_or_default()
+}
@@ -0,0 +1,68 @@
+// --- Event Bus ---
+//
+// Things worth telling the outside world about. Producers call `emit`, any
+// number of consumers (e.g. control API subscriptions) hold a receiver from
+// `subscribe`. Receivers that were dropped are pruned on the next emit.
+
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    // The polling check found the calibration missing
+    DriftDetected,
+    // The current calibration was re-applied
+    Reloaded,
+    // dispwin could not re-apply the calibration
+    ReloadFailed(String),
+    // Reloads kept failing; drift no longer triggers new attempts
+    GaveUp,
+    // Auto-reload was switched on or off
+    PollingChanged(bool),
+    // Auto-reload was paused for a number of seconds, or until restart (None)
+    Paused(Option<u64>),
+    // A pause ran out or was ended early
+    PauseEnded,
+    // A new profile was installed and loaded
+    ProfileInstalled(String),
+    // A preset was applied; carries its name
+    PresetApplied(String),
+    // A preset or library entry could not be applied; carries the error
+    ApplyFailed(String),
+    // A display's profile is older than its re-calibration limit
+    ProfileOverdue { display: String, age_days: u32 },
+}
+
+impl Event {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Event::DriftDetected => "drift_detected",
+            Event::Reloaded => "reloaded",
+            Event::ReloadFailed(_) => "reload_failed",
+            Event::GaveUp => "gave_up",
+            Event::PollingChanged(_) => "polling_changed",
+            Event::Paused(_) => "paused",
+            Event::PauseEnded => "pause_ended",
+            Event::ProfileInstalled(_) => "profile_installed",
+            Event::PresetApplied(_) => "preset_applied",
+            Event::ApplyFailed(_) => "apply_failed",
+            Event::ProfileOverdue { .. } => "profile_overdue",
+        }
+    }
+}
+
+static SUBSCRIBERS: Mutex<Vec<Sender<Event>>> = Mutex::new(Vec::new());
+
+pub fn subscribe() -> Receiver<Event> {
+    let (tx, rx) = mpsc::channel();
+    if let Ok(mut subs) = SUBSCRIBERS.lock() {
+        subs.push(tx);
+    }
+    rx
+}
+
+pub fn emit(event: Event) {
+    if let Ok(mut subs) = SUBSCRIBERS.lock() {
+        subs.retain(|tx| tx.send(event.clone()).is_ok());
+    }
+}
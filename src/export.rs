@@ -0,0 +1,118 @@
+// --- Ramp Export ---
+//
+// Writes a ramp, either read back from a display or the calibration it is
+// expected to have, in formats other tools understand:
+//
+//   .cal    ArgyllCMS calibration (RGB_I/RGB_R/RGB_G/RGB_B), loadable by dispwin
+//   .cube   1D LUT as used by Resolve, OBS and ffmpeg's lut1d filter
+//   .csv    input,red,green,blue with one row per entry
+//
+// All values are normalized to 0..1. The 256-entry ramp can be resampled to
+// 1024 or 4096 entries for tools that expect finer tables.
+
+use std::path::Path;
+
+use crate::calibration;
+use crate::dates;
+use crate::display::{self, Ramp};
+use crate::icc::Profile;
+use crate::ramp;
+
+// Entry counts offered for export; the first is the native ramp size
+pub const SIZES: [usize; 3] = [256, 1024, 4096];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RampSource {
+    // Read back from the video card
+    Live,
+    // What the display's calibration source should have loaded
+    Expected,
+    // A profile's `vcgt` or a calibration file
+    File(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Cal,
+    Cube,
+    Csv,
+}
+
+impl Format {
+    pub fn from_path(path: &Path) -> Option<Format> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "cal" => Some(Format::Cal),
+            "cube" => Some(Format::Cube),
+            "csv" => Some(Format::Csv),
+            _ => None,
+        }
+    }
+}
+
+// The ramp `source` describes; `display_number` is 1-based like dispwin's.
+pub fn source_ramp(source: &RampSource, display_number: usize) -> Result<Ramp, String> {
+    let display = || {
+        display::enumerate()
+            .into_iter()
+            .nth(display_number.saturating_sub(1))
+            .ok_or_else(|| format!("display {} not found", display_number))
+    };
+    match source {
+        RampSource::Live => display::read_ramp(&display()?).ok_or_else(|| "the display's ramp could not be read".to_string()),
+        RampSource::Expected => calibration::expected_ramp(&display()?),
+        RampSource::File(path) => file_ramp(Path::new(path)),
+    }
+}
+
+// Curves of a calibration file, or the `vcgt` of a profile.
+pub fn file_ramp(path: &Path) -> Result<Ramp, String> {
+    if calibration::is_calibration_file(path) {
+        return calibration::load(path);
+    }
+    Profile::load(path)?.vcgt().ok_or_else(|| format!("{} has no usable vcgt tag", path.display()))
+}
+
+pub fn save(path: &Path, ramp: &Ramp, size: usize) -> Result<(), String> {
+    let format = Format::from_path(path).ok_or_else(|| format!("{}: expected a .cal, .cube or .csv file", path.display()))?;
+    std::fs::write(path, render(ramp, format, size)).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+pub fn render(ramp: &Ramp, format: Format, size: usize) -> String {
+    let curves: Vec<Vec<f64>> =
+        ramp.iter().map(|channel| ramp::resample(&channel.map(|v| v as f64 / 65535.0), size)).collect();
+    // input, red, green, blue
+    let rows: Vec<[f64; 4]> = (0..size).map(|i| [i as f64 / (size - 1) as f64, curves[0][i], curves[1][i], curves[2][i]]).collect();
+    let mut out = String::new();
+
+    match format {
+        Format::Cal => {
+            out.push_str("CAL    \n\n");
+            out.push_str("DESCRIPTOR \"Argyll Device Calibration State\"\n");
+            out.push_str(&format!("ORIGINATOR \"Icc Auto Reloader {}\"\n", env!("CARGO_PKG_VERSION")));
+            out.push_str(&format!("CREATED \"{}\"\n", dates::format_iso8601(std::time::SystemTime::now())));
+            out.push_str("KEYWORD \"DEVICE_CLASS\"\nDEVICE_CLASS \"DISPLAY\"\n");
+            out.push_str("KEYWORD \"COLOR_REP\"\nCOLOR_REP \"RGB\"\n\n");
+            out.push_str("NUMBER_OF_FIELDS 4\nBEGIN_DATA_FORMAT\nRGB_I RGB_R RGB_G RGB_B\nEND_DATA_FORMAT\n\n");
+            out.push_str(&format!("NUMBER_OF_SETS {}\nBEGIN_DATA\n", size));
+            for [x, r, g, b] in &rows {
+                out.push_str(&format!("{:.7} {:.7} {:.7} {:.7}\n", x, r, g, b));
+            }
+            out.push_str("END_DATA\n");
+        }
+        Format::Cube => {
+            out.push_str("TITLE \"Icc Auto Reloader calibration\"\n");
+            out.push_str(&format!("LUT_1D_SIZE {}\n", size));
+            out.push_str("DOMAIN_MIN 0.0 0.0 0.0\nDOMAIN_MAX 1.0 1.0 1.0\n\n");
+            for [_, r, g, b] in &rows {
+                out.push_str(&format!("{:.7} {:.7} {:.7}\n", r, g, b));
+            }
+        }
+        Format::Csv => {
+            out.push_str("input,red,green,blue\n");
+            for [x, r, g, b] in &rows {
+                out.push_str(&format!("{:.7},{:.7},{:.7},{:.7}\n", x, r, g, b));
+            }
+        }
+    }
+    out
+}
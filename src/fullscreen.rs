@@ -0,0 +1,248 @@
+// --- Fullscreen Detection ---
+//
+// Loading the ramp while a fullscreen game or video player owns the display
+// flickers and fights with the program. The drift check asks a
+// `FullscreenDetector` first and follows the policy stored under
+// `HKCU\Software\IccAutoReloader` as
+//
+//   FullscreenPolicy    REG_DWORD, 0 = keep going (default),
+//                       1 = suspend polling while fullscreen,
+//                       2 = no reloads while fullscreen, one reload after it ends
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Mutex;
+
+use windows::Win32::UI::Shell::{
+    SHQueryUserNotificationState, QUNS_BUSY, QUNS_PRESENTATION_MODE, QUNS_RUNNING_D3D_FULL_SCREEN,
+};
+
+use crate::engine;
+use crate::log;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Policy {
+    Keep,
+    Suspend,
+    ReloadOnExit,
+}
+
+impl Policy {
+    // In the order of the registry values and the Settings combo box
+    pub const ALL: [Policy; 3] = [Policy::Keep, Policy::Suspend, Policy::ReloadOnExit];
+
+    pub fn from_dword(value: u32) -> Policy {
+        Policy::ALL.get(value as usize).copied().unwrap_or(Policy::Keep)
+    }
+
+    pub fn to_dword(self) -> u32 {
+        Policy::ALL.iter().position(|&p| p == self).unwrap_or(0) as u32
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Policy::Keep => "keep",
+            Policy::Suspend => "suspend",
+            Policy::ReloadOnExit => "reload_on_exit",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum State {
+    Windowed,
+    // A program covers the screen
+    Fullscreen,
+    // Direct3D exclusive mode
+    Exclusive,
+    // Windows presentation settings are on
+    Presentation,
+}
+
+impl State {
+    pub fn name(self) -> &'static str {
+        match self {
+            State::Windowed => "windowed",
+            State::Fullscreen => "fullscreen",
+            State::Exclusive => "exclusive fullscreen",
+            State::Presentation => "presentation mode",
+        }
+    }
+}
+
+// Where the fullscreen state comes from; tests use a scripted one.
+pub trait FullscreenDetector {
+    fn state(&mut self) -> State;
+}
+
+// Asks the shell, which also decides when to hold back notifications.
+pub struct ShellDetector;
+
+impl FullscreenDetector for ShellDetector {
+    fn state(&mut self) -> State {
+        match unsafe { SHQueryUserNotificationState() } {
+            Ok(QUNS_RUNNING_D3D_FULL_SCREEN) => State::Exclusive,
+            Ok(QUNS_PRESENTATION_MODE) => State::Presentation,
+            // QUNS_APP only says a Store app is in front, not that it covers the screen
+            Ok(QUNS_BUSY) => State::Fullscreen,
+            _ => State::Windowed,
+        }
+    }
+}
+
+// What the drift check does this time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Decision {
+    Poll,
+    // Leave the display alone: no ramp reads, no reloads
+    Skip,
+    // Read the ramps but do not reload
+    Hold,
+    // Fullscreen ended: reload whether or not the ramp looks reset
+    Reload,
+}
+
+#[derive(Debug, Default)]
+pub struct Tracker {
+    state: Option<State>,
+    // Fullscreen ended while something else held reloads back
+    owed: bool,
+}
+
+impl Tracker {
+    // `held`: whether anything besides fullscreen holds drift reloads back.
+    // The reload after fullscreen then waits until that hold is released.
+    pub fn update(&mut self, state: State, policy: Policy, held: bool) -> Decision {
+        let was_fullscreen = self.state.is_some_and(|s| s != State::Windowed);
+        self.state = Some(state);
+        match (state != State::Windowed, policy) {
+            (true, Policy::Suspend) => Decision::Skip,
+            (true, Policy::ReloadOnExit) => Decision::Hold,
+            (false, Policy::ReloadOnExit) if was_fullscreen || self.owed => {
+                self.owed = held;
+                if held { Decision::Poll } else { Decision::Reload }
+            }
+            _ => {
+                self.owed = false;
+                Decision::Poll
+            }
+        }
+    }
+
+    pub fn state(&self) -> Option<State> {
+        self.state
+    }
+}
+
+static POLICY: AtomicU32 = AtomicU32::new(0);
+static TRACKER: Mutex<Tracker> = Mutex::new(Tracker { state: None, owed: false });
+
+pub fn policy() -> Policy {
+    Policy::from_dword(POLICY.load(Ordering::Relaxed))
+}
+
+pub fn set_policy(policy: Policy) {
+    POLICY.store(policy.to_dword(), Ordering::Relaxed);
+}
+
+// The state seen by the last drift check.
+pub fn state() -> Option<State> {
+    TRACKER.lock().ok().and_then(|t| t.state())
+}
+
+// Called by the drift check before it reads any ramp, with `ShellDetector`.
+pub fn check(detector: &mut dyn FullscreenDetector) -> Decision {
+    let state = detector.state();
+    let Ok(mut tracker) = TRACKER.lock() else { return Decision::Poll };
+    if tracker.state().is_some_and(|s| s != state) {
+        log::info(&format!("Display is now {}", state.name()));
+    }
+    if state == State::Windowed {
+        // Our own hold must not count as someone else's
+        engine::hold("fullscreen", None);
+    }
+    let decision = tracker.update(state, policy(), engine::held_by().is_some());
+    match decision {
+        Decision::Skip | Decision::Hold => engine::hold("fullscreen", Some(state.name())),
+        Decision::Poll | Decision::Reload => engine::hold("fullscreen", None),
+    }
+    decision
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Plays back a list of states, one per check
+    struct Script(Vec<State>);
+
+    impl FullscreenDetector for Script {
+        fn state(&mut self) -> State {
+            self.0.remove(0)
+        }
+    }
+
+    fn run(policy: Policy, states: &[State]) -> Vec<Decision> {
+        let mut detector = Script(states.to_vec());
+        let mut tracker = Tracker::default();
+        states.iter().map(|_| tracker.update(detector.state(), policy, false)).collect()
+    }
+
+    const GAME: [State; 4] = [State::Windowed, State::Exclusive, State::Exclusive, State::Windowed];
+
+    #[test]
+    fn keep_going_always_polls() {
+        assert_eq!(run(Policy::Keep, &GAME), vec![Decision::Poll; 4]);
+    }
+
+    #[test]
+    fn suspend_skips_while_fullscreen() {
+        assert_eq!(run(Policy::Suspend, &GAME), vec![Decision::Poll, Decision::Skip, Decision::Skip, Decision::Poll]);
+    }
+
+    #[test]
+    fn reload_on_exit_reloads_once() {
+        let states = [GAME.as_slice(), &[State::Windowed]].concat();
+        assert_eq!(
+            run(Policy::ReloadOnExit, &states),
+            vec![Decision::Poll, Decision::Hold, Decision::Hold, Decision::Reload, Decision::Poll]
+        );
+    }
+
+    #[test]
+    fn switching_fullscreen_kinds_is_not_an_exit() {
+        let states = [State::Fullscreen, State::Presentation, State::Windowed];
+        assert_eq!(run(Policy::ReloadOnExit, &states), vec![Decision::Hold, Decision::Hold, Decision::Reload]);
+    }
+
+    #[test]
+    fn reload_waits_for_other_holds() {
+        let mut tracker = Tracker::default();
+        assert_eq!(tracker.update(State::Exclusive, Policy::ReloadOnExit, true), Decision::Hold);
+        // An application rule still suspends reloads when the game leaves fullscreen
+        assert_eq!(tracker.update(State::Windowed, Policy::ReloadOnExit, true), Decision::Poll);
+        assert_eq!(tracker.update(State::Windowed, Policy::ReloadOnExit, true), Decision::Poll);
+        assert_eq!(tracker.update(State::Windowed, Policy::ReloadOnExit, false), Decision::Reload);
+        assert_eq!(tracker.update(State::Windowed, Policy::ReloadOnExit, false), Decision::Poll);
+    }
+
+    #[test]
+    fn check_follows_the_detector() {
+        set_policy(Policy::Suspend);
+        let mut detector = Script(vec![State::Windowed, State::Presentation, State::Windowed]);
+        assert_eq!(check(&mut detector), Decision::Poll);
+        assert_eq!(check(&mut detector), Decision::Skip);
+        assert_eq!(state(), Some(State::Presentation));
+        assert_eq!(engine::held_by().as_deref(), Some("presentation mode"));
+        assert_eq!(check(&mut detector), Decision::Poll);
+        assert_eq!(engine::held_by(), None);
+        set_policy(Policy::Keep);
+    }
+
+    #[test]
+    fn policy_round_trips_through_the_registry_value() {
+        for policy in Policy::ALL {
+            assert_eq!(Policy::from_dword(policy.to_dword()), policy);
+        }
+        assert_eq!(Policy::from_dword(7), Policy::Keep);
+    }
+}
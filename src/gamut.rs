@@ -0,0 +1,189 @@
+// --- Gamut Analysis ---
+//
+// Compares a matrix profile's primaries with the common RGB spaces. The
+// primaries are first brought back from the PCS (D50) to the display's own
+// white: through the inverse of `chad` when present, otherwise by Bradford
+// adaptation to `wtpt` for v2 profiles that store the measured white there.
+//
+// Coverage is the share of the reference triangle inside the profile's
+// triangle; relative area is the profile's triangle area over the
+// reference's, and can exceed 100%. Both are given in CIE xy and u'v'.
+
+use crate::color::{self, round, Matrix3, D50};
+use crate::icc::{Profile, Xyz};
+use crate::json::Value;
+
+pub type Point = (f64, f64);
+
+pub struct Reference {
+    pub name: &'static str,
+    pub key: &'static str,
+    pub primaries: [Point; 3],
+}
+
+pub const REFERENCES: [Reference; 4] = [
+    Reference { name: "sRGB", key: "srgb", primaries: [(0.640, 0.330), (0.300, 0.600), (0.150, 0.060)] },
+    Reference { name: "Display P3", key: "p3", primaries: [(0.680, 0.320), (0.265, 0.690), (0.150, 0.060)] },
+    Reference { name: "Adobe RGB", key: "adobe_rgb", primaries: [(0.640, 0.330), (0.210, 0.710), (0.150, 0.060)] },
+    Reference { name: "Rec.2020", key: "rec2020", primaries: [(0.708, 0.292), (0.170, 0.797), (0.131, 0.046)] },
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Comparison {
+    pub coverage: f64,
+    pub relative_area: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GamutReport {
+    // Display white and primaries in xy, after undoing the PCS adaptation
+    pub white: Point,
+    pub primaries: [Point; 3],
+    pub area_xy: f64,
+    pub area_uv: f64,
+    // One per entry of REFERENCES, in the same order: (xy, u'v')
+    pub comparisons: Vec<(Comparison, Comparison)>,
+}
+
+impl GamutReport {
+    pub fn to_json(&self) -> Value {
+        let point = |p: Point| {
+            let (u, v) = color::xy_to_uv(p);
+            Value::object(vec![
+                ("x", Value::from(round(p.0, 4))),
+                ("y", Value::from(round(p.1, 4))),
+                ("u", Value::from(round(u, 4))),
+                ("v", Value::from(round(v, 4))),
+            ])
+        };
+        let comparison = |c: &Comparison| {
+            Value::object(vec![
+                ("coverage", Value::from(round(c.coverage * 100.0, 1))),
+                ("relative_area", Value::from(round(c.relative_area * 100.0, 1))),
+            ])
+        };
+        let references = REFERENCES
+            .iter()
+            .zip(&self.comparisons)
+            .map(|(r, (xy, uv))| (r.key, Value::object(vec![("xy", comparison(xy)), ("uv", comparison(uv))])))
+            .collect();
+
+        Value::object(vec![
+            ("white", point(self.white)),
+            (
+                "primaries",
+                Value::object(vec![
+                    ("red", point(self.primaries[0])),
+                    ("green", point(self.primaries[1])),
+                    ("blue", point(self.primaries[2])),
+                ]),
+            ),
+            ("area_xy", Value::from(round(self.area_xy, 5))),
+            ("area_uv", Value::from(round(self.area_uv, 5))),
+            ("references", Value::object(references)),
+        ])
+    }
+}
+
+// None for profiles without rXYZ/gXYZ/bXYZ (LUT-only or non-RGB).
+pub fn analyze(profile: &Profile) -> Option<GamutReport> {
+    let stored = [profile.xyz(b"rXYZ")?, profile.xyz(b"gXYZ")?, profile.xyz(b"bXYZ")?];
+    let to_display = to_display_white(profile);
+
+    let primaries = stored.map(|p| color::mul_vec(&to_display, &p).chromaticity());
+    let white_pcs = Xyz { x: stored.iter().map(|p| p.x).sum(), y: stored.iter().map(|p| p.y).sum(), z: stored.iter().map(|p| p.z).sum() };
+    let white = color::mul_vec(&to_display, &white_pcs).chromaticity();
+
+    let primaries_uv = primaries.map(color::xy_to_uv);
+    let comparisons = REFERENCES
+        .iter()
+        .map(|r| (compare(&primaries, &r.primaries), compare(&primaries_uv, &r.primaries.map(color::xy_to_uv))))
+        .collect();
+
+    Some(GamutReport {
+        white,
+        primaries,
+        area_xy: area(&primaries),
+        area_uv: area(&primaries_uv),
+        comparisons,
+    })
+}
+
+// Matrix taking PCS-relative XYZ back to the display's adapted white.
+fn to_display_white(profile: &Profile) -> Matrix3 {
+    const IDENTITY: Matrix3 = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+    if let Some(inverse) = profile.matrix(b"chad").and_then(|m| color::invert(&m)) {
+        return inverse;
+    }
+    match profile.xyz(b"wtpt") {
+        Some(w) if profile.version().0 < 4 && (w.x - D50.x).abs() + (w.z - D50.z).abs() > 0.002 => {
+            color::bradford(&D50, &Xyz { x: w.x / w.y, y: 1.0, z: w.z / w.y })
+        }
+        _ => IDENTITY,
+    }
+}
+
+fn compare(profile: &[Point; 3], reference: &[Point; 3]) -> Comparison {
+    let reference_area = area(reference);
+    if reference_area <= 0.0 {
+        return Comparison { coverage: 0.0, relative_area: 0.0 };
+    }
+    let overlap = area(&clip(profile, reference));
+    Comparison { coverage: overlap / reference_area, relative_area: area(profile) / reference_area }
+}
+
+fn area(points: &[Point]) -> f64 {
+    signed_area(points).abs()
+}
+
+fn signed_area(points: &[Point]) -> f64 {
+    let n = points.len();
+    let twice: f64 = (0..n)
+        .map(|i| {
+            let (a, b) = (points[i], points[(i + 1) % n]);
+            a.0 * b.1 - b.0 * a.1
+        })
+        .sum();
+    twice / 2.0
+}
+
+// Sutherland-Hodgman: the part of triangle `subject` inside triangle `clip`.
+fn clip(subject: &[Point; 3], clip: &[Point; 3]) -> Vec<Point> {
+    let mut window = clip.to_vec();
+    if signed_area(&window) < 0.0 {
+        window.reverse();
+    }
+    let inside = |p: Point, a: Point, b: Point| (b.0 - a.0) * (p.1 - a.1) - (b.1 - a.1) * (p.0 - a.0) >= 0.0;
+    let intersect = |p: Point, q: Point, a: Point, b: Point| {
+        let (dx, dy) = (q.0 - p.0, q.1 - p.1);
+        let (ex, ey) = (b.0 - a.0, b.1 - a.1);
+        let denom = dx * ey - dy * ex;
+        if denom.abs() < 1e-15 {
+            return p;
+        }
+        let t = ((a.0 - p.0) * ey - (a.1 - p.1) * ex) / denom;
+        (p.0 + t * dx, p.1 + t * dy)
+    };
+
+    let mut output = subject.to_vec();
+    for i in 0..window.len() {
+        let (a, b) = (window[i], window[(i + 1) % window.len()]);
+        let input = std::mem::take(&mut output);
+        for j in 0..input.len() {
+            let (p, q) = (input[j], input[(j + 1) % input.len()]);
+            match (inside(p, a, b), inside(q, a, b)) {
+                (true, true) => output.push(q),
+                (true, false) => output.push(intersect(p, q, a, b)),
+                (false, true) => {
+                    output.push(intersect(p, q, a, b));
+                    output.push(q);
+                }
+                (false, false) => {}
+            }
+        }
+        if output.is_empty() {
+            break;
+        }
+    }
+    output
+}
@@ -0,0 +1,155 @@
+// --- Headless Mode ---
+//
+// `icc_auto_reloader.exe --headless [--interval <seconds>]` runs the polling
+// engine, logging, the single-instance pipe and the control API without
+// creating any windows. Configuration comes from the registry, with command
+// line overrides. Schedule and application rules are followed like in the
+// tray. It stops on Ctrl+C / console close or on a QUIT request.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+use std::thread::{self, Thread};
+use std::time::{Duration, Instant};
+
+use windows::Win32::Foundation::{BOOL, TRUE};
+use windows::Win32::System::Console::SetConsoleCtrlHandler;
+
+use crate::age;
+use crate::engine;
+use crate::i18n::Msg;
+use crate::ipc::{self, Request, Response};
+use crate::log;
+use crate::rpc;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HeadlessOptions {
+    pub interval_ms: Option<u32>,
+}
+
+// Same as the tray's schedule and application rule timers
+const SCHEDULE_CHECK: Duration = Duration::from_secs(30);
+const APP_RULES_CHECK: Duration = Duration::from_secs(1);
+
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+static SHUTDOWN: AtomicBool = AtomicBool::new(false);
+static ENGINE_THREAD: OnceLock<Thread> = OnceLock::new();
+
+pub fn is_active() -> bool {
+    ACTIVE.load(Ordering::Relaxed)
+}
+
+pub fn run(options: HeadlessOptions) {
+    ACTIVE.store(true, Ordering::Relaxed);
+    if let Some(ms) = options.interval_ms {
+        crate::POLLING_INTERVAL_MS.store(ms, Ordering::Relaxed);
+    }
+
+    let _ = ENGINE_THREAD.set(thread::current());
+    unsafe {
+        let _ = SetConsoleCtrlHandler(Some(on_console_ctrl), TRUE);
+    }
+
+    ipc::set_waker(wake);
+    ipc::start_server();
+    rpc::start();
+
+    let interval = crate::POLLING_INTERVAL_MS.load(Ordering::Relaxed);
+    log::info(&format!("Headless mode started (polling every {} ms)", interval));
+    engine::reload_profile();
+
+    let mut next_poll = Instant::now();
+    let mut next_age_check = Instant::now();
+    let mut next_schedule_check = Instant::now();
+    let mut next_app_check = Instant::now();
+    while !SHUTDOWN.load(Ordering::SeqCst) {
+        process_requests();
+
+        let now = Instant::now();
+        if now >= next_age_check {
+            // Logged and published as events; there is nobody to show a balloon to
+            age::newly_overdue(&age::check_all());
+            next_age_check = now + Duration::from_secs(3600);
+        }
+        if now >= next_schedule_check {
+            crate::run_schedule();
+            next_schedule_check = now + SCHEDULE_CHECK;
+        }
+        if now >= next_app_check {
+            crate::run_app_rules();
+            next_app_check = now + APP_RULES_CHECK;
+        }
+        if now >= next_poll {
+            if crate::is_polling_enabled() {
+                engine::poll_once();
+            }
+            let interval = crate::POLLING_INTERVAL_MS.load(Ordering::Relaxed);
+            next_poll = now + Duration::from_millis(interval as u64);
+        }
+        // Check for drift as soon as a timed pause runs out
+        if let Some(engine::Pause::Until(deadline)) = engine::paused() {
+            next_poll = next_poll.min(deadline);
+        }
+
+        // Woken early by IPC requests and shutdown signals
+        let wake_at = next_poll.min(next_schedule_check).min(next_app_check);
+        thread::park_timeout(wake_at.saturating_duration_since(Instant::now()));
+    }
+
+    log::info("Headless mode stopped");
+    ipc::wait_idle();
+}
+
+fn process_requests() {
+    for pending in ipc::take_pending() {
+        let Err(pending) = crate::answer_on_worker(pending) else { continue };
+        let response = match &pending.request {
+            Request::Reload => {
+                engine::reload_profile();
+                Response::Ok(crate::get_str(Msg::MsgReloadRequested))
+            }
+            Request::Install(_) | Request::Assign { .. } | Request::ApplyPreset(_) | Request::Status => {
+                unreachable!("answered on the worker")
+            }
+            Request::Pause => {
+                crate::set_polling_enabled(false);
+                Response::Ok(crate::get_str(Msg::MsgPaused))
+            }
+            Request::PauseFor(seconds) => {
+                let duration = seconds.map(Duration::from_secs);
+                engine::pause_for(duration);
+                Response::Ok(crate::pause_message(duration))
+            }
+            Request::Resume => {
+                // Ending a pause leaves the saved polling setting alone
+                if engine::end_pause() {
+                    engine::poll_once();
+                } else {
+                    crate::set_polling_enabled(true);
+                }
+                Response::Ok(crate::get_str(Msg::MsgResumed))
+            }
+            Request::OpenSettings => Response::Error(crate::get_str(Msg::MsgHeadlessNoUi)),
+            Request::Quit => {
+                SHUTDOWN.store(true, Ordering::SeqCst);
+                Response::Ok(crate::get_str(Msg::MsgQuitting))
+            }
+        };
+        pending.respond(response);
+    }
+}
+
+fn wake() {
+    if let Some(t) = ENGINE_THREAD.get() {
+        t.unpark();
+    }
+}
+
+// Runs on a thread created by the system for Ctrl+C, Ctrl+Break, console
+// close, logoff and shutdown.
+unsafe extern "system" fn on_console_ctrl(_ctrl_type: u32) -> BOOL {
+    SHUTDOWN.store(true, Ordering::SeqCst);
+    wake();
+    // Give the loop a moment to finish; the process may be killed after we return
+    thread::sleep(Duration::from_millis(500));
+    TRUE
+}
@@ -0,0 +1,216 @@
+// --- Hook Commands ---
+//
+// User commands run when drift is detected, a reload completes, or the engine
+// gives up after repeated failures. They are configured as REG_SZ values under
+// `HKCU\Software\IccAutoReloader`:
+//
+//   HookOnDrift, HookOnReload, HookOnFailure   command line, run via `cmd /C`
+//   HookTimeout                                DWORD seconds (default 30)
+//
+// The values are passed as the environment variables ICCAR_EVENT,
+// ICCAR_DISPLAY, ICCAR_PROFILE, ICCAR_CLASSIFICATION and ICCAR_ERROR.
+// Placeholders {event}, {display}, {profile}, {classification} and {error}
+// become `"!ICCAR_...!"` references to them, expanded by cmd with delayed
+// expansion: that happens after the line is parsed, so `%`, `"`, `&`, `|` and
+// the like in a value are passed on as text instead of being interpreted.
+// The value itself never appears in the command line. The flip side is that a
+// literal `!` in the command has to be written as `^^!`.
+//
+// Each hook runs on a thread of its own, so a slow one holds up neither the UI
+// nor reloads; output is captured into the log.
+
+use std::io::Read;
+use std::os::windows::process::CommandExt;
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use windows::core::{w, PCWSTR};
+
+use crate::log;
+
+const DEFAULT_TIMEOUT_SECS: u32 = 30;
+// Captured output beyond this is dropped from the log
+const MAX_LOGGED_OUTPUT: usize = 2000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HookEvent {
+    Drift,
+    Reload,
+    Failure,
+}
+
+impl HookEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            HookEvent::Drift => "drift",
+            HookEvent::Reload => "reload",
+            HookEvent::Failure => "failure",
+        }
+    }
+
+    fn setting(&self) -> PCWSTR {
+        match self {
+            HookEvent::Drift => w!("HookOnDrift"),
+            HookEvent::Reload => w!("HookOnReload"),
+            HookEvent::Failure => w!("HookOnFailure"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct HookContext {
+    pub display: String,
+    pub profile: String,
+    pub classification: String,
+    pub error: String,
+}
+
+// Queues the hook for `event` if one is configured. Cheap enough for the
+// timer path: one registry read, no process is started here.
+pub fn fire(event: HookEvent, context: HookContext) {
+    let Some(template) = crate::get_reg_string(event.setting()) else { return };
+    if template.trim().is_empty() {
+        return;
+    }
+    let timeout = crate::get_reg_dword(w!("HookTimeout")).filter(|&s| s > 0).unwrap_or(DEFAULT_TIMEOUT_SECS);
+
+    std::thread::spawn(move || {
+        let command = substitute(&template);
+        run(&command, event, &context, Duration::from_secs(timeout as u64));
+    });
+}
+
+// Replaces each placeholder with a quoted delayed-expansion reference to its
+// environment variable.
+pub fn substitute(template: &str) -> String {
+    let values = [
+        ("{event}", "ICCAR_EVENT"),
+        ("{display}", "ICCAR_DISPLAY"),
+        ("{profile}", "ICCAR_PROFILE"),
+        ("{classification}", "ICCAR_CLASSIFICATION"),
+        ("{error}", "ICCAR_ERROR"),
+    ];
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        match values.iter().find(|(placeholder, _)| rest.starts_with(placeholder)) {
+            Some((placeholder, variable)) => {
+                rest = &rest[placeholder.len()..];
+                // `"{profile}"` is quoted by hand already
+                if out.ends_with('"') && rest.starts_with('"') {
+                    out.pop();
+                    rest = &rest[1..];
+                }
+                // Quoted so spaces in the value do not split the argument
+                out.push_str(&format!("\"!{}!\"", variable));
+            }
+            None => {
+                out.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn run(command: &str, event: HookEvent, context: &HookContext, timeout: Duration) {
+    let child = Command::new("cmd")
+        .raw_arg(format!("/V:ON /S /C \"{}\"", command))
+        .env("ICCAR_EVENT", event.name())
+        .env("ICCAR_DISPLAY", &context.display)
+        .env("ICCAR_PROFILE", &context.profile)
+        .env("ICCAR_CLASSIFICATION", &context.classification)
+        .env("ICCAR_ERROR", &context.error)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .creation_flags(0x08000000)
+        .spawn();
+
+    let mut child = match child {
+        Ok(c) => c,
+        Err(e) => {
+            log::error(&format!("Hook '{}' could not be started: {}", event.name(), e));
+            return;
+        }
+    };
+
+    // Drain both pipes on their own threads so a chatty hook cannot block on a
+    // full pipe while we wait for it
+    let (tx, rx) = mpsc::channel::<Vec<u8>>();
+    let pipes: Vec<Box<dyn Read + Send>> = [
+        child.stdout.take().map(|p| Box::new(p) as Box<dyn Read + Send>),
+        child.stderr.take().map(|p| Box::new(p) as Box<dyn Read + Send>),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    let pipe_count = pipes.len();
+    for mut pipe in pipes {
+        let tx = tx.clone();
+        std::thread::spawn(move || {
+            let mut buf = Vec::new();
+            let _ = pipe.read_to_end(&mut buf);
+            let _ = tx.send(buf);
+        });
+    }
+
+    let started = Instant::now();
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break Some(status),
+            Ok(None) if started.elapsed() < timeout => std::thread::sleep(Duration::from_millis(100)),
+            _ => {
+                let _ = child.kill();
+                let _ = child.wait();
+                break None;
+            }
+        }
+    };
+
+    // Processes the hook left behind may still hold the pipes open; don't let
+    // them stall the worker
+    let mut output = Vec::new();
+    for _ in 0..pipe_count {
+        match rx.recv_timeout(Duration::from_secs(1)) {
+            Ok(buf) => output.extend(buf),
+            Err(_) => break,
+        }
+    }
+    let output = String::from_utf8_lossy(&output);
+    let output: String = output.trim().chars().take(MAX_LOGGED_OUTPUT).collect();
+
+    match status {
+        Some(s) if s.success() => log::info(&format!("Hook '{}' finished: {}", event.name(), output)),
+        Some(s) => log::warn(&format!("Hook '{}' exited with {}: {}", event.name(), s, output)),
+        None => log::warn(&format!("Hook '{}' killed after {} s: {}", event.name(), timeout.as_secs(), output)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn placeholders_refer_to_the_environment() {
+        let command = substitute("notify.exe {event} {profile} {classification}");
+        assert_eq!(command, r#"notify.exe "!ICCAR_EVENT!" "!ICCAR_PROFILE!" "!ICCAR_CLASSIFICATION!""#);
+    }
+
+    #[test]
+    fn accepts_placeholders_quoted_by_hand() {
+        let command = substitute(r#"copy "{profile}" D:\backup"#);
+        assert_eq!(command, r#"copy "!ICCAR_PROFILE!" D:\backup"#);
+    }
+
+    #[test]
+    fn leaves_everything_else_alone() {
+        // `%` in the template is the user's own; values never get here
+        let command = substitute("echo %DATE% {error} {unknown} {");
+        assert_eq!(command, r#"echo %DATE% "!ICCAR_ERROR!" {unknown} {"#);
+    }
+}
@@ -0,0 +1,359 @@
+// --- Localization ---
+//
+// Messages live in Fluent-style catalogs, one `lang/<tag>.ftl` per language.
+// build.rs embeds every catalog it finds there; a `lang` directory next to the
+// executable can override single messages or add whole languages without a
+// rebuild. Lookups walk from the selected tag to its base language (zh-TW ->
+// zh) and finally to English; a message missing everywhere renders as its id.
+//
+// Supported syntax: `id = value`, indented continuation lines for multi-line
+// values, `# comments`, and `{ $name }` placeholders. The term
+// `-language-name` is the name shown in the language pickers.
+//
+// Message ids are the `Msg` enum generated by build.rs from `lang/en.ftl`.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{OnceLock, RwLock};
+
+#[cfg(windows)]
+use windows::Win32::Globalization::GetUserDefaultLocaleName;
+
+use crate::log;
+
+include!(concat!(env!("OUT_DIR"), "/catalogs.rs"));
+include!(concat!(env!("OUT_DIR"), "/messages.rs"));
+
+pub const FALLBACK: &str = "en";
+const LANGUAGE_NAME: &str = "-language-name";
+
+struct Catalog {
+    tag: String,
+    messages: HashMap<String, String>,
+}
+
+static CATALOGS: OnceLock<Vec<Catalog>> = OnceLock::new();
+static CURRENT: RwLock<String> = RwLock::new(String::new());
+
+fn catalogs() -> &'static [Catalog] {
+    CATALOGS.get_or_init(|| {
+        let mut catalogs: Vec<Catalog> =
+            EMBEDDED.iter().map(|(tag, source)| Catalog { tag: tag.to_string(), messages: parse(source) }).collect();
+        if let Some(dir) = override_dir() {
+            load_overrides(&mut catalogs, &dir);
+        }
+        // English first, the rest by tag
+        catalogs.sort_by(|a, b| (a.tag != FALLBACK, &a.tag).cmp(&(b.tag != FALLBACK, &b.tag)));
+        catalogs
+    })
+}
+
+fn override_dir() -> Option<PathBuf> {
+    Some(std::env::current_exe().ok()?.parent()?.join("lang"))
+}
+
+fn load_overrides(catalogs: &mut Vec<Catalog>, dir: &Path) {
+    let Ok(entries) = std::fs::read_dir(dir) else { return };
+    for path in entries.flatten().map(|e| e.path()) {
+        if !path.extension().map_or(false, |ext| ext.eq_ignore_ascii_case("ftl")) {
+            continue;
+        }
+        let Some(tag) = path.file_stem().map(|s| s.to_string_lossy().to_string()) else { continue };
+        let source = match std::fs::read_to_string(&path) {
+            Ok(s) => s,
+            Err(e) => {
+                log::warn(&format!("Could not read {}: {}", path.display(), e));
+                continue;
+            }
+        };
+        let messages = parse(&source);
+        match catalogs.iter_mut().find(|c| c.tag.eq_ignore_ascii_case(&tag)) {
+            Some(catalog) => catalog.messages.extend(messages),
+            None => catalogs.push(Catalog { tag, messages }),
+        }
+    }
+}
+
+// Parses a catalog into id -> value. Lines that don't parse are ignored so a
+// broken override can't take the UI down.
+pub fn parse(source: &str) -> HashMap<String, String> {
+    let mut messages = HashMap::new();
+    let mut current: Option<(String, Vec<&str>)> = None;
+
+    for line in source.lines() {
+        let line = line.trim_start_matches('\u{feff}');
+        if line.starts_with(' ') || line.starts_with('\t') || line.trim().is_empty() {
+            if let Some((_, lines)) = current.as_mut() {
+                lines.push(line);
+            }
+            continue;
+        }
+        if let Some((id, lines)) = current.take() {
+            messages.insert(id, join_value(&lines));
+        }
+        if line.starts_with('#') {
+            continue;
+        }
+        if let Some((id, value)) = line.split_once('=') {
+            let id = id.trim();
+            if is_identifier(id) {
+                current = Some((id.to_string(), vec![value]));
+            }
+        }
+    }
+    if let Some((id, lines)) = current.take() {
+        messages.insert(id, join_value(&lines));
+    }
+    messages
+}
+
+fn is_identifier(id: &str) -> bool {
+    let id = id.strip_prefix('-').unwrap_or(id);
+    let mut chars = id.chars();
+    chars.next().map_or(false, |c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+// The first element is the text after `=`, the rest are continuation lines.
+// Continuation lines lose their common indentation; blank lines between them
+// are kept, trailing ones are not.
+fn join_value(lines: &[&str]) -> String {
+    let inline = lines[0].trim();
+    let mut block = &lines[1..];
+    while let Some((last, rest)) = block.split_last() {
+        if !last.trim().is_empty() {
+            break;
+        }
+        block = rest;
+    }
+    let indent = block
+        .iter()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| l.len() - l.trim_start().len())
+        .min()
+        .unwrap_or(0);
+
+    let mut parts: Vec<&str> = Vec::new();
+    if !inline.is_empty() {
+        parts.push(inline);
+    }
+    parts.extend(block.iter().map(|l| l.get(indent..).unwrap_or("").trim_end()));
+    parts.join("\n")
+}
+
+// Replaces `{ $name }` with the matching argument. Unknown placeholders are
+// left visible as `{$name}` rather than silently dropped.
+pub fn format(template: &str, args: &[(&str, &str)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        out.push_str(&rest[..open]);
+        let Some(close) = rest[open..].find('}') else {
+            rest = &rest[open..];
+            break;
+        };
+        let inner = rest[open + 1..open + close].trim();
+        match inner.strip_prefix('$') {
+            Some(name) => match args.iter().find(|(k, _)| *k == name) {
+                Some((_, value)) => out.push_str(value),
+                None => out.push_str(&format!("{{${}}}", name)),
+            },
+            None => out.push_str(&rest[open..=open + close]),
+        }
+        rest = &rest[open + close + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+// Tags to try for `tag`, most specific first: zh-Hant-TW, zh-Hant, zh, en.
+fn fallback_chain(tag: &str) -> Vec<&str> {
+    let mut chain = Vec::new();
+    let mut t = tag;
+    while !t.is_empty() {
+        chain.push(t);
+        t = t.rfind('-').map_or("", |i| &t[..i]);
+    }
+    chain.push(FALLBACK);
+    chain
+}
+
+fn find_catalog(tag: &str) -> Option<&'static Catalog> {
+    catalogs().iter().find(|c| c.tag.eq_ignore_ascii_case(tag))
+}
+
+// The catalog that will actually serve `tag`.
+pub fn resolve(tag: &str) -> String {
+    fallback_chain(tag)
+        .into_iter()
+        .find_map(find_catalog)
+        .map_or(FALLBACK.to_string(), |c| c.tag.clone())
+}
+
+// (tag, display name) of every available language, English first.
+pub fn languages() -> Vec<(String, String)> {
+    catalogs()
+        .iter()
+        .map(|c| {
+            let name = c.messages.get(LANGUAGE_NAME).cloned().unwrap_or_else(|| c.tag.clone());
+            (c.tag.clone(), name)
+        })
+        .collect()
+}
+
+pub fn language() -> String {
+    match CURRENT.read() {
+        Ok(tag) if !tag.is_empty() => tag.clone(),
+        _ => FALLBACK.to_string(),
+    }
+}
+
+pub fn set_language(tag: &str) {
+    if let Ok(mut current) = CURRENT.write() {
+        *current = tag.to_string();
+    }
+}
+
+// BCP-47 tag of the user's locale, e.g. "zh-TW". Falls back to the POSIX
+// locale variables, which is also what non-Windows builds use.
+pub fn system_language() -> Option<String> {
+    platform_language().or_else(|| {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|v| !v.is_empty())
+            .and_then(|v| posix_to_bcp47(&v))
+    })
+}
+
+#[cfg(windows)]
+fn platform_language() -> Option<String> {
+    // LOCALE_NAME_MAX_LENGTH
+    let mut buf = [0u16; 85];
+    let len = unsafe { GetUserDefaultLocaleName(&mut buf) };
+    // The returned length includes the terminating null
+    (len > 1).then(|| String::from_utf16_lossy(&buf[..len as usize - 1]))
+}
+
+#[cfg(not(windows))]
+fn platform_language() -> Option<String> {
+    None
+}
+
+// "zh_TW.UTF-8@latin" -> "zh-TW"; "C" and "POSIX" mean no preference.
+fn posix_to_bcp47(value: &str) -> Option<String> {
+    let locale = value.split(|c| c == '.' || c == '@').next()?;
+    if locale.is_empty() || locale == "C" || locale == "POSIX" {
+        return None;
+    }
+    Some(locale.replace('_', "-"))
+}
+
+pub fn tr(msg: Msg) -> String {
+    tr_args(msg, &[])
+}
+
+pub fn tr_args(msg: Msg, args: &[(&str, &str)]) -> String {
+    let language = language();
+    fallback_chain(&language)
+        .into_iter()
+        .filter_map(find_catalog)
+        .find_map(|c| c.messages.get(msg.id()))
+        .map_or_else(|| msg.id().to_string(), |template| format(template, args))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    fn embedded(tag: &str) -> HashMap<String, String> {
+        let (_, source) = EMBEDDED.iter().find(|(t, _)| *t == tag).expect("catalog not embedded");
+        parse(source)
+    }
+
+    fn placeholders(value: &str) -> BTreeSet<String> {
+        value
+            .split('{')
+            .skip(1)
+            .filter_map(|rest| rest.split_once('}'))
+            .filter_map(|(inner, _)| inner.trim().strip_prefix('$').map(str::to_string))
+            .collect()
+    }
+
+    fn keys(messages: &HashMap<String, String>) -> BTreeSet<&str> {
+        messages.keys().map(String::as_str).collect()
+    }
+
+    #[test]
+    fn english_defines_every_message() {
+        let english = embedded(FALLBACK);
+        for msg in Msg::ALL {
+            assert!(english.contains_key(msg.id()), "en.ftl has no `{}`", msg.id());
+        }
+    }
+
+    #[test]
+    fn catalogs_have_no_missing_keys() {
+        let english = embedded(FALLBACK);
+        for (tag, _) in EMBEDDED {
+            let catalog = embedded(tag);
+            let missing: Vec<_> = keys(&english).difference(&keys(&catalog)).copied().collect();
+            assert!(missing.is_empty(), "{}.ftl is missing {:?}", tag, missing);
+        }
+    }
+
+    #[test]
+    fn catalogs_have_no_extra_keys() {
+        let english = embedded(FALLBACK);
+        for (tag, _) in EMBEDDED {
+            let catalog = embedded(tag);
+            let extra: Vec<_> = keys(&catalog).difference(&keys(&english)).copied().collect();
+            assert!(extra.is_empty(), "{}.ftl has keys English does not: {:?}", tag, extra);
+        }
+    }
+
+    #[test]
+    fn placeholders_match_english() {
+        let english = embedded(FALLBACK);
+        for (tag, _) in EMBEDDED {
+            for (id, value) in embedded(tag) {
+                let Some(reference) = english.get(&id) else { continue };
+                assert_eq!(placeholders(&value), placeholders(reference), "placeholders of `{}` in {}.ftl", id, tag);
+            }
+        }
+    }
+
+    #[test]
+    fn every_catalog_names_its_language() {
+        for (tag, _) in EMBEDDED {
+            assert!(embedded(tag).contains_key(LANGUAGE_NAME), "{}.ftl has no {}", tag, LANGUAGE_NAME);
+        }
+    }
+
+    #[test]
+    fn parses_multiline_values_and_placeholders() {
+        let messages = parse("a = one\nb =\n    first\n\n    second\n\n# comment\nc = { $x } and { $y }\n");
+        assert_eq!(messages["a"], "one");
+        assert_eq!(messages["b"], "first\n\nsecond");
+        assert_eq!(format(&messages["c"], &[("x", "1")]), "1 and {$y}");
+    }
+
+    #[test]
+    fn falls_back_to_base_language_then_english() {
+        assert_eq!(fallback_chain("zh-Hant-TW"), vec!["zh-Hant-TW", "zh-Hant", "zh", FALLBACK]);
+        assert_eq!(resolve("ja-JP"), "ja");
+        assert_eq!(resolve("xx"), FALLBACK);
+    }
+
+    #[test]
+    fn maps_system_locales_to_catalogs() {
+        assert_eq!(posix_to_bcp47("zh_TW.UTF-8").as_deref(), Some("zh-TW"));
+        assert_eq!(posix_to_bcp47("ja_JP@euro").as_deref(), Some("ja-JP"));
+        assert_eq!(posix_to_bcp47("C.UTF-8"), None);
+        assert_eq!(resolve("zh-TW"), "zh");
+        assert_eq!(resolve("zh-Hans-CN"), "zh");
+        assert_eq!(resolve("JA-jp"), "ja");
+        assert_eq!(resolve("de-DE"), FALLBACK);
+    }
+}
@@ -0,0 +1,564 @@
+// --- ICC Profiles ---
+//
+// Just enough of ICC.1 (v2 and v4) to tell which calibration is loaded: the
+// header, the tag table, and the text and XYZ tags that describe a display
+// profile. Nothing here touches Windows, so profiles can be inspected from the
+// command line on any file.
+
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use crate::accuracy::{self, AccuracyReport};
+use crate::color::{round, Matrix3};
+use crate::dates;
+use crate::display::Ramp;
+use crate::gamut::{self, GamutReport};
+use crate::json::Value;
+use crate::md5;
+use crate::ramp::{self, RampReport};
+
+const HEADER_SIZE: usize = 128;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Xyz {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Xyz {
+    // CIE 1931 xy chromaticity
+    pub fn chromaticity(&self) -> (f64, f64) {
+        let sum = self.x + self.y + self.z;
+        if sum <= 0.0 {
+            return (0.0, 0.0);
+        }
+        (self.x / sum, self.y / sum)
+    }
+}
+
+// Windows advanced color calibration from the private `MHC2` tag: a 3x4
+// matrix and per-channel regamma LUTs the compositor applies, plus the
+// panel's luminance range. The gamma ramp does not carry any of this.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mhc2 {
+    // cd/m²
+    pub min_luminance: f64,
+    pub max_luminance: f64,
+    // Rows of [r g b offset]
+    pub matrix: [[f64; 4]; 3],
+    pub luts: [Vec<f64>; 3],
+}
+
+impl Mhc2 {
+    pub fn matrix_is_identity(&self) -> bool {
+        self.matrix.iter().enumerate().all(|(r, row)| row.iter().enumerate().all(|(c, &v)| (v - if r == c { 1.0 } else { 0.0 }).abs() < 1e-4))
+    }
+
+    // True when every LUT maps its input to itself
+    pub fn luts_are_identity(&self) -> bool {
+        self.luts.iter().all(|lut| {
+            let last = lut.len().saturating_sub(1).max(1) as f64;
+            lut.iter().enumerate().all(|(i, &v)| (v - i as f64 / last).abs() < 1e-3)
+        })
+    }
+
+    // Whether the tag changes anything at all
+    pub fn is_identity(&self) -> bool {
+        self.matrix_is_identity() && self.luts_are_identity()
+    }
+
+    pub fn to_json(&self) -> Value {
+        let number = |v: f64| Value::from(round(v, 6));
+        Value::object(vec![
+            ("min_luminance", Value::from(round(self.min_luminance, 4))),
+            ("max_luminance", Value::from(round(self.max_luminance, 1))),
+            ("matrix", Value::Array(self.matrix.iter().map(|row| Value::Array(row.iter().map(|&v| number(v)).collect())).collect())),
+            ("matrix_identity", Value::from(self.matrix_is_identity())),
+            ("lut_entries", Value::from(self.luts[0].len())),
+            ("luts", Value::Array(self.luts.iter().map(|lut| Value::Array(lut.iter().map(|&v| number(v)).collect())).collect())),
+            ("luts_identity", Value::from(self.luts_are_identity())),
+        ])
+    }
+}
+
+// Header date/time. ICC stores these in UTC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct IccDate {
+    pub year: u16,
+    pub month: u16,
+    pub day: u16,
+    pub hour: u16,
+    pub minute: u16,
+    pub second: u16,
+}
+
+impl IccDate {
+    // Accepts `YYYY-MM-DD`, optionally followed by `T` or a space and
+    // `HH:MM:SS`. Anything after the seconds (fractions, zone) is ignored.
+    pub fn parse(text: &str) -> Option<IccDate> {
+        let text = text.trim();
+        let num = |range: std::ops::Range<usize>| text.get(range)?.parse::<u16>().ok();
+        let sep = |i: usize, c: &[u8]| text.as_bytes().get(i).is_some_and(|b| c.contains(b));
+        if !(sep(4, b"-") && sep(7, b"-")) {
+            return None;
+        }
+        let mut date = IccDate { year: num(0..4)?, month: num(5..7)?, day: num(8..10)?, hour: 0, minute: 0, second: 0 };
+        if text.len() > 10 {
+            if !(sep(10, b"T ") && sep(13, b":") && sep(16, b":")) {
+                return None;
+            }
+            date.hour = num(11..13)?;
+            date.minute = num(14..16)?;
+            date.second = num(17..19)?;
+        }
+        date.is_valid().then_some(date)
+    }
+
+    fn is_valid(&self) -> bool {
+        self.year > 0 && (1..=12).contains(&self.month) && (1..=31).contains(&self.day) && self.hour < 24 && self.minute < 60 && self.second < 61
+    }
+
+    pub fn unix_seconds(&self) -> i64 {
+        let days = dates::days_from_civil(self.year as i64, self.month as u32, self.day as u32);
+        days * 86400 + self.hour as i64 * 3600 + self.minute as i64 * 60 + self.second as i64
+    }
+
+    pub fn to_iso8601(self) -> String {
+        format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+}
+
+impl std::fmt::Display for IccDate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TagEntry {
+    pub signature: [u8; 4],
+    pub offset: usize,
+    pub size: usize,
+}
+
+pub struct Profile {
+    data: Vec<u8>,
+    tags: Vec<TagEntry>,
+}
+
+impl Profile {
+    pub fn load(path: &Path) -> Result<Profile, String> {
+        let data = std::fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Profile::from_bytes(data)
+    }
+
+    pub fn from_bytes(data: Vec<u8>) -> Result<Profile, String> {
+        if data.len() < HEADER_SIZE + 4 {
+            return Err("file is too small to be an ICC profile".to_string());
+        }
+        if &data[36..40] != b"acsp" {
+            return Err("missing 'acsp' signature, not an ICC profile".to_string());
+        }
+
+        let count = read_u32(&data, HEADER_SIZE).unwrap_or(0) as usize;
+        let mut tags = Vec::new();
+        for i in 0..count {
+            let entry = HEADER_SIZE + 4 + i * 12;
+            let (Some(sig), Some(offset), Some(size)) =
+                (data.get(entry..entry + 4), read_u32(&data, entry + 4), read_u32(&data, entry + 8))
+            else {
+                return Err(format!("tag table is truncated ({} of {} entries)", i, count));
+            };
+            tags.push(TagEntry { signature: [sig[0], sig[1], sig[2], sig[3]], offset: offset as usize, size: size as usize });
+        }
+        Ok(Profile { data, tags })
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    // Tag table entries in file order.
+    pub fn tags(&self) -> &[TagEntry] {
+        &self.tags
+    }
+
+    // (major, minor, bugfix)
+    pub fn version(&self) -> (u8, u8, u8) {
+        (self.data[8], self.data[9] >> 4, self.data[9] & 0x0f)
+    }
+
+    pub fn class(&self) -> [u8; 4] {
+        signature_at(&self.data, 12)
+    }
+
+    pub fn color_space(&self) -> [u8; 4] {
+        signature_at(&self.data, 16)
+    }
+
+    pub fn created(&self) -> Option<IccDate> {
+        let field = |i: usize| read_u16(&self.data, 24 + i * 2).unwrap_or(0);
+        let date = IccDate { year: field(0), month: field(1), day: field(2), hour: field(3), minute: field(4), second: field(5) };
+        date.is_valid().then_some(date)
+    }
+
+    pub fn has_tag(&self, signature: &[u8; 4]) -> bool {
+        self.tags.iter().any(|t| &t.signature == signature)
+    }
+
+    // Raw tag data, type signature included. None if missing or out of bounds.
+    pub fn tag(&self, signature: &[u8; 4]) -> Option<&[u8]> {
+        let entry = self.tags.iter().find(|t| &t.signature == signature)?;
+        self.data.get(entry.offset..entry.offset.checked_add(entry.size)?)
+    }
+
+    // Text from a `desc`, `mluc` or `text` tag.
+    pub fn text(&self, signature: &[u8; 4]) -> Option<String> {
+        let tag = self.tag(signature)?;
+        let text = match tag.get(0..4)? {
+            b"desc" => {
+                let len = read_u32(tag, 8)? as usize;
+                ascii(tag.get(12..12 + len)?)
+            }
+            b"text" => ascii(tag.get(8..)?),
+            b"mluc" => mluc_text(tag)?,
+            _ => return None,
+        };
+        let text = text.trim().to_string();
+        (!text.is_empty()).then_some(text)
+    }
+
+    // Name/value pairs of a `dict` tag, such as the `meta` tag DisplayCAL
+    // writes. Empty if the tag is missing or malformed.
+    pub fn dict(&self, signature: &[u8; 4]) -> Vec<(String, String)> {
+        let Some(tag) = self.tag(signature).filter(|t| t.get(0..4) == Some(b"dict")) else { return Vec::new() };
+        let string = |offset: Option<u32>, size: Option<u32>| -> Option<String> {
+            let (offset, size) = (offset? as usize, size? as usize);
+            let units: Vec<u16> = tag.get(offset..offset + size)?.chunks_exact(2).map(|c| u16::from_be_bytes([c[0], c[1]])).collect();
+            Some(String::from_utf16_lossy(&units))
+        };
+
+        let record_size = read_u32(tag, 12).unwrap_or(0) as usize;
+        if record_size < 16 {
+            return Vec::new();
+        }
+        // No more records than fit in the tag, whatever the count says
+        let count = (read_u32(tag, 8).unwrap_or(0) as usize).min(tag.len().saturating_sub(16) / record_size);
+        (0..count)
+            .map(|i| 16 + i * record_size)
+            .map_while(|r| {
+                let name = string(read_u32(tag, r), read_u32(tag, r + 4))?;
+                // Offset and size 0 mean the entry has no value
+                let value = string(read_u32(tag, r + 8), read_u32(tag, r + 12)).unwrap_or_default();
+                Some((name, value))
+            })
+            .collect()
+    }
+
+    // Value of an `XYZ ` tag (first entry).
+    pub fn xyz(&self, signature: &[u8; 4]) -> Option<Xyz> {
+        let tag = self.tag(signature)?;
+        if tag.get(0..4)? != b"XYZ " {
+            return None;
+        }
+        Some(Xyz { x: read_s15f16(tag, 8)?, y: read_s15f16(tag, 12)?, z: read_s15f16(tag, 16)? })
+    }
+
+    // A 3x3 `sf32` tag such as `chad`, row by row.
+    pub fn matrix(&self, signature: &[u8; 4]) -> Option<Matrix3> {
+        let tag = self.tag(signature)?;
+        if tag.get(0..4)? != b"sf32" {
+            return None;
+        }
+        let mut m = [[0.0; 3]; 3];
+        for (i, cell) in m.iter_mut().flatten().enumerate() {
+            *cell = read_s15f16(tag, 8 + i * 4)?;
+        }
+        Some(m)
+    }
+
+    // Calibration curves of the `vcgt` tag, either a table of 8 or 16-bit
+    // entries (resampled to 256) or a gamma/min/max formula per channel.
+    pub fn vcgt(&self) -> Option<Ramp> {
+        let tag = self.tag(b"vcgt")?;
+        if tag.get(0..4)? != b"vcgt" {
+            return None;
+        }
+        let curves = match read_u32(tag, 8)? {
+            0 => {
+                let channels = read_u16(tag, 12)? as usize;
+                let entries = read_u16(tag, 14)? as usize;
+                let entry_size = read_u16(tag, 16)? as usize;
+                type Read = fn(&[u8], usize) -> Option<f64>;
+                let (read, max): (Read, f64) = match entry_size {
+                    1 => (|d, o| d.get(o).map(|&b| b as f64), 255.0),
+                    2 => (|d, o| read_u16(d, o).map(f64::from), 65535.0),
+                    _ => return None,
+                };
+                (0..channels)
+                    .map(|c| (0..entries).map(|i| Some(read(tag, 18 + (c * entries + i) * entry_size)? / max)).collect())
+                    .collect::<Option<Vec<Vec<f64>>>>()?
+            }
+            1 => (0..3)
+                .map(|c| {
+                    let (gamma, min, max) = (read_s15f16(tag, 12 + c * 12)?, read_s15f16(tag, 16 + c * 12)?, read_s15f16(tag, 20 + c * 12)?);
+                    Some((0..256).map(|i| min + (max - min) * (i as f64 / 255.0).powf(gamma)).collect())
+                })
+                .collect::<Option<Vec<Vec<f64>>>>()?,
+            _ => return None,
+        };
+        ramp::from_curves(&curves)
+    }
+
+    // The `MHC2` tag: entry count, min and max luminance, then offsets (from
+    // the tag start) of the matrix and the red, green and blue LUTs, each
+    // stored as an `sf32` array.
+    pub fn mhc2(&self) -> Option<Mhc2> {
+        let tag = self.tag(b"MHC2")?;
+        if tag.get(0..4)? != b"MHC2" {
+            return None;
+        }
+        let entries = read_u32(tag, 8)? as usize;
+        if !(2..=4096).contains(&entries) {
+            return None;
+        }
+        let array = |offset_field: usize, count: usize| -> Option<Vec<f64>> {
+            let mut offset = read_u32(tag, offset_field)? as usize;
+            if tag.get(offset..offset + 4)? == b"sf32" {
+                offset += 8;
+            }
+            (0..count).map(|i| read_s15f16(tag, offset + i * 4)).collect()
+        };
+        let values = array(20, 12)?;
+        let mut matrix = [[0.0; 4]; 3];
+        for (i, cell) in matrix.iter_mut().flatten().enumerate() {
+            *cell = values[i];
+        }
+        Some(Mhc2 {
+            min_luminance: read_s15f16(tag, 12)?,
+            max_luminance: read_s15f16(tag, 16)?,
+            matrix,
+            luts: [array(24, entries)?, array(28, entries)?, array(32, entries)?],
+        })
+    }
+
+    // True when the calibration lives in `MHC2` and the `vcgt` is missing or
+    // linear, so reloading the gamma ramp restores nothing.
+    pub fn relies_on_mhc2(&self) -> bool {
+        let Some(mhc2) = self.mhc2() else { return false };
+        let linear = |ramp: Ramp| ramp.iter().all(|c| c.iter().enumerate().all(|(i, &v)| (v as i32 - i as i32 * 257).abs() <= 257));
+        !mhc2.is_identity() && self.vcgt().is_none_or(linear)
+    }
+
+    pub fn info(&self) -> ProfileInfo {
+        let primaries = match (self.xyz(b"rXYZ"), self.xyz(b"gXYZ"), self.xyz(b"bXYZ")) {
+            (Some(r), Some(g), Some(b)) => Some([r, g, b]),
+            _ => None,
+        };
+        let gamut = gamut::analyze(self);
+        ProfileInfo {
+            description: self.text(b"desc"),
+            copyright: self.text(b"cprt"),
+            manufacturer: self.text(b"dmnd"),
+            model: self.text(b"dmdd"),
+            created: self.created(),
+            version: self.version(),
+            class: self.class(),
+            color_space: self.color_space(),
+            white_point: self.xyz(b"wtpt"),
+            luminance: self.xyz(b"lumi").map(|l| l.y),
+            primaries,
+            has_vcgt: self.has_tag(b"vcgt"),
+            calibration: self.vcgt().map(|r| ramp::analyze(&r, gamut.as_ref())),
+            gamut,
+            mhc2: self.mhc2(),
+            relies_on_mhc2: self.relies_on_mhc2(),
+            accuracy: accuracy::analyze(self),
+        }
+    }
+}
+
+// What the tray, the tooltip and `profile info` show about a profile.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProfileInfo {
+    pub description: Option<String>,
+    pub copyright: Option<String>,
+    pub manufacturer: Option<String>,
+    pub model: Option<String>,
+    pub created: Option<IccDate>,
+    pub version: (u8, u8, u8),
+    pub class: [u8; 4],
+    pub color_space: [u8; 4],
+    pub white_point: Option<Xyz>,
+    // cd/m², the Y of the `lumi` tag
+    pub luminance: Option<f64>,
+    // rXYZ, gXYZ, bXYZ as stored (PCS-relative)
+    pub primaries: Option<[Xyz; 3]>,
+    pub has_vcgt: bool,
+    pub gamut: Option<GamutReport>,
+    // Analysis of the `vcgt` curves, when they could be decoded
+    pub calibration: Option<RampReport>,
+    pub mhc2: Option<Mhc2>,
+    pub relies_on_mhc2: bool,
+    // ΔE2000 of the embedded `targ` measurements against the profile
+    pub accuracy: Option<AccuracyReport>,
+}
+
+impl ProfileInfo {
+    pub fn version_string(&self) -> String {
+        format!("{}.{}.{}", self.version.0, self.version.1, self.version.2)
+    }
+
+    pub fn class_name(&self) -> String {
+        match &self.class {
+            b"mntr" => "Display".to_string(),
+            b"scnr" => "Input".to_string(),
+            b"prtr" => "Output".to_string(),
+            b"link" => "DeviceLink".to_string(),
+            b"spac" => "ColorSpace".to_string(),
+            b"abst" => "Abstract".to_string(),
+            b"nmcl" => "NamedColor".to_string(),
+            other => signature_string(other),
+        }
+    }
+
+    pub fn device(&self) -> Option<String> {
+        match (&self.manufacturer, &self.model) {
+            (Some(m), Some(d)) if d.starts_with(m.as_str()) => Some(d.clone()),
+            (Some(m), Some(d)) => Some(format!("{} {}", m, d)),
+            (m, d) => m.clone().or_else(|| d.clone()),
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        let xyz = |v: &Xyz| {
+            let (x, y) = v.chromaticity();
+            Value::object(vec![
+                ("X", Value::from(round(v.x, 4))),
+                ("Y", Value::from(round(v.y, 4))),
+                ("Z", Value::from(round(v.z, 4))),
+                ("x", Value::from(round(x, 4))),
+                ("y", Value::from(round(y, 4))),
+            ])
+        };
+        Value::object(vec![
+            ("description", Value::from(self.description.clone())),
+            ("copyright", Value::from(self.copyright.clone())),
+            ("manufacturer", Value::from(self.manufacturer.clone())),
+            ("model", Value::from(self.model.clone())),
+            ("created", Value::from(self.created.map(|d| d.to_iso8601()))),
+            ("version", Value::from(self.version_string())),
+            ("class", Value::from(self.class_name())),
+            ("color_space", Value::from(signature_string(&self.color_space).trim_end().to_string())),
+            ("white_point", self.white_point.as_ref().map_or(Value::Null, xyz)),
+            ("luminance", Value::from(self.luminance.map(|l| round(l, 1)))),
+            (
+                "primaries",
+                self.primaries.as_ref().map_or(Value::Null, |p| {
+                    Value::object(vec![("red", xyz(&p[0])), ("green", xyz(&p[1])), ("blue", xyz(&p[2]))])
+                }),
+            ),
+            ("vcgt", Value::from(self.has_vcgt)),
+            ("gamut", self.gamut.as_ref().map_or(Value::Null, GamutReport::to_json)),
+            ("calibration", self.calibration.as_ref().map_or(Value::Null, RampReport::to_json)),
+            ("mhc2", self.mhc2.as_ref().map_or(Value::Null, Mhc2::to_json)),
+            ("relies_on_mhc2", Value::from(self.relies_on_mhc2)),
+            ("accuracy", self.accuracy.as_ref().map_or(Value::Null, AccuracyReport::to_json)),
+        ])
+    }
+}
+
+// Profiles are re-read only when the file changes, so the tray can refresh
+// its tooltip on every poll.
+pub fn inspect(path: &Path) -> Result<ProfileInfo, String> {
+    type Entry = (PathBuf, Option<SystemTime>, ProfileInfo);
+    static CACHE: Mutex<Vec<Entry>> = Mutex::new(Vec::new());
+
+    let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+    if let Ok(cache) = CACHE.lock() {
+        if let Some((_, _, info)) = cache.iter().find(|(p, m, _)| p == path && *m == modified) {
+            return Ok(info.clone());
+        }
+    }
+
+    let info = Profile::load(path)?.info();
+    if let Ok(mut cache) = CACHE.lock() {
+        cache.retain(|(p, _, _)| p != path);
+        cache.push((path.to_path_buf(), modified, info.clone()));
+    }
+    Ok(info)
+}
+
+// The profile ID: MD5 of the whole profile with the flags, rendering intent
+// and profile ID header fields set to zero.
+pub fn compute_profile_id(data: &[u8]) -> [u8; 16] {
+    let mut copy = data.to_vec();
+    for range in [44..48, 64..68, 84..100] {
+        if let Some(field) = copy.get_mut(range) {
+            field.fill(0);
+        }
+    }
+    md5::digest(&copy)
+}
+
+fn mluc_text(tag: &[u8]) -> Option<String> {
+    let record_size = read_u32(tag, 12)? as usize;
+    if record_size < 12 {
+        return None;
+    }
+    // No more records than fit in the tag, whatever the count says
+    let count = (read_u32(tag, 8)? as usize).min(tag.len().saturating_sub(16) / record_size);
+    let mut chosen: Option<(usize, usize)> = None;
+    for i in 0..count {
+        let record = 16 + i * record_size;
+        let language = tag.get(record..record + 4)?;
+        let len = read_u32(tag, record + 4)? as usize;
+        let offset = read_u32(tag, record + 8)? as usize;
+        // Prefer English, otherwise take the first record
+        if chosen.is_none() || language == b"enUS" {
+            chosen = Some((offset, len));
+        }
+        if language == b"enUS" {
+            break;
+        }
+    }
+    let (offset, len) = chosen?;
+    let units: Vec<u16> = tag.get(offset..offset + len)?.chunks_exact(2).map(|c| u16::from_be_bytes([c[0], c[1]])).collect();
+    Some(String::from_utf16_lossy(&units).trim_end_matches('\0').to_string())
+}
+
+fn ascii(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).to_string()
+}
+
+fn signature_at(data: &[u8], offset: usize) -> [u8; 4] {
+    [data[offset], data[offset + 1], data[offset + 2], data[offset + 3]]
+}
+
+pub fn signature_string(signature: &[u8; 4]) -> String {
+    signature.iter().map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '?' }).collect()
+}
+
+pub fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    let b = data.get(offset..offset + 2)?;
+    Some(u16::from_be_bytes([b[0], b[1]]))
+}
+
+pub fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let b = data.get(offset..offset + 4)?;
+    Some(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+pub fn read_s15f16(data: &[u8], offset: usize) -> Option<f64> {
+    Some(read_u32(data, offset)? as i32 as f64 / 65536.0)
+}
@@ -0,0 +1,227 @@
+// --- ICC Writer ---
+//
+// Rewrites a profile with tags added, replaced or removed. The output is laid
+// out from scratch: header, tag table, then every tag's data at a 4-byte
+// boundary, with tags of identical content sharing one copy. The header's
+// size field is updated, and the profile ID is recomputed for v4 profiles and
+// for v2 profiles that already carried one (the field is reserved in v2).
+//
+// Files are always written to a new path; the source profile is left alone.
+
+use std::path::{Path, PathBuf};
+
+use crate::display::Ramp;
+use crate::export;
+use crate::icc::{self, Profile};
+
+const HEADER_SIZE: usize = 128;
+
+// (signature, data) in tag table order
+pub type Tags = Vec<([u8; 4], Vec<u8>)>;
+
+// Data of every tag, in file order, for editing before `build`.
+pub fn tags_of(profile: &Profile) -> Result<Tags, String> {
+    profile
+        .tags()
+        .iter()
+        .map(|t| {
+            let end = t.offset.checked_add(t.size).filter(|&end| end <= profile.bytes().len());
+            let data = end.map(|end| profile.bytes()[t.offset..end].to_vec());
+            data.map(|d| (t.signature, d))
+                .ok_or_else(|| format!("tag '{}' points outside the file", icc::signature_string(&t.signature)))
+        })
+        .collect()
+}
+
+// Replaces the tag with `signature`, or appends it when missing.
+pub fn set_tag(tags: &mut Tags, signature: &[u8; 4], data: Vec<u8>) {
+    match tags.iter_mut().find(|(s, _)| s == signature) {
+        Some(tag) => tag.1 = data,
+        None => tags.push((*signature, data)),
+    }
+}
+
+// Lays out a profile from the first 128 bytes of `header` and `tags`.
+pub fn build(header: &[u8], tags: &[([u8; 4], Vec<u8>)]) -> Vec<u8> {
+    let mut out = header[..HEADER_SIZE].to_vec();
+    out.extend_from_slice(&(tags.len() as u32).to_be_bytes());
+    let table = out.len();
+    out.resize(table + tags.len() * 12, 0);
+
+    // (data, offset) of blobs already written
+    let mut written: Vec<(&[u8], usize)> = Vec::new();
+    for (i, (signature, data)) in tags.iter().enumerate() {
+        let offset = match written.iter().find(|(d, _)| *d == data.as_slice()) {
+            Some(&(_, offset)) => offset,
+            None => {
+                pad_to_4(&mut out);
+                let offset = out.len();
+                out.extend_from_slice(data);
+                written.push((data.as_slice(), offset));
+                offset
+            }
+        };
+        let entry = table + i * 12;
+        out[entry..entry + 4].copy_from_slice(signature);
+        out[entry + 4..entry + 8].copy_from_slice(&(offset as u32).to_be_bytes());
+        out[entry + 8..entry + 12].copy_from_slice(&(data.len() as u32).to_be_bytes());
+    }
+    pad_to_4(&mut out);
+
+    let size = out.len() as u32;
+    out[0..4].copy_from_slice(&size.to_be_bytes());
+    let had_id = header[84..100].iter().any(|&b| b != 0);
+    if header[8] >= 4 || had_id {
+        let id = icc::compute_profile_id(&out);
+        out[84..100].copy_from_slice(&id);
+    }
+    out
+}
+
+// A `vcgt` tag holding `ramp` as a 3 x 256 table of 16-bit entries.
+pub fn vcgt_tag(ramp: &Ramp) -> Vec<u8> {
+    let mut tag = b"vcgt".to_vec();
+    tag.extend_from_slice(&[0; 4]);
+    tag.extend_from_slice(&0u32.to_be_bytes());
+    for value in [3u16, 256, 2] {
+        tag.extend_from_slice(&value.to_be_bytes());
+    }
+    for value in ramp.iter().flatten() {
+        tag.extend_from_slice(&value.to_be_bytes());
+    }
+    tag
+}
+
+// Writes a copy of `profile` whose `vcgt` holds the curves of `curves` (a
+// calibration file or another profile). Returns the path written.
+pub fn merge_calibration(profile: &Path, curves: &Path, output: Option<&Path>) -> Result<PathBuf, String> {
+    let source = Profile::load(profile)?;
+    let ramp = export::file_ramp(curves)?;
+    let output = output.map_or_else(|| merged_path(profile), Path::to_path_buf);
+    if same_file(&output, profile) {
+        return Err("the merged profile must be written to a new file".to_string());
+    }
+
+    let mut tags = tags_of(&source)?;
+    set_tag(&mut tags, b"vcgt", vcgt_tag(&ramp));
+    let data = build(source.bytes(), &tags);
+    // Make sure what we wrote reads back
+    Profile::from_bytes(data.clone())?;
+    std::fs::write(&output, data).map_err(|e| format!("{}: {}", output.display(), e))?;
+    Ok(output)
+}
+
+// `display.icc` -> `display-calibrated.icc` next to it.
+pub fn merged_path(profile: &Path) -> PathBuf {
+    let stem = profile.file_stem().map_or("profile".to_string(), |s| s.to_string_lossy().to_string());
+    let ext = profile.extension().map_or("icc".to_string(), |e| e.to_string_lossy().to_string());
+    profile.with_file_name(format!("{}-calibrated.{}", stem, ext))
+}
+
+pub fn same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+fn pad_to_4(data: &mut Vec<u8>) {
+    while !data.len().is_multiple_of(4) {
+        data.push(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lint;
+
+    fn header(major: u8) -> Vec<u8> {
+        let mut header = vec![0; HEADER_SIZE];
+        header[8] = major;
+        header[12..16].copy_from_slice(b"mntr");
+        header[16..20].copy_from_slice(b"RGB ");
+        header[20..24].copy_from_slice(b"XYZ ");
+        header[36..40].copy_from_slice(b"acsp");
+        header
+    }
+
+    fn tag(kind: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        [kind.as_slice(), &[0; 4], body].concat()
+    }
+
+    fn xyz(x: f64, y: f64, z: f64) -> Vec<u8> {
+        let body: Vec<u8> = [x, y, z].iter().flat_map(|v| ((v * 65536.0).round() as i32).to_be_bytes()).collect();
+        tag(b"XYZ ", &body)
+    }
+
+    // Text tags of odd length and one curve shared by the three channels
+    fn display_tags() -> Tags {
+        let curve = tag(b"curv", &[0, 0, 0, 1, 0x02, 0x33]);
+        vec![
+            (*b"desc", tag(b"text", b"Test display\0")),
+            (*b"cprt", tag(b"text", b"No copyright\0")),
+            (*b"wtpt", xyz(0.9642, 1.0, 0.8249)),
+            (*b"rXYZ", xyz(0.4361, 0.2225, 0.0139)),
+            (*b"gXYZ", xyz(0.3851, 0.7169, 0.0971)),
+            (*b"bXYZ", xyz(0.1431, 0.0606, 0.7141)),
+            (*b"rTRC", curve.clone()),
+            (*b"gTRC", curve.clone()),
+            (*b"bTRC", curve),
+        ]
+    }
+
+    #[test]
+    fn build_lays_out_a_valid_profile() {
+        let data = build(&header(4), &display_tags());
+        let profile = Profile::from_bytes(data.clone()).unwrap();
+
+        assert_eq!(icc::read_u32(&data, 0), Some(data.len() as u32));
+        assert!(data.len().is_multiple_of(4));
+        assert!(profile.tags().iter().all(|t| t.offset.is_multiple_of(4)));
+        assert_eq!(profile.text(b"desc").as_deref(), Some("Test display"));
+
+        let offset = |sig: &[u8; 4]| profile.tags().iter().find(|t| &t.signature == sig).map(|t| t.offset);
+        assert_eq!(offset(b"rTRC"), offset(b"gTRC"));
+        assert_eq!(offset(b"rTRC"), offset(b"bTRC"));
+        assert_ne!(offset(b"rXYZ"), offset(b"gXYZ"));
+
+        assert_ne!(data[84..100], [0; 16]);
+        assert_eq!(data[84..100], icc::compute_profile_id(&data));
+        assert_eq!(lint::check(&data), []);
+    }
+
+    #[test]
+    fn v2_profiles_get_an_id_only_if_they_had_one() {
+        let data = build(&header(2), &display_tags());
+        assert_eq!(data[84..100], [0; 16]);
+        assert_eq!(lint::check(&data), []);
+
+        let mut with_id = header(2);
+        with_id[84..100].fill(0xff);
+        let data = build(&with_id, &display_tags());
+        assert_eq!(data[84..100], icc::compute_profile_id(&data));
+        assert_eq!(lint::check(&data), []);
+    }
+
+    #[test]
+    fn edits_round_trip() {
+        let profile = Profile::from_bytes(build(&header(4), &display_tags())).unwrap();
+        let mut tags = tags_of(&profile).unwrap();
+        assert_eq!(tags, display_tags());
+
+        let mut ramp: Ramp = [[0; 256]; 3];
+        for (i, value) in ramp.iter_mut().flat_map(|c| c.iter_mut().enumerate()) {
+            *value = i as u16 * 257;
+        }
+        set_tag(&mut tags, b"desc", tag(b"text", b"Renamed\0"));
+        set_tag(&mut tags, b"vcgt", vcgt_tag(&ramp));
+        assert_eq!(tags.len(), display_tags().len() + 1);
+
+        let data = build(profile.bytes(), &tags);
+        let rebuilt = Profile::from_bytes(data.clone()).unwrap();
+        assert_eq!(rebuilt.text(b"desc").as_deref(), Some("Renamed"));
+        assert_eq!(rebuilt.vcgt(), Some(ramp));
+        assert_eq!(lint::check(&data), []);
+    }
+}
@@ -0,0 +1,403 @@
+// --- Single Instance IPC ---
+//
+// A second instance forwards its command line to the primary instance over a
+// local named pipe. Each connection carries exactly one request line and one
+// response line:
+//
+//   client -> server   ICCAR/1 RELOAD
+//                      ICCAR/1 INSTALL C:\path\to\profile.icc
+//                      ICCAR/1 ASSIGN 2 3f2a91c0   (display number, library entry)
+//                      ICCAR/1 PRESET Print D50
+//                      ICCAR/1 PAUSE
+//                      ICCAR/1 PAUSE 900     (seconds up to a week, not saved; RESTART: until restart)
+//                      ICCAR/1 RESUME
+//                      ICCAR/1 STATUS
+//                      ICCAR/1 SETTINGS
+//                      ICCAR/1 QUIT
+//   server -> client   ICCAR/1 OK <message>   (STATUS: the report as one-line JSON)
+//                      ICCAR/1 ERR <message>
+//
+// The number after the slash is the protocol version. A server that receives a
+// version it does not speak answers with ERR instead of guessing.
+
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::os::windows::io::FromRawHandle;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Mutex, TryLockError};
+use std::time::{Duration, Instant};
+
+use windows::core::{w, PCWSTR};
+use windows::Win32::Foundation::ERROR_PIPE_CONNECTED;
+use windows::Win32::Storage::FileSystem::PIPE_ACCESS_DUPLEX;
+use windows::Win32::System::Pipes::{
+    ConnectNamedPipe, CreateNamedPipeW, PIPE_READMODE_BYTE, PIPE_REJECT_REMOTE_CLIENTS, PIPE_TYPE_BYTE,
+    PIPE_UNLIMITED_INSTANCES, PIPE_WAIT,
+};
+
+use crate::engine;
+
+pub const PROTOCOL_VERSION: u32 = 1;
+
+const PROTOCOL_TAG: &str = "ICCAR";
+const PIPE_NAME: PCWSTR = w!(r"\\.\pipe\IccAutoReloader");
+const PIPE_PATH: &str = r"\\.\pipe\IccAutoReloader";
+
+// How long the server waits for the UI thread to handle a request.
+const REPLY_TIMEOUT: Duration = Duration::from_secs(30);
+// How long a client keeps retrying while the pipe is busy or not yet created.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+// How long an exiting server waits for the reply it is writing.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(5);
+// Longer request lines are answered with ERR.
+const MAX_LINE_BYTES: u64 = 4096;
+
+const NOT_READY: &str = "the running instance is not ready yet";
+const EXITING: &str = "the running instance is exiting";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Request {
+    Reload,
+    Install(String),
+    // Library entry (hash, hash prefix or file name) for a display (1-based)
+    Assign { display: usize, entry: String },
+    // Preset name
+    ApplyPreset(String),
+    // Switches polling off (saved)
+    Pause,
+    // Pauses without saving, for a number of seconds or until restart (None)
+    PauseFor(Option<u64>),
+    Resume,
+    Status,
+    OpenSettings,
+    Quit,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Response {
+    Ok(String),
+    Error(String),
+}
+
+impl Request {
+    pub fn encode(&self) -> String {
+        let body = match self {
+            Request::Reload => "RELOAD".to_string(),
+            Request::Install(path) => format!("INSTALL {}", path),
+            Request::Assign { display, entry } => format!("ASSIGN {} {}", display, entry),
+            Request::ApplyPreset(name) => format!("PRESET {}", name),
+            Request::Pause => "PAUSE".to_string(),
+            Request::PauseFor(Some(seconds)) => format!("PAUSE {}", seconds),
+            Request::PauseFor(None) => "PAUSE RESTART".to_string(),
+            Request::Resume => "RESUME".to_string(),
+            Request::Status => "STATUS".to_string(),
+            Request::OpenSettings => "SETTINGS".to_string(),
+            Request::Quit => "QUIT".to_string(),
+        };
+        format!("{}/{} {}\n", PROTOCOL_TAG, PROTOCOL_VERSION, body)
+    }
+
+    pub fn decode(line: &str) -> Result<Request, String> {
+        let body = strip_header(line)?;
+        let (verb, arg) = match body.split_once(' ') {
+            Some((v, a)) => (v, a.trim()),
+            None => (body, ""),
+        };
+        match verb {
+            "RELOAD" => Ok(Request::Reload),
+            "INSTALL" if !arg.is_empty() => Ok(Request::Install(arg.to_string())),
+            "INSTALL" => Err("INSTALL requires a path".to_string()),
+            "ASSIGN" => {
+                let (display, entry) = arg.split_once(' ').ok_or_else(|| "ASSIGN requires a display and an entry".to_string())?;
+                let display = display.parse().ok().filter(|&n| n >= 1).ok_or_else(|| format!("invalid display number: {}", display))?;
+                Ok(Request::Assign { display, entry: entry.trim().to_string() })
+            }
+            "PRESET" if !arg.is_empty() => Ok(Request::ApplyPreset(arg.to_string())),
+            "PRESET" => Err("PRESET requires a name".to_string()),
+            "PAUSE" if arg.is_empty() => Ok(Request::Pause),
+            "PAUSE" if arg == "RESTART" => Ok(Request::PauseFor(None)),
+            "PAUSE" => match arg.parse::<u64>() {
+                Ok(seconds) if (1..=engine::MAX_PAUSE.as_secs()).contains(&seconds) => Ok(Request::PauseFor(Some(seconds))),
+                _ => Err(format!("invalid pause duration: {}", arg)),
+            },
+            "RESUME" => Ok(Request::Resume),
+            "STATUS" => Ok(Request::Status),
+            "SETTINGS" => Ok(Request::OpenSettings),
+            "QUIT" => Ok(Request::Quit),
+            _ => Err(format!("unknown request: {}", verb)),
+        }
+    }
+}
+
+impl Response {
+    pub fn encode(&self) -> String {
+        let (status, msg) = match self {
+            Response::Ok(m) => ("OK", m),
+            Response::Error(m) => ("ERR", m),
+        };
+        // Messages are single-line on the wire
+        let msg = msg.replace(['\r', '\n'], " ");
+        format!("{}/{} {} {}\n", PROTOCOL_TAG, PROTOCOL_VERSION, status, msg.trim())
+    }
+
+    pub fn decode(line: &str) -> Result<Response, String> {
+        let body = strip_header(line)?;
+        let (status, msg) = match body.split_once(' ') {
+            Some((s, m)) => (s, m.trim().to_string()),
+            None => (body, String::new()),
+        };
+        match status {
+            "OK" => Ok(Response::Ok(msg)),
+            "ERR" => Ok(Response::Error(msg)),
+            _ => Err(format!("malformed response: {}", body)),
+        }
+    }
+}
+
+fn strip_header(line: &str) -> Result<&str, String> {
+    let line = line.trim_end_matches(['\r', '\n']);
+    let (header, body) = line.split_once(' ').unwrap_or((line, ""));
+    let version = header
+        .strip_prefix(PROTOCOL_TAG)
+        .and_then(|rest| rest.strip_prefix('/'))
+        .ok_or_else(|| "not an IccAutoReloader message".to_string())?;
+    match version.parse::<u32>() {
+        Ok(PROTOCOL_VERSION) => Ok(body),
+        Ok(v) => Err(format!("unsupported protocol version {} (expected {})", v, PROTOCOL_VERSION)),
+        Err(_) => Err(format!("invalid protocol version: {}", version)),
+    }
+}
+
+// --- Server Side ---
+
+// A request waiting to be handled on the UI thread.
+pub struct Pending {
+    pub request: Request,
+    reply: mpsc::Sender<Response>,
+}
+
+impl Pending {
+    pub fn respond(self, response: Response) {
+        // The client may already have given up; nothing to do then
+        let _ = self.reply.send(response);
+    }
+}
+
+static QUEUE: Mutex<VecDeque<Pending>> = Mutex::new(VecDeque::new());
+// Tells the owner of the queue (the tray) that requests are waiting
+static WAKER: Mutex<Option<Box<dyn Fn() + Send>>> = Mutex::new(None);
+// Held while a request is dispatched and answered, see `wait_idle`
+static SERVING: Mutex<()> = Mutex::new(());
+// Set by `wait_idle`; requests arriving later are refused instead of queued
+static STOPPING: AtomicBool = AtomicBool::new(false);
+
+// Drains requests received since the last call. Called on the UI thread.
+pub fn take_pending() -> Vec<Pending> {
+    QUEUE.lock().map(|mut q| q.drain(..).collect()).unwrap_or_default()
+}
+
+// Queues a request from this process' own command line, e.g. when the first
+// instance is started with `settings`. Nobody waits for the response.
+pub fn push_local(request: Request) {
+    let (reply, _) = mpsc::channel();
+    if let Ok(mut q) = QUEUE.lock() {
+        q.push_back(Pending { request, reply });
+    }
+}
+
+// Registers the callback that is invoked after a request has been queued, so
+// the owner can call `take_pending` on its own thread.
+pub fn set_waker<F>(wake: F)
+where
+    F: Fn() + Send + 'static,
+{
+    if let Ok(mut waker) = WAKER.lock() {
+        *waker = Some(Box::new(wake));
+    }
+}
+
+// Queues a request for the owner of the queue and waits for its response.
+// Used by the pipe server and the control API threads. Nothing is queued
+// while there is no owner yet, so a refused request never runs later.
+pub fn dispatch(request: Request) -> Response {
+    let (reply, rx) = mpsc::channel();
+    {
+        let waker = WAKER.lock().ok();
+        let Some(wake) = waker.as_ref().and_then(|w| w.as_ref()) else {
+            return Response::Error(NOT_READY.to_string());
+        };
+        // The flag is checked under the queue lock, so `wait_idle` either
+        // sees the request or the request sees the flag
+        match QUEUE.lock() {
+            Ok(mut q) if !STOPPING.load(Ordering::SeqCst) => q.push_back(Pending { request, reply }),
+            _ => return Response::Error(EXITING.to_string()),
+        }
+        wake();
+    }
+    rx.recv_timeout(REPLY_TIMEOUT)
+        .unwrap_or_else(|_| Response::Error("timed out waiting for the running instance".to_string()))
+}
+
+// Starts the pipe server on a background thread.
+pub fn start_server() {
+    std::thread::spawn(move || loop {
+        let pipe = unsafe {
+            CreateNamedPipeW(
+                PIPE_NAME,
+                PIPE_ACCESS_DUPLEX,
+                PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_WAIT | PIPE_REJECT_REMOTE_CLIENTS,
+                PIPE_UNLIMITED_INSTANCES,
+                4096,
+                4096,
+                0,
+                None,
+            )
+        };
+        if pipe.is_invalid() {
+            return;
+        }
+
+        let connected = unsafe { ConnectNamedPipe(pipe, None) };
+        // The file takes ownership of the handle and closes it on drop
+        let file = unsafe { File::from_raw_handle(pipe.0 as _) };
+        if let Err(e) = connected {
+            if e.code() != ERROR_PIPE_CONNECTED.to_hresult() {
+                continue;
+            }
+        }
+
+        // A client that connects and sends nothing only stalls its own thread
+        std::thread::spawn(move || {
+            let _ = serve_connection(file);
+        });
+    });
+}
+
+// Blocks until the request currently being answered (if any) has written its
+// response, for at most `IDLE_TIMEOUT`, and refuses requests from then on.
+// Called before the process exits so a QUIT still gets its answer.
+pub fn wait_idle() {
+    if let Ok(mut q) = QUEUE.lock() {
+        STOPPING.store(true, Ordering::SeqCst);
+        for pending in q.drain(..) {
+            pending.respond(Response::Error(EXITING.to_string()));
+        }
+    }
+    let deadline = Instant::now() + IDLE_TIMEOUT;
+    while matches!(SERVING.try_lock(), Err(TryLockError::WouldBlock)) && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(20));
+    }
+}
+
+fn serve_connection(file: File) -> io::Result<()> {
+    let mut reader = BufReader::new(file);
+    let mut line = String::new();
+    let read = (&mut reader).take(MAX_LINE_BYTES + 1).read_line(&mut line)?;
+
+    let _guard = SERVING.lock();
+    let response = if read as u64 > MAX_LINE_BYTES {
+        Response::Error(format!("request longer than {} bytes", MAX_LINE_BYTES))
+    } else {
+        match Request::decode(&line) {
+            Ok(request) => dispatch(request),
+            Err(e) => Response::Error(e),
+        }
+    };
+
+    let mut file = reader.into_inner();
+    file.write_all(response.encode().as_bytes())?;
+    // FlushFileBuffers: wait until the client has read the response
+    file.sync_all()
+}
+
+// --- Client Side ---
+
+// Sends a request to the primary instance and waits for its answer.
+pub fn send_request(request: &Request) -> io::Result<Response> {
+    let mut pipe = connect()?;
+    pipe.write_all(request.encode().as_bytes())?;
+
+    let mut line = String::new();
+    BufReader::new(pipe).read_line(&mut line)?;
+    Response::decode(&line).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+fn connect() -> io::Result<File> {
+    let started = std::time::Instant::now();
+    loop {
+        match OpenOptions::new().read(true).write(true).open(PIPE_PATH) {
+            Ok(f) => return Ok(f),
+            // Busy (another client) or not created yet (primary still starting)
+            Err(_) if started.elapsed() < CONNECT_TIMEOUT => {
+                std::thread::sleep(Duration::from_millis(100));
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_round_trip() {
+        let requests = [
+            Request::Reload,
+            Request::Install(r"C:\Profiles\My Display.icc".to_string()),
+            Request::Assign { display: 2, entry: "My Profile.icc".to_string() },
+            Request::ApplyPreset("Print D50".to_string()),
+            Request::Pause,
+            Request::PauseFor(Some(900)),
+            Request::PauseFor(None),
+            Request::Resume,
+            Request::Status,
+            Request::OpenSettings,
+            Request::Quit,
+        ];
+        for request in requests {
+            assert_eq!(Request::decode(&request.encode()), Ok(request));
+        }
+    }
+
+    #[test]
+    fn pause_arguments() {
+        assert_eq!(Request::PauseFor(None).encode(), "ICCAR/1 PAUSE RESTART\n");
+        assert_eq!(Request::decode("ICCAR/1 PAUSE"), Ok(Request::Pause));
+        assert_eq!(Request::decode("ICCAR/1 PAUSE 60"), Ok(Request::PauseFor(Some(60))));
+        assert!(Request::decode("ICCAR/1 PAUSE 0").is_err());
+        assert!(Request::decode("ICCAR/1 PAUSE soon").is_err());
+        // Longer than a week, or too large for an Instant
+        let week = engine::MAX_PAUSE.as_secs();
+        assert_eq!(Request::decode(&format!("ICCAR/1 PAUSE {}", week)), Ok(Request::PauseFor(Some(week))));
+        assert!(Request::decode(&format!("ICCAR/1 PAUSE {}", week + 1)).is_err());
+        assert!(Request::decode("ICCAR/1 PAUSE 18446744073709551615").is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_requests() {
+        assert!(Request::decode("ICCAR/1 FROB").unwrap_err().contains("unknown request"));
+        assert!(Request::decode("ICCAR/1 INSTALL").is_err());
+        assert!(Request::decode("ICCAR/1 ASSIGN 2").is_err());
+        assert!(Request::decode("ICCAR/1 ASSIGN 0 abc").is_err());
+        assert!(Request::decode("RELOAD").is_err());
+    }
+
+    #[test]
+    fn rejects_other_protocol_versions() {
+        let error = Request::decode("ICCAR/2 RELOAD").unwrap_err();
+        assert!(error.contains("unsupported protocol version 2"), "{}", error);
+        assert!(Request::decode("ICCAR/x RELOAD").is_err());
+        assert!(Response::decode("ICCAR/2 OK fine").is_err());
+    }
+
+    #[test]
+    fn responses_round_trip_on_one_line() {
+        assert_eq!(Response::decode(&Response::Ok("done".to_string()).encode()), Ok(Response::Ok("done".to_string())));
+        let error = Response::Error("first\r\nsecond".to_string()).encode();
+        assert_eq!(error.lines().count(), 1);
+        assert_eq!(Response::decode(&error), Ok(Response::Error("first  second".to_string())));
+        assert!(Response::decode("ICCAR/1 MAYBE").is_err());
+    }
+}
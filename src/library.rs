@@ -0,0 +1,279 @@
+// --- Profile Library ---
+//
+// Every profile or calibration file that is imported is kept in
+// `<app data>\library`, one folder per file named after the MD5 of its
+// content, with the original file name inside:
+//
+//   library\index.json
+//   library\3f2a...e1\Studio D65.icc
+//   library\9b07...4c\night.cal
+//
+// Importing the same content twice yields the same entry. `index.json` holds
+// what was parsed at import time, so listing and searching never re-read the
+// files. The monitor is the ID of the display the entry was imported for or
+// last assigned to; for profiles whose device description names a connected
+// monitor, that monitor wins.
+
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::calibration;
+use crate::dates;
+use crate::display::Display;
+use crate::icc::Profile;
+use crate::json::{self, Value};
+use crate::md5;
+
+// Shortest hash prefix accepted to name an entry
+const MIN_PREFIX: usize = 6;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    // MD5 of the content, lowercase hex
+    pub hash: String,
+    // Original file name
+    pub name: String,
+    // Calibration file rather than ICC profile
+    pub calibration: bool,
+    pub description: Option<String>,
+    pub device: Option<String>,
+    // Profile creation date (ISO 8601)
+    pub created: Option<String>,
+    pub has_vcgt: bool,
+    // Monitor ID (PnP ID plus instance) the entry belongs to
+    pub monitor: Option<String>,
+    pub imported: String,
+}
+
+impl Entry {
+    pub fn path(&self) -> PathBuf {
+        dir().join(&self.hash).join(&self.name)
+    }
+
+    // Short form of the hash used in listings and accepted by `find`
+    pub fn short_hash(&self) -> &str {
+        &self.hash[..self.hash.len().min(8)]
+    }
+
+    // Description, or the file name when the file has none.
+    pub fn title(&self) -> &str {
+        self.description.as_deref().unwrap_or(&self.name)
+    }
+
+    pub fn to_json(&self) -> Value {
+        Value::object(vec![
+            ("hash", Value::from(self.hash.as_str())),
+            ("name", Value::from(self.name.as_str())),
+            ("kind", Value::from(if self.calibration { "calibration" } else { "profile" })),
+            ("description", Value::from(self.description.clone())),
+            ("device", Value::from(self.device.clone())),
+            ("created", Value::from(self.created.clone())),
+            ("vcgt", Value::from(self.has_vcgt)),
+            ("monitor", Value::from(self.monitor.clone())),
+            ("imported", Value::from(self.imported.as_str())),
+            ("path", Value::from(self.path().to_string_lossy().to_string())),
+        ])
+    }
+
+    fn from_json(value: &Value) -> Option<Entry> {
+        let text = |key: &str| value.get(key).and_then(Value::as_str).map(str::to_string);
+        Some(Entry {
+            hash: text("hash").filter(|h| h.len() == 32 && h.bytes().all(|b| b.is_ascii_hexdigit()))?,
+            name: text("name").filter(|n| is_plain_name(n))?,
+            calibration: text("kind").as_deref() == Some("calibration"),
+            description: text("description"),
+            device: text("device"),
+            created: text("created"),
+            has_vcgt: value.get("vcgt").and_then(Value::as_bool).unwrap_or(false),
+            monitor: text("monitor"),
+            imported: text("imported").unwrap_or_default(),
+        })
+    }
+
+    fn matches(&self, query: &str) -> bool {
+        let query = query.to_lowercase();
+        [Some(&self.name), self.description.as_ref(), self.device.as_ref(), self.monitor.as_ref(), Some(&self.hash)]
+            .into_iter()
+            .flatten()
+            .any(|field| field.to_lowercase().contains(&query))
+    }
+}
+
+pub fn dir() -> PathBuf {
+    crate::app_data_dir().join("library")
+}
+
+// All entries, most recently imported first.
+pub fn list() -> Vec<Entry> {
+    let mut entries = load();
+    // The index is in import order; the sort keeps it for equal times
+    entries.reverse();
+    entries.sort_by(|a, b| b.imported.cmp(&a.imported));
+    entries
+}
+
+// Entries whose name, description, device, monitor or hash contain `query`
+// (case-insensitive).
+pub fn search(query: &str) -> Vec<Entry> {
+    list().into_iter().filter(|e| e.matches(query.trim())).collect()
+}
+
+// An entry by hash (or a prefix of at least 6 characters) or exact file name.
+pub fn find(key: &str) -> Result<Entry, String> {
+    let key = key.trim();
+    let entries = list();
+    if let Some(entry) = entries.iter().find(|e| e.hash.eq_ignore_ascii_case(key) || e.name.eq_ignore_ascii_case(key)) {
+        return Ok(entry.clone());
+    }
+    let candidates: Vec<&Entry> = if key.len() >= MIN_PREFIX {
+        entries.iter().filter(|e| e.hash.starts_with(&key.to_ascii_lowercase())).collect()
+    } else {
+        Vec::new()
+    };
+    match candidates[..] {
+        [entry] => Ok(entry.clone()),
+        [] => Err(format!("no library entry matches '{}'", key)),
+        _ => Err(format!("'{}' matches {} library entries", key, candidates.len())),
+    }
+}
+
+// Copies `path` into the library, or returns the entry that already holds the
+// same content. `display` is the monitor the file is being imported for.
+pub fn import(path: &Path, display: Option<&Display>, displays: &[Display]) -> Result<Entry, String> {
+    let data = std::fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let hash = md5::hex(&md5::digest(&data));
+    let mut entries = load();
+    if let Some(existing) = entries.iter().find(|e| e.hash == hash) {
+        if existing.path().exists() {
+            return Ok(existing.clone());
+        }
+        // The copy went missing; import it again
+        entries.retain(|e| e.hash != hash);
+    }
+
+    let name = path.file_name().map(|n| n.to_string_lossy().to_string()).filter(|n| is_plain_name(n));
+    let name = name.ok_or_else(|| format!("{}: not a file name", path.display()))?;
+    let mut entry = Entry {
+        hash,
+        name,
+        calibration: calibration::is_calibration_file(path),
+        description: None,
+        device: None,
+        created: None,
+        has_vcgt: false,
+        monitor: display.map(|d| d.id.clone()).filter(|id| !id.is_empty()),
+        imported: dates::format_iso8601(SystemTime::now()),
+    };
+    if entry.calibration {
+        calibration::load(path)?;
+    } else {
+        let info = Profile::from_bytes(data.clone())?.info();
+        entry.description = info.description.clone();
+        entry.device = info.device();
+        entry.created = info.created.map(|d| d.to_iso8601());
+        entry.has_vcgt = info.has_vcgt;
+        if let Some(named) = entry.device.as_deref().and_then(|device| named_monitor(device, displays)) {
+            entry.monitor = Some(named.id.clone());
+        }
+    }
+
+    let target = entry.path();
+    let folder = target.parent().unwrap_or(&target);
+    std::fs::create_dir_all(folder).map_err(|e| format!("{}: {}", folder.display(), e))?;
+    std::fs::write(&target, &data).map_err(|e| format!("{}: {}", target.display(), e))?;
+    entries.push(entry.clone());
+    save(&entries)?;
+    Ok(entry)
+}
+
+// Records that `hash` is now used on `display`.
+pub fn set_monitor(hash: &str, display: &Display) -> Result<(), String> {
+    if display.id.is_empty() {
+        return Ok(());
+    }
+    let mut entries = load();
+    let Some(entry) = entries.iter_mut().find(|e| e.hash == hash) else { return Ok(()) };
+    if entry.monitor.as_deref() == Some(display.id.as_str()) {
+        return Ok(());
+    }
+    entry.monitor = Some(display.id.clone());
+    save(&entries)
+}
+
+// Removes an entry and its file. Entries still loaded as a display's
+// calibration source are kept.
+pub fn delete(key: &str, displays: &[Display]) -> Result<Entry, String> {
+    let entry = find(key)?;
+    let path = entry.path();
+    if let Some(user) = displays.iter().find(|d| calibration::source_for(d).is_some_and(|s| s == path)) {
+        return Err(format!("{} is the calibration of {}", entry.name, user.device));
+    }
+    let mut entries = load();
+    entries.retain(|e| e.hash != entry.hash);
+    save(&entries)?;
+    let folder = dir().join(&entry.hash);
+    if folder.exists() {
+        std::fs::remove_dir_all(&folder).map_err(|e| format!("{}: {}", folder.display(), e))?;
+    }
+    Ok(entry)
+}
+
+// The entry holding exactly the content of `path`, if any.
+pub fn entry_for_file(path: &Path) -> Option<Entry> {
+    let hash = md5::hex(&md5::digest(&std::fs::read(path).ok()?));
+    list().into_iter().find(|e| e.hash == hash)
+}
+
+// Text table for `library list` and `library search`.
+pub fn format_text(entries: &[Entry]) -> String {
+    if entries.is_empty() {
+        return "library is empty\n".to_string();
+    }
+    let mut out = String::new();
+    for e in entries {
+        let kind = if e.calibration { "cal" } else if e.has_vcgt { "icc+vcgt" } else { "icc" };
+        out.push_str(&format!("{}  {:<8}  {}  ({})\n", e.short_hash(), kind, e.name, e.title()));
+        let device = e.device.as_deref().unwrap_or("-");
+        let created = e.created.as_deref().unwrap_or("-");
+        out.push_str(&format!("          device: {}  created: {}  imported: {}\n", device, created, e.imported));
+        if let Some(monitor) = &e.monitor {
+            out.push_str(&format!("          monitor: {}\n", monitor));
+        }
+    }
+    out
+}
+
+// Entries in index (import) order.
+fn load() -> Vec<Entry> {
+    std::fs::read_to_string(dir().join("index.json"))
+        .ok()
+        .and_then(|text| json::parse(&text).ok())
+        .and_then(|index| index.as_array().map(|a| a.iter().filter_map(Entry::from_json).collect()))
+        .unwrap_or_default()
+}
+
+fn save(entries: &[Entry]) -> Result<(), String> {
+    let dir = dir();
+    std::fs::create_dir_all(&dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    let index = Value::Array(entries.iter().map(Entry::to_json).collect());
+    // Written next to the index first so a crash never leaves half a file
+    let temp = dir.join("index.json.tmp");
+    std::fs::write(&temp, index.pretty()).map_err(|e| format!("{}: {}", temp.display(), e))?;
+    std::fs::rename(&temp, dir.join("index.json")).map_err(|e| format!("{}: {}", dir.display(), e))
+}
+
+// The connected monitor a profile's device description refers to, e.g.
+// "DELL U2720Q" for a monitor named "DELL U2720Q(DisplayPort)".
+fn named_monitor<'a>(device: &str, displays: &'a [Display]) -> Option<&'a Display> {
+    let device = device.to_lowercase();
+    displays.iter().find(|d| {
+        let name = d.name.to_lowercase();
+        let name = name.split('(').next().unwrap_or("").trim();
+        !name.is_empty() && !name.starts_with("generic") && (device.contains(name) || name.contains(&device))
+    })
+}
+
+// Index entries must not point outside their folder.
+fn is_plain_name(name: &str) -> bool {
+    !name.is_empty() && !name.contains(['/', '\\', ':']) && name != "." && name != ".."
+}
@@ -0,0 +1,371 @@
+// --- ICC Lint ---
+//
+// Structural checks for profiles we are asked to install. Windows and dispwin
+// reject broken profiles or load them inconsistently, so problems are
+// reported up front:
+//
+//   error     the profile is broken or incomplete (bad size, tags outside
+//             the file, duplicate tags, required tags missing, wrong ID)
+//   warning   against the spec but usually tolerated (misaligned tags,
+//             overlapping tag data, odd version, undecodable vcgt)
+//   info      worth knowing (no profile ID recorded)
+//
+// `repair` fixes what can be fixed without guessing: it rewrites the file
+// with the ICC writer (size, alignment, overlaps, ID) and drops duplicate
+// tags, keeping the first one as readers do. Missing tags or tags pointing
+// outside the file are left to the user.
+
+use std::path::{Path, PathBuf};
+
+use crate::icc::{self, Profile};
+use crate::icc_writer;
+use crate::json::Value;
+
+const HEADER_SIZE: usize = 128;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+impl Severity {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Info => "info",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Issue {
+    pub severity: Severity,
+    // Stable identifier for scripts, e.g. `header_size`
+    pub code: &'static str,
+    pub message: String,
+    pub repairable: bool,
+}
+
+impl Issue {
+    fn new(severity: Severity, code: &'static str, message: String, repairable: bool) -> Issue {
+        Issue { severity, code, message, repairable }
+    }
+
+    pub fn to_json(&self) -> Value {
+        Value::object(vec![
+            ("severity", Value::from(self.severity.name())),
+            ("code", Value::from(self.code)),
+            ("message", Value::from(self.message.as_str())),
+            ("repairable", Value::from(self.repairable)),
+        ])
+    }
+}
+
+pub fn check_file(path: &Path) -> Result<Vec<Issue>, String> {
+    let data = std::fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(check(&data))
+}
+
+// Issues sorted by severity, errors first.
+pub fn check(data: &[u8]) -> Vec<Issue> {
+    let profile = match Profile::from_bytes(data.to_vec()) {
+        Ok(p) => p,
+        Err(e) => return vec![Issue::new(Severity::Error, "unreadable", e, false)],
+    };
+    let mut issues = Vec::new();
+    let mut add = |severity, code, message: String, repairable| issues.push(Issue::new(severity, code, message, repairable));
+
+    let declared = icc::read_u32(data, 0).unwrap_or(0) as usize;
+    if declared != data.len() {
+        add(Severity::Error, "header_size", format!("header size is {} but the file has {} bytes", declared, data.len()), true);
+    }
+    if !data.len().is_multiple_of(4) {
+        add(Severity::Warning, "file_padding", format!("file size {} is not a multiple of 4", data.len()), true);
+    }
+
+    let version = profile.version();
+    if !matches!(version.0, 2 | 4) {
+        add(Severity::Warning, "version", format!("unknown profile version {}.{}.{}", version.0, version.1, version.2), false);
+    }
+
+    let table_end = HEADER_SIZE + 4 + profile.tags().len() * 12;
+    for (i, tag) in profile.tags().iter().enumerate() {
+        let name = icc::signature_string(&tag.signature);
+        let end = tag.offset.checked_add(tag.size);
+        if end.is_none_or(|end| end > data.len()) {
+            add(Severity::Error, "tag_bounds", format!("tag '{}' ends past the end of the file", name), false);
+            continue;
+        }
+        if tag.offset < table_end {
+            add(Severity::Error, "tag_offset", format!("tag '{}' starts inside the header or tag table", name), false);
+            continue;
+        }
+        if !tag.offset.is_multiple_of(4) {
+            add(Severity::Warning, "tag_alignment", format!("tag '{}' is not 4-byte aligned", name), true);
+        }
+        if profile.tags()[..i].iter().any(|t| t.signature == tag.signature) {
+            add(Severity::Error, "duplicate_tag", format!("tag '{}' appears more than once", name), true);
+        }
+        // Sharing identical data is allowed; partial overlaps are not
+        let overlaps = profile.tags()[..i].iter().any(|t| {
+            let same = t.offset == tag.offset && t.size == tag.size;
+            !same && t.offset < tag.offset + tag.size && tag.offset < t.offset + t.size
+        });
+        if overlaps {
+            add(Severity::Warning, "tag_overlap", format!("tag '{}' overlaps another tag's data", name), true);
+        }
+    }
+
+    for missing in required_tags(&profile).into_iter().filter(|group| !group.iter().any(|sig| profile.has_tag(sig))) {
+        let names: Vec<String> = missing.iter().map(|sig| format!("'{}'", icc::signature_string(sig))).collect();
+        add(Severity::Error, "missing_tag", format!("required tag {} is missing", names.join(" or ")), false);
+    }
+
+    if profile.has_tag(b"vcgt") && profile.vcgt().is_none() {
+        add(Severity::Warning, "vcgt", "the 'vcgt' tag cannot be decoded".to_string(), false);
+    }
+
+    let stored = &data[84..100];
+    if stored.iter().any(|&b| b != 0) {
+        if stored != icc::compute_profile_id(data) {
+            let severity = if version.0 >= 4 { Severity::Error } else { Severity::Warning };
+            add(severity, "profile_id", "profile ID does not match the content".to_string(), true);
+        }
+    } else if version.0 >= 4 {
+        add(Severity::Info, "profile_id", "no profile ID recorded".to_string(), true);
+    }
+
+    issues.sort_by_key(|i| i.severity);
+    issues
+}
+
+pub fn has_errors(issues: &[Issue]) -> bool {
+    issues.iter().any(|i| i.severity == Severity::Error)
+}
+
+// Writes a repaired copy of `path` and returns where, with the issues the copy
+// still has.
+pub fn repair(path: &Path, output: Option<&Path>) -> Result<(PathBuf, Vec<Issue>), String> {
+    let profile = Profile::load(path)?;
+    let issues = check(profile.bytes());
+    if !issues.iter().any(|i| i.repairable) {
+        return Err("nothing to repair".to_string());
+    }
+    let bad_bounds: Vec<&Issue> = issues.iter().filter(|i| matches!(i.code, "tag_bounds" | "tag_offset")).collect();
+    if let Some(issue) = bad_bounds.first() {
+        return Err(format!("cannot repair safely: {}", issue.message));
+    }
+
+    let mut tags = icc_writer::tags_of(&profile)?;
+    let mut seen: Vec<[u8; 4]> = Vec::new();
+    tags.retain(|(sig, _)| {
+        let first = !seen.contains(sig);
+        seen.push(*sig);
+        first
+    });
+    let data = icc_writer::build(profile.bytes(), &tags);
+
+    let output = output.map_or_else(|| repaired_path(path), Path::to_path_buf);
+    if icc_writer::same_file(&output, path) {
+        return Err("the repaired profile must be written to a new file".to_string());
+    }
+    std::fs::write(&output, &data).map_err(|e| format!("{}: {}", output.display(), e))?;
+    Ok((output, check(&data)))
+}
+
+// Text for `profile lint` and the install error.
+pub fn format_text(issues: &[Issue]) -> String {
+    if issues.is_empty() {
+        return "no issues found\n".to_string();
+    }
+    issues
+        .iter()
+        .map(|i| {
+            let note = if i.repairable { " (repairable)" } else { "" };
+            format!("{:<8} {:<14} {}{}\n", i.severity.name(), i.code, i.message, note)
+        })
+        .collect()
+}
+
+fn repaired_path(path: &Path) -> PathBuf {
+    let stem = path.file_stem().map_or("profile".to_string(), |s| s.to_string_lossy().to_string());
+    let ext = path.extension().map_or("icc".to_string(), |e| e.to_string_lossy().to_string());
+    path.with_file_name(format!("{}-repaired.{}", stem, ext))
+}
+
+// Tags each class must have; one tag of every inner list is enough.
+fn required_tags(profile: &Profile) -> Vec<Vec<[u8; 4]>> {
+    let mut required = vec![vec![*b"desc"], vec![*b"cprt"], vec![*b"wtpt"]];
+    let matrix_or_lut = |required: &mut Vec<Vec<[u8; 4]>>| {
+        if profile.has_tag(b"A2B0") {
+            return;
+        }
+        let gray = &profile.color_space() == b"GRAY";
+        let tags: &[&[u8; 4]] = if gray { &[b"kTRC"] } else { &[b"rXYZ", b"gXYZ", b"bXYZ", b"rTRC", b"gTRC", b"bTRC"] };
+        required.extend(tags.iter().map(|t| vec![**t, *b"A2B0"]));
+    };
+    match &profile.class() {
+        b"mntr" | b"scnr" => matrix_or_lut(&mut required),
+        b"prtr" => required.extend([b"A2B0", b"B2A0", b"A2B1", b"B2A1", b"A2B2", b"B2A2", b"gamt"].map(|t| vec![*t])),
+        b"spac" => required.extend([vec![*b"A2B0"], vec![*b"B2A0"]]),
+        b"abst" => required.push(vec![*b"A2B0"]),
+        b"link" => {
+            required.retain(|t| t[0] != *b"wtpt");
+            required.extend([vec![*b"A2B0"], vec![*b"pseq"]]);
+        }
+        b"nmcl" => required.push(vec![*b"ncl2"]),
+        _ => {}
+    }
+    required
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::icc_writer::Tags;
+
+    fn header(major: u8) -> Vec<u8> {
+        let mut header = vec![0; HEADER_SIZE];
+        header[8] = major;
+        header[12..16].copy_from_slice(b"mntr");
+        header[16..20].copy_from_slice(b"RGB ");
+        header[36..40].copy_from_slice(b"acsp");
+        header
+    }
+
+    fn tag(kind: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        [kind.as_slice(), &[0; 4], body].concat()
+    }
+
+    // A minimal display profile; `bTRC` comes last and is not shared, so the
+    // tests can move it around
+    fn display_tags() -> Tags {
+        let xyz = |n: u8| tag(b"XYZ ", &[0, 0, n, 0, 0, 1, 0, 0, 0, 0, n, 0]);
+        let curve = |gamma: u8| tag(b"curv", &[0, 0, 0, 1, gamma, 0]);
+        [(b"desc", tag(b"text", b"Display\0")), (b"cprt", tag(b"text", b"None\0")), (b"wtpt", xyz(1))]
+            .into_iter()
+            .chain([(b"rXYZ", xyz(2)), (b"gXYZ", xyz(3)), (b"bXYZ", xyz(4))])
+            .chain([(b"rTRC", curve(2)), (b"gTRC", curve(2)), (b"bTRC", curve(3))])
+            .map(|(sig, data)| (*sig, data))
+            .collect()
+    }
+
+    fn profile(major: u8) -> Vec<u8> {
+        icc_writer::build(&header(major), &display_tags())
+    }
+
+    // Offset of the `field`th u32 (0 signature, 1 offset, 2 size) of tag table entry `i`
+    fn entry(i: usize, field: usize) -> usize {
+        HEADER_SIZE + 4 + i * 12 + field * 4
+    }
+
+    fn set_u32(data: &mut [u8], offset: usize, value: u32) {
+        data[offset..offset + 4].copy_from_slice(&value.to_be_bytes());
+    }
+
+    fn add_u32(data: &mut [u8], offset: usize, delta: i64) {
+        let value = icc::read_u32(data, offset).unwrap() as i64 + delta;
+        set_u32(data, offset, value as u32);
+    }
+
+    // Drops the last `bytes` of the file, keeping the header size right
+    fn truncate(data: &mut Vec<u8>, bytes: usize) {
+        let len = data.len() - bytes;
+        data.truncate(len);
+        set_u32(data, 0, len as u32);
+    }
+
+    fn codes(issues: &[Issue]) -> Vec<(Severity, &'static str)> {
+        let mut codes: Vec<(Severity, &'static str)> = issues.iter().map(|i| (i.severity, i.code)).collect();
+        codes.dedup();
+        codes
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("lint-test-{}-{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn clean_profiles_have_no_issues() {
+        assert_eq!(check(&profile(2)), []);
+        assert_eq!(check(&profile(4)), []);
+    }
+
+    fn rebuilt(data: &mut Vec<u8>, change: fn(&mut Tags)) {
+        let mut tags = display_tags();
+        change(&mut tags);
+        *data = icc_writer::build(data, &tags);
+    }
+
+    #[test]
+    fn one_case_per_issue_code() {
+        use Severity::*;
+        const LAST: usize = 8;
+        type Change = fn(&mut Vec<u8>);
+        type Expected = &'static [(Severity, &'static str)];
+        // (version, change to the clean profile, issues expected)
+        let cases: [(u8, Change, Expected); 14] = [
+            (2, |d| d.truncate(100), &[(Error, "unreadable")]),
+            (2, |d| add_u32(d, 0, 4), &[(Error, "header_size")]),
+            (2, |d| truncate(d, 2), &[(Warning, "file_padding")]),
+            (2, |d| d[8] = 3, &[(Warning, "version")]),
+            (2, |d| add_u32(d, entry(LAST, 2), 100), &[(Error, "tag_bounds")]),
+            (2, |d| set_u32(d, entry(0, 1), 64), &[(Error, "tag_offset")]),
+            (2, |d| add_u32(d, entry(LAST, 1), 1), &[(Warning, "tag_alignment")]),
+            (2, |d| rebuilt(d, |t| t.push((*b"desc", tag(b"text", b"Other\0")))), &[(Error, "duplicate_tag")]),
+            (2, |d| add_u32(d, entry(LAST, 1), -4), &[(Warning, "tag_overlap")]),
+            (2, |d| rebuilt(d, |t| t.retain(|(sig, _)| sig != b"cprt")), &[(Error, "missing_tag")]),
+            (2, |d| rebuilt(d, |t| t.push((*b"vcgt", tag(b"vcgt", &[0, 0, 0, 7])))), &[(Warning, "vcgt")]),
+            (2, |d| d[84..100].fill(1), &[(Warning, "profile_id")]),
+            (4, |d| d[100] = 1, &[(Error, "profile_id")]),
+            (4, |d| d[84..100].fill(0), &[(Info, "profile_id")]),
+        ];
+        for (major, change, expected) in cases {
+            let mut data = profile(major);
+            change(&mut data);
+            assert_eq!(codes(&check(&data)), expected, "expected {:?}", expected);
+        }
+    }
+
+    #[test]
+    fn repair_writes_a_clean_copy() {
+        let mut tags = display_tags();
+        tags.insert(1, (*b"desc", tag(b"text", b"Second\0")));
+        let mut data = icc_writer::build(&header(4), &tags);
+        add_u32(&mut data, 0, 8);
+        data.extend([0, 0]);
+        let source = temp_path("broken.icc");
+        std::fs::write(&source, &data).unwrap();
+
+        let output = temp_path("repaired.icc");
+        let (written, remaining) = repair(&source, Some(&output)).unwrap();
+        assert_eq!(written, output);
+        assert_eq!(remaining, []);
+        assert_eq!(std::fs::read(&source).unwrap(), data);
+        let repaired = Profile::load(&output).unwrap();
+        assert_eq!(repaired.tags().len(), display_tags().len());
+        assert_eq!(repaired.text(b"desc").as_deref(), Some("Display"));
+
+        assert_eq!(repair(&output, None).unwrap_err(), "nothing to repair");
+        assert_eq!(repair(&source, Some(&source)).unwrap_err(), "the repaired profile must be written to a new file");
+        for path in [source, output] {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+
+    #[test]
+    fn repair_refuses_tags_outside_the_file() {
+        let mut data = profile(2);
+        add_u32(&mut data, 0, 4);
+        add_u32(&mut data, entry(0, 1), 4096);
+        let source = temp_path("outside.icc");
+        std::fs::write(&source, &data).unwrap();
+        let error = repair(&source, Some(&temp_path("outside-repaired.icc"))).unwrap_err();
+        assert_eq!(error, "cannot repair safely: tag 'desc' ends past the end of the file");
+        assert!(!temp_path("outside-repaired.icc").exists());
+        let _ = std::fs::remove_file(source);
+    }
+}
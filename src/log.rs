@@ -0,0 +1,53 @@
+// --- Logging ---
+//
+// Appends timestamped lines to `%LOCALAPPDATA%\IccAutoReloader\icc_auto_reloader.log`.
+// In headless mode the same lines are echoed to the attached console.
+
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use windows::Win32::System::SystemInformation::GetLocalTime;
+
+static ECHO: AtomicBool = AtomicBool::new(false);
+static WRITE_LOCK: Mutex<()> = Mutex::new(());
+
+pub fn set_echo(enabled: bool) {
+    ECHO.store(enabled, Ordering::Relaxed);
+}
+
+pub fn info(msg: &str) {
+    write("INFO", msg);
+}
+
+pub fn warn(msg: &str) {
+    write("WARN", msg);
+}
+
+pub fn error(msg: &str) {
+    write("ERROR", msg);
+}
+
+fn write(level: &str, msg: &str) {
+    let line = format!("{} [{}] {}", timestamp(), level, msg);
+    let _guard = WRITE_LOCK.lock();
+
+    if ECHO.load(Ordering::Relaxed) {
+        eprintln!("{}", line);
+    }
+
+    let dir = crate::app_data_dir();
+    let _ = std::fs::create_dir_all(&dir);
+    if let Ok(mut file) = OpenOptions::new().create(true).append(true).open(dir.join("icc_auto_reloader.log")) {
+        let _ = writeln!(file, "{}", line);
+    }
+}
+
+fn timestamp() -> String {
+    let t = unsafe { GetLocalTime() };
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        t.wYear, t.wMonth, t.wDay, t.wHour, t.wMinute, t.wSecond
+    )
+}
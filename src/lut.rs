@@ -0,0 +1,250 @@
+// --- 1D LUT Files ---
+//
+// Calibration curves written by hardware calibration tools, as opposed to
+// ArgyllCMS `.cal` files:
+//
+//   .cube   1D LUT (Adobe/Resolve): `LUT_1D_SIZE n`, optional `DOMAIN_MIN` /
+//           `DOMAIN_MAX` (or Resolve's `LUT_1D_INPUT_RANGE`), then n lines of
+//           `r g b`
+//   .csv    one row per entry with `r,g,b` or `input,r,g,b`, or a single
+//           column used for all channels; `;` and tabs work as separators and
+//           a header line is skipped. Values above 1 are taken as 8, 10, 12 or
+//           16-bit integers.
+//
+// Both are resampled to the 256-entry ramp. Curves must stay in range and
+// must not decrease.
+
+use std::path::Path;
+
+use crate::display::Ramp;
+use crate::ramp;
+
+pub fn load_cube(path: &Path) -> Result<Ramp, String> {
+    parse_cube(&read(path)?)
+}
+
+pub fn load_csv(path: &Path) -> Result<Ramp, String> {
+    parse_csv(&read(path)?)
+}
+
+pub fn parse_cube(text: &str) -> Result<Ramp, String> {
+    let mut size = None;
+    let (mut min, mut max) = ([0.0; 3], [1.0; 3]);
+    let mut rows: Vec<[f64; 3]> = Vec::new();
+
+    for (number, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        let mut tokens = line.split_whitespace();
+        let Some(first) = tokens.next() else { continue };
+        let error = |message: &str| format!("line {}: {}", number + 1, message);
+        let numbers = |tokens: std::str::SplitWhitespace| -> Result<Vec<f64>, String> {
+            tokens.map(|t| t.parse::<f64>().map_err(|_| error(&format!("not a number: {}", t)))).collect()
+        };
+
+        match first {
+            "TITLE" => {}
+            "LUT_3D_SIZE" => return Err("3D LUTs cannot be loaded as a calibration".to_string()),
+            "LUT_1D_SIZE" => {
+                let value = tokens.next().and_then(|t| t.parse::<usize>().ok()).filter(|&n| n >= 2);
+                size = Some(value.ok_or_else(|| error("invalid LUT_1D_SIZE"))?);
+            }
+            "DOMAIN_MIN" | "DOMAIN_MAX" => {
+                let values: [f64; 3] = numbers(tokens)?.try_into().map_err(|_| error("expected three values"))?;
+                if first == "DOMAIN_MIN" {
+                    min = values;
+                } else {
+                    max = values;
+                }
+            }
+            "LUT_1D_INPUT_RANGE" => {
+                let values = numbers(tokens)?;
+                let [low, high] = values[..] else { return Err(error("expected two values")) };
+                (min, max) = ([low; 3], [high; 3]);
+            }
+            _ if first.starts_with(|c: char| c.is_ascii_alphabetic()) => {}
+            _ => {
+                let values: Vec<f64> = numbers(line.split_whitespace())?;
+                rows.push(values.try_into().map_err(|_| error("expected three values"))?);
+            }
+        }
+    }
+
+    let size = size.ok_or_else(|| "missing LUT_1D_SIZE".to_string())?;
+    if rows.len() != size {
+        return Err(format!("LUT_1D_SIZE is {} but the file has {} entries", size, rows.len()));
+    }
+    if (0..3).any(|c| max[c] <= min[c]) {
+        return Err("DOMAIN_MAX must be above DOMAIN_MIN".to_string());
+    }
+
+    // Each channel's entries are spread evenly over its domain; inputs of the
+    // ramp outside the domain take the nearest entry
+    let curves = (0..3)
+        .map(|c| {
+            let inputs: Vec<f64> = (0..size).map(|i| min[c] + (max[c] - min[c]) * i as f64 / (size - 1) as f64).collect();
+            let outputs: Vec<f64> = rows.iter().map(|r| r[c]).collect();
+            (inputs, outputs)
+        })
+        .collect();
+    to_ramp(curves)
+}
+
+pub fn parse_csv(text: &str) -> Result<Ramp, String> {
+    let mut rows: Vec<Vec<f64>> = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let fields: Vec<&str> = line.split([',', ';', '\t']).map(str::trim).filter(|f| !f.is_empty()).collect();
+        if fields.is_empty() {
+            continue;
+        }
+        match fields.iter().map(|f| f.parse::<f64>()).collect::<Result<Vec<f64>, _>>() {
+            Ok(values) => rows.push(values),
+            // Column names
+            Err(_) if rows.is_empty() => continue,
+            Err(_) => return Err(format!("line {}: not a number", number + 1)),
+        }
+    }
+
+    let columns = rows.first().map_or(0, Vec::len);
+    if rows.len() < 2 {
+        return Err("curve needs at least two entries".to_string());
+    }
+    if !matches!(columns, 1 | 3 | 4) || rows.iter().any(|r| r.len() != columns) {
+        return Err("expected 1, 3 or 4 columns on every line".to_string());
+    }
+
+    let scale = integer_scale(rows.iter().flat_map(|r| r.iter().skip(if columns == 4 { 1 } else { 0 })).copied());
+    let column = |c: usize, scale: f64| -> Vec<f64> { rows.iter().map(|r| r[c] / scale).collect() };
+    let evenly = |n: usize| -> Vec<f64> { (0..n).map(|i| i as f64 / (n - 1) as f64).collect() };
+
+    let curves = match columns {
+        1 => vec![(evenly(rows.len()), column(0, scale))],
+        3 => (0..3).map(|c| (evenly(rows.len()), column(c, scale))).collect(),
+        _ => {
+            let inputs = column(0, integer_scale(rows.iter().map(|r| r[0])));
+            (1..4).map(|c| (inputs.clone(), column(c, scale))).collect()
+        }
+    };
+    to_ramp(curves)
+}
+
+fn read(path: &Path) -> Result<String, String> {
+    std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+// Divisor bringing integer-coded values to 0..1, guessed from the largest.
+fn integer_scale(values: impl Iterator<Item = f64>) -> f64 {
+    let max = values.fold(0.0, f64::max);
+    [1.0, 255.0, 1023.0, 4095.0].into_iter().find(|&scale| max <= scale + 1e-6).unwrap_or(65535.0)
+}
+
+// Validates (inputs, outputs) per channel and resamples them to 256 entries.
+fn to_ramp(curves: Vec<(Vec<f64>, Vec<f64>)>) -> Result<Ramp, String> {
+    const NAMES: [&str; 3] = ["red", "green", "blue"];
+    let mut resampled = Vec::new();
+    for (c, (inputs, outputs)) in curves.iter().enumerate() {
+        let name = if curves.len() == 1 { "curve" } else { NAMES[c] };
+        if inputs.windows(2).any(|w| w[1] <= w[0]) {
+            return Err(format!("{}: inputs are not strictly increasing", name));
+        }
+        if outputs.iter().any(|v| !(-0.001..=1.001).contains(v)) {
+            return Err(format!("{}: values outside 0..1", name));
+        }
+        if let Some(i) = outputs.windows(2).position(|w| w[1] < w[0] - 1e-6) {
+            return Err(format!("{}: decreases after entry {}", name, i + 1));
+        }
+        resampled.push((0..256).map(|i| ramp::interpolate(inputs, outputs, i as f64 / 255.0)).collect());
+    }
+    ramp::from_curves(&resampled).ok_or_else(|| "curve is empty".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Channel, ramp index and the value expected there
+    type Entry = (usize, usize, f64);
+
+    fn level(value: f64) -> u16 {
+        (value * 65535.0).round() as u16
+    }
+
+    #[test]
+    fn reads_cube_domains() {
+        let cases: [(&str, &[Entry]); 4] = [
+            ("TITLE \"x\"\nLUT_1D_SIZE 2\n0 0 0\n1 0.5 1\n", &[(0, 255, 1.0), (1, 255, 0.5), (2, 0, 0.0)]),
+            ("# comment\nLUT_1D_SIZE 3\n0 0 0\n0.25 0.25 0.25 # mid\n1 1 1\n", &[(0, 0, 0.0), (1, 255, 1.0)]),
+            // Everything above 0.5 takes the last entry
+            ("LUT_1D_SIZE 2\nLUT_1D_INPUT_RANGE 0 0.5\n0 0 0\n1 1 1\n", &[(0, 127, 254.0 / 255.0), (0, 200, 1.0)]),
+            (
+                "LUT_1D_SIZE 2\nDOMAIN_MIN 0 0 0.5\nDOMAIN_MAX 1 1 1\n0.2 0.2 0.2\n1 1 1\n",
+                &[(0, 0, 0.2), (2, 0, 0.2), (2, 127, 0.2)],
+            ),
+        ];
+        for (text, entries) in cases {
+            let ramp = parse_cube(text).unwrap();
+            for &(c, i, expected) in entries {
+                assert_eq!(ramp[c][i], level(expected), "{:?} channel {} entry {}", text, c, i);
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_bad_cubes() {
+        let cases = [
+            ("0 0 0\n1 1 1\n", "missing LUT_1D_SIZE"),
+            ("LUT_3D_SIZE 17\n", "3D LUTs cannot be loaded as a calibration"),
+            ("LUT_1D_SIZE 1\n0 0 0\n", "line 1: invalid LUT_1D_SIZE"),
+            ("LUT_1D_SIZE 3\n0 0 0\n1 1 1\n", "LUT_1D_SIZE is 3 but the file has 2 entries"),
+            ("LUT_1D_SIZE 2\n0 0\n1 1 1\n", "line 2: expected three values"),
+            ("LUT_1D_SIZE 2\n0 0 0\n1 x 1\n", "line 3: not a number: x"),
+            ("LUT_1D_SIZE 2\nDOMAIN_MAX 1 0 1\n0 0 0\n1 1 1\n", "DOMAIN_MAX must be above DOMAIN_MIN"),
+            ("LUT_1D_SIZE 2\nLUT_1D_INPUT_RANGE 0\n0 0 0\n1 1 1\n", "line 2: expected two values"),
+            ("LUT_1D_SIZE 2\n0 0 0\n1.5 1 1\n", "red: values outside 0..1"),
+            ("LUT_1D_SIZE 3\n0 0 0\n0.5 0.6 0.5\n1 0.5 1\n", "green: decreases after entry 2"),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(parse_cube(text).map(|_| ()), Err(expected.to_string()), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn detects_csv_integer_scale() {
+        // (file, expected value of the last red entry)
+        let cases = [
+            ("r,g,b\n0,0,0\n1,1,1\n", 1.0),
+            ("0\n128\n255\n", 1.0),
+            ("0;0;0\n200;255;255\n", 200.0 / 255.0),
+            ("0\t0\t0\n1023\t512\t512\n", 1.0),
+            ("0,0,0\n4095,4095,4095\n", 1.0),
+            ("0,0,0\n32768,65535,65535\n", 32768.0 / 65535.0),
+            // The input column is scaled on its own
+            ("in,r,g,b\n0,0,0,0\n1023,0.5,1,1\n", 0.5),
+        ];
+        for (text, expected) in cases {
+            let ramp = parse_csv(text).unwrap();
+            assert_eq!(ramp[0][255], level(expected), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn single_column_applies_to_all_channels() {
+        let ramp = parse_csv("0\n0.5\n1\n").unwrap();
+        assert_eq!(ramp[0], ramp[1]);
+        assert_eq!(ramp[1], ramp[2]);
+    }
+
+    #[test]
+    fn rejects_bad_csv() {
+        let cases = [
+            ("0,0,0\n", "curve needs at least two entries"),
+            ("0,0\n1,1\n", "expected 1, 3 or 4 columns on every line"),
+            ("0,0,0\n1,1\n", "expected 1, 3 or 4 columns on every line"),
+            ("0,0,0\nx,1,1\n", "line 2: not a number"),
+            ("0\n0.8\n0.6\n1\n", "curve: decreases after entry 2"),
+            ("0,0,0,0\n0.5,0,0,0\n0.5,1,1,1\n", "red: inputs are not strictly increasing"),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(parse_csv(text).map(|_| ()), Err(expected.to_string()), "{:?}", text);
+        }
+    }
+}
@@ -3,225 +3,164 @@
 extern crate native_windows_gui as nwg;
 extern crate native_windows_derive as nwd;
 
+mod accuracy;
+mod age;
+mod app_rules;
+mod cal;
+mod calibration;
+mod cgats;
+mod cli;
+mod color;
+mod dates;
+mod display;
+mod engine;
+mod events;
+mod export;
+mod fullscreen;
+mod gamut;
+mod headless;
+mod hooks;
+mod i18n;
+mod icc;
+mod icc_writer;
+mod ipc;
+mod json;
+mod library;
+mod lint;
+mod log;
+mod lut;
+mod md5;
+mod presets;
+mod ramp;
+mod rpc;
+mod schedule;
+mod status;
+
 use nwd::NwgUi;
 use nwg::NativeUi;
 use std::cell::RefCell;
 use std::path::{Path, PathBuf};
 use std::process::Command;
-use std::sync::atomic::{AtomicUsize, AtomicU32, AtomicBool, Ordering};
+use std::sync::atomic::{AtomicU32, AtomicBool, Ordering};
+use std::sync::{mpsc, Mutex};
 use std::os::windows::process::CommandExt;
 use std::time::Duration;
-use std::ffi::c_void;
 
 // Keep windows crate for low-level system calls
-use windows::core::{PCWSTR, PCSTR, w};
-use windows::Win32::Foundation::{BOOL, TRUE, ERROR_ALREADY_EXISTS};
+use windows::core::{PCWSTR, w};
+use windows::Win32::Foundation::{TRUE, ERROR_ALREADY_EXISTS};
 use windows::Win32::System::Threading::CreateMutexW;
 use windows::Win32::System::Registry::*;
-use windows::Win32::Graphics::Gdi::{GetDC, ReleaseDC, HDC};
-use windows::Win32::System::LibraryLoader::{GetModuleHandleW, GetProcAddress};
 use windows::Win32::UI::WindowsAndMessaging::{SetMenuItemInfoW, MENUITEMINFOW, MessageBoxW, MB_YESNO, MB_ICONWARNING, IDYES};
 use windows::Win32::UI::HiDpi::{SetProcessDpiAwarenessContext, DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2};
+use windows::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+use windows::Win32::Security::Cryptography::{BCryptGenRandom, BCRYPT_USE_SYSTEM_PREFERRED_RNG};
+
+use cli::{CliCommand, LibraryCommand, PresetCommand, ProfileCommand};
+use events::Event;
+use export::RampSource;
+use i18n::Msg;
+use ipc::{Request, Response};
 
 // --- Global State ---
-static CURRENT_LANG: AtomicUsize = AtomicUsize::new(0);
 static POLLING_INTERVAL_MS: AtomicU32 = AtomicU32::new(3000);
 static SETTINGS_OPEN: AtomicBool = AtomicBool::new(false);
+static LIBRARY_OPEN: AtomicBool = AtomicBool::new(false);
+
+// Library entries listed directly in the tray menu; the rest are in the
+// library window
+const TRAY_LIBRARY_ENTRIES: usize = 15;
+
+thread_local! {
+    // Menu item picked last, handed from the raw event handler to `dynamic_menu_pick`
+    static MENU_PICK: std::cell::Cell<Option<nwg::ControlHandle>> = const { std::cell::Cell::new(None) };
+}
 
 const APP_NAME: PCWSTR = w!("IccAutoReloader");
 const REG_SUBKEY: PCWSTR = w!(r"Software\IccAutoReloader");
 const REG_RUN_KEY: PCWSTR = w!(r"Software\Microsoft\Windows\CurrentVersion\Run");
 
 // --- Localization ---
-fn get_str(id: &str) -> String {
-    let lang = CURRENT_LANG.load(Ordering::Relaxed);
-    let s = match id {
-        "tray_reload" => match lang {
-            1 => "重新加载校准",
-            2 => "キャリブレーションを再読み込み",
-            _ => "Reload Calibration",
-        },
-        "tray_import" => match lang {
-            1 => "导入 ICC 配置文件...",
-            2 => "ICCプロファイルをインポート...",
-            _ => "Import ICC Profile...",
-        },
-        "tray_polling" => match lang {
-            1 => "启用自动重新加载 (轮询)",
-            2 => "自動再読み込みを有効化 (ポーリング)",
-            _ => "Enable Auto-Reload (Polling)",
-        },
-        "tray_startup" => match lang {
-            1 => "开机启动",
-            2 => "スタートアップに登録",
-            _ => "Run at Startup",
-        },
-        "tray_settings" => match lang {
-            1 => "设置...",
-            2 => "設定...",
-            _ => "Settings...",
-        },
-        "tray_exit" => match lang {
-            1 => "退出",
-            2 => "終了",
-            _ => "Exit",
-        },
-        "settings_title" => match lang {
-            1 => "设置",
-            2 => "設定",
-            _ => "Settings",
-        },
-        "lbl_interval" => match lang {
-            1 => "轮询间隔 (秒):",
-            2 => "ポーリング間隔 (秒):",
-            _ => "Polling Interval (seconds):",
-        },
-        "lbl_lang" => match lang {
-            1 => "语言:",
-            2 => "言語:",
-            _ => "Language:",
-        },
-        "btn_save" => match lang {
-            1 => "保存",
-            2 => "保存",
-            _ => "Save",
-        },
-        "msg_install_success" => match lang {
-            1 => "配置文件已安装并加载成功。",
-            2 => "プロファイルが正常にインストールされ、読み込まれました。",
-            _ => "Profile installed and loaded successfully.",
-        },
-        "msg_install_fail" => match lang {
-            1 => "安装配置文件失败。",
-            2 => "プロファイルのインストールに失敗しました。",
-            _ => "Failed to install profile.",
-        },
-        "msg_dispwin_missing" => match lang {
-            1 => "未找到 dispwin.exe",
-            2 => "dispwin.exe が見つかりません",
-            _ => "Could not find dispwin.exe",
-        },
-        "msg_select_profile" => match lang {
-            1 => "选择 ICC 配置文件",
-            2 => "ICCプロファイルを選択",
-            _ => "Select ICC Profile",
-        },
-        "wiz_title" => match lang {
-            1 => "Icc Auto Reloader - 设置向导",
-            2 => "Icc Auto Reloader - セットアップウィザード",
-            _ => "Icc Auto Reloader - Setup Wizard",
-        },
-        "wiz_lbl_lang" => match lang {
-            1 => "请选择界面语言:",
-            2 => "言語を選択してください:",
-            _ => "Please select your language:",
-        },
-        "wiz_welcome" => match lang {
-            1 => "欢迎！\n\n请导入一个 ICC 配置文件以开始使用。\n这将确保您的显示器颜色始终准确。",
-            2 => "ようこそ！\n\n開始するには ICC プロファイルをインポートしてください。\nこれにより、モニターの色が常に正確に保たれます。",
-            _ => "Welcome!\n\nPlease import an ICC profile to get started.\nThis ensures your display colors remain accurate.",
-        },
-        "wiz_btn_import" => match lang {
-            1 => "导入",
-            2 => "インポート",
-            _ => "Import",
-        },
-        "wiz_btn_skip" => match lang {
-            1 => "跳过",
-            2 => "スキップ",
-            _ => "Skip",
-        },
-        "wiz_btn_next" => match lang {
-            1 => "下一步 >",
-            2 => "次へ >",
-            _ => "Next >",
-        },
-        "wiz_congrats" => match lang {
-            1 => "恭喜！设置已完成。\n\nIcc Auto Reloader 将在后台运行并保护您的校准。",
-            2 => "おめでとうございます！準備が完了しました。\n\nIcc Auto Reloader はバックグラウンドで実行され、\nキャリブレーションを保護します。",
-            _ => "Congratulations! You are all set.\n\nIcc Auto Reloader will run in the background\nand protect your calibration.",
-        },
-        "wiz_chk_startup" => match lang {
-            1 => "开机时自动运行",
-            2 => "スタートアップ時に実行",
-            _ => "Run at startup",
-        },
-        "wiz_btn_finish" => match lang {
-            1 => "完成",
-            2 => "完了",
-            _ => "Finish",
-        },
-        "wiz_title_welcome" => match lang {
-            1 => "欢迎",
-            2 => "ようこそ",
-            _ => "Welcome",
-        },
-        "wiz_step1_title" => match lang {
-            1 => "导入 ICC 配置文件",
-            2 => "ICCプロファイルをインポート",
-            _ => "Import ICC Profile",
-        },
-        "wiz_step1_desc" => match lang {
-            1 => "选择一个ICC配置文件，或跳过以使用Windows已安装的配置文件。",
-            2 => "ICCプロファイルを選択するか、スキップしてWindowsのデフォルトプロファイルを使用します。",
-            _ => "Select an ICC profile, or skip to use the Windows default profile.",
-        },
-        "wiz_step1_no_profile" => match lang {
-            1 => "未选择配置文件（将使用Windows默认）",
-            2 => "プロファイル未選択（Windowsのデフォルトを使用）",
-            _ => "No profile selected (Windows default will be used)",
-        },
-        "wiz_step1_profile" => match lang {
-            1 => "配置文件: ",
-            2 => "プロファイル: ",
-            _ => "Profile: ",
-        },
-        "wiz_step2_title" => match lang {
-            1 => "设置完成！",
-            2 => "セットアップ完了！",
-            _ => "Setup Complete!",
-        },
-        "wiz_btn_back" => match lang {
-            1 => "< 返回",
-            2 => "< 戻る",
-            _ => "< Back",
-        },
-        "wiz_btn_cancel" => match lang {
-            1 => "取消",
-            2 => "キャンセル",
-            _ => "Cancel",
-        },
-        "exit_confirm_title" => match lang {
-            1 => "确认退出",
-            2 => "終了の確認",
-            _ => "Confirm Exit",
-        },
-        "exit_confirm_msg" => match lang {
-            1 => "注意，退出后系统将不再自动加载色彩校准，是否继续？",
-            2 => "注意：終了すると、システムは自動的にキャリブレーションを読み込まなくなります。続行しますか？",
-            _ => "Warning: After exiting, the system will no longer automatically load color calibration. Continue?",
-        },
-        _ => id,
-    };
-    s.to_string()
+fn get_str(msg: Msg) -> String {
+    i18n::tr(msg)
+}
+
+fn get_str_args(msg: Msg, args: &[(&str, &str)]) -> String {
+    i18n::tr_args(msg, args)
+}
+
+// Older versions stored the language as a `Language` DWORD indexing this list
+const LEGACY_LANGUAGES: [&str; 3] = ["en", "zh", "ja"];
+
+// Fills a language picker from the available catalogs and selects the current one.
+fn fill_language_combo(combo: &nwg::ComboBox<String>) {
+    let languages = i18n::languages();
+    let current = i18n::resolve(&i18n::language());
+    combo.set_collection(languages.iter().map(|(_, name)| name.clone()).collect());
+    combo.set_selection(languages.iter().position(|(tag, _)| *tag == current));
+}
+
+fn selected_language(combo: &nwg::ComboBox<String>) -> Option<String> {
+    let idx = combo.selection()?;
+    i18n::languages().into_iter().nth(idx).map(|(tag, _)| tag)
 }
 
 
 // --- Main Entry ---
 fn main() {
-    nwg::init().expect("Failed to init Native Windows GUI");
+    let command = match cli::parse_args(std::env::args().skip(1)) {
+        Ok(c) => c,
+        Err(e) => {
+            attach_parent_console();
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
+
+    if let CliCommand::Profile(profile_command) = command {
+        attach_parent_console();
+        load_language();
+        std::process::exit(run_profile_command(profile_command));
+    }
+    if let CliCommand::Library(library_command) = command {
+        attach_parent_console();
+        load_language();
+        std::process::exit(run_library_command(library_command));
+    }
+    if let CliCommand::Preset(preset_command) = command {
+        attach_parent_console();
+        load_language();
+        std::process::exit(run_preset_command(preset_command));
+    }
+    if let CliCommand::Schedule { json } = command {
+        attach_parent_console();
+        std::process::exit(run_schedule_command(json));
+    }
 
     // Single Instance Check
-    unsafe {
+    let already_running = unsafe {
         let mutex_name = w!(r"Global\IccAutoReloaderInstance");
         let _h_mutex = CreateMutexW(None, TRUE, mutex_name).unwrap();
-        if let Some(err) = std::io::Error::last_os_error().raw_os_error() {
-            if err as u32 == ERROR_ALREADY_EXISTS.0 {
-                return;
-            }
-        }
+        matches!(std::io::Error::last_os_error().raw_os_error(), Some(err) if err as u32 == ERROR_ALREADY_EXISTS.0)
+    };
+    if already_running {
+        // Hand the command line over to the primary instance. Only the
+        // language is needed; load_settings would start a second RPC listener
+        load_language();
+        std::process::exit(forward_command(command));
+    }
+
+    if let CliCommand::Headless(options) = command {
+        // No nwg windows at all; output goes to the console we were started from
+        attach_parent_console();
+        log::set_echo(true);
+        load_settings();
+        headless::run(options);
+        return;
     }
 
+    nwg::init().expect("Failed to init Native Windows GUI");
+
     // Set Per-Monitor DPI V2 for best text rendering on high DPI displays
     // This must be called before any windows are created
     unsafe {
@@ -229,7 +168,27 @@ fn main() {
     }
 
     load_settings();
-    reload_profile();
+
+    match command {
+        CliCommand::Status { json } => {
+            // Nothing is running, but displays and profiles can still be reported
+            attach_parent_console();
+            print_status(&status::report(false), json);
+            std::process::exit(1);
+        }
+        CliCommand::Send(Request::Quit) => {
+            attach_parent_console();
+            eprintln!("{}", get_str(Msg::MsgNotRunning));
+            std::process::exit(1);
+        }
+        // Startup reloads anyway
+        CliCommand::Send(Request::Reload) | CliCommand::Run | CliCommand::Headless(_) => {}
+        CliCommand::Profile(_) | CliCommand::Library(_) | CliCommand::Preset(_) | CliCommand::Schedule { .. } => unreachable!("handled before the instance check"),
+        // Handled by the tray once it is up
+        CliCommand::Send(request) => ipc::push_local(request),
+    }
+
+    engine::reload_profile();
 
     // Check first run
     if need_first_run() {
@@ -242,6 +201,441 @@ fn main() {
     // Run Tray App
     let _app = SystemTrayApp::build_ui(Default::default()).expect("Failed to build Tray UI");
     nwg::dispatch_thread_events();
+
+    // Let a pending QUIT request receive its answer before the process ends
+    ipc::wait_idle();
+}
+
+// Runs in the second instance: forwards the command and prints the answer.
+// Returns the process exit code.
+fn forward_command(command: CliCommand) -> i32 {
+    let request = match command {
+        // Plain launch while already running: nothing to do
+        CliCommand::Run => return 0,
+        CliCommand::Headless(_) => {
+            attach_parent_console();
+            eprintln!("{}", get_str(Msg::MsgAlreadyRunning));
+            return 1;
+        }
+        CliCommand::Status { json } => {
+            attach_parent_console();
+            return match ipc::send_request(&Request::Status) {
+                Ok(Response::Ok(report)) => match json::parse(&report) {
+                    Ok(report) => {
+                        print_status(&report, json);
+                        0
+                    }
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        1
+                    }
+                },
+                Ok(Response::Error(msg)) => {
+                    eprintln!("{}", msg);
+                    1
+                }
+                Err(e) => {
+                    eprintln!("{}: {}", get_str(Msg::MsgIpcUnreachable), e);
+                    1
+                }
+            };
+        }
+        CliCommand::Send(r) => r,
+        CliCommand::Profile(_) | CliCommand::Library(_) | CliCommand::Preset(_) | CliCommand::Schedule { .. } => unreachable!("handled before the instance check"),
+    };
+
+    attach_parent_console();
+    match ipc::send_request(&request) {
+        Ok(Response::Ok(msg)) => {
+            println!("{}", msg);
+            0
+        }
+        Ok(Response::Error(msg)) => {
+            eprintln!("{}", msg);
+            1
+        }
+        Err(e) => {
+            eprintln!("{}: {}", get_str(Msg::MsgIpcUnreachable), e);
+            1
+        }
+    }
+}
+
+fn print_status(report: &json::Value, as_json: bool) {
+    if as_json {
+        println!("{}", report.pretty());
+    } else {
+        print!("{}", status::format_text(report));
+    }
+}
+
+// Commands that work on profile files and need no running instance.
+fn run_profile_command(command: ProfileCommand) -> i32 {
+    match command {
+        ProfileCommand::Info { path, json } if calibration::is_calibration_file(Path::new(&path)) => {
+            match calibration::load(Path::new(&path)) {
+                Ok(ramp) => {
+                    let report = ramp::analyze(&ramp, None);
+                    if json {
+                        println!("{}", report.to_json().pretty());
+                    } else {
+                        println!("{}", path);
+                        for line in curve_lines(Some(&report)) {
+                            println!("  {}", line);
+                        }
+                    }
+                    0
+                }
+                Err(e) => {
+                    eprintln!("{}", get_str_args(Msg::MsgCalibrationInvalid, &[("error", &e)]));
+                    1
+                }
+            }
+        }
+        ProfileCommand::Info { path, json } => match icc::Profile::load(Path::new(&path)) {
+            Ok(profile) => {
+                let info = profile.info();
+                if json {
+                    println!("{}", info.to_json().pretty());
+                } else {
+                    println!("{}", path);
+                    for line in profile_lines(&info) {
+                        println!("  {}", line);
+                    }
+                }
+                if info.relies_on_mhc2 {
+                    eprintln!("{}", get_str_args(Msg::MsgMhc2Only, &[("file", &path)]));
+                }
+                0
+            }
+            Err(e) => {
+                eprintln!("{}", get_str_args(Msg::MsgProfileUnreadable, &[("error", &e)]));
+                1
+            }
+        },
+        ProfileCommand::Export { output, source, display, size } => {
+            match export::source_ramp(&source, display).and_then(|ramp| export::save(Path::new(&output), &ramp, size)) {
+                Ok(()) => {
+                    println!("{}", get_str_args(Msg::MsgExportDone, &[("file", &output)]));
+                    0
+                }
+                Err(e) => {
+                    eprintln!("{}", get_str_args(Msg::MsgExportFailed, &[("error", &e)]));
+                    1
+                }
+            }
+        }
+        ProfileCommand::Merge { profile, curves, output } => {
+            match icc_writer::merge_calibration(Path::new(&profile), Path::new(&curves), output.as_deref().map(Path::new)) {
+                Ok(path) => {
+                    println!("{}", get_str_args(Msg::MsgMergeDone, &[("file", &path.to_string_lossy())]));
+                    0
+                }
+                Err(e) => {
+                    eprintln!("{}", get_str_args(Msg::MsgMergeFailed, &[("error", &e)]));
+                    1
+                }
+            }
+        }
+        ProfileCommand::Lint { path, json, repair, output } => {
+            let result = if repair {
+                lint::repair(Path::new(&path), output.as_deref().map(Path::new)).map(|(written, issues)| (Some(written), issues))
+            } else {
+                lint::check_file(Path::new(&path)).map(|issues| (None, issues))
+            };
+            match result {
+                Ok((written, issues)) => {
+                    if let Some(written) = &written {
+                        println!("{}", get_str_args(Msg::MsgRepairDone, &[("file", &written.to_string_lossy())]));
+                    }
+                    if json {
+                        println!("{}", json::Value::Array(issues.iter().map(lint::Issue::to_json).collect()).pretty());
+                    } else {
+                        print!("{}", lint::format_text(&issues));
+                    }
+                    if lint::has_errors(&issues) {
+                        1
+                    } else {
+                        0
+                    }
+                }
+                Err(e) => {
+                    eprintln!("{}", get_str_args(Msg::MsgRepairFailed, &[("error", &e)]));
+                    1
+                }
+            }
+        }
+        ProfileCommand::Ages { json } => {
+            let ages = age::check_all();
+            if json {
+                println!("{}", json::Value::Array(ages.iter().map(age::ProfileAge::to_json).collect()).pretty());
+            } else {
+                print!("{}", age::format_text(&ages));
+            }
+            // Non-zero when a display is due, so scripts can alert on it
+            if ages.iter().any(age::ProfileAge::is_overdue) { 1 } else { 0 }
+        }
+    }
+}
+
+// Library maintenance; installing an entry goes through the running
+// instance instead (`library assign`).
+fn run_library_command(command: LibraryCommand) -> i32 {
+    let print = |entries: &[library::Entry], json: bool| {
+        if json {
+            println!("{}", json::Value::Array(entries.iter().map(library::Entry::to_json).collect()).pretty());
+        } else {
+            print!("{}", library::format_text(entries));
+        }
+    };
+    match command {
+        LibraryCommand::List { json } => {
+            print(&library::list(), json);
+            0
+        }
+        LibraryCommand::Search { query, json } => {
+            let entries = library::search(&query);
+            print(&entries, json);
+            // Like grep: non-zero when nothing matched
+            if entries.is_empty() { 1 } else { 0 }
+        }
+        LibraryCommand::Import { path } => {
+            let displays = display::enumerate();
+            let result = check_importable(Path::new(&path)).and_then(|()| {
+                library::import(Path::new(&path), None, &displays)
+                    .map_err(|e| get_str_args(Msg::MsgLibraryImportFailed, &[("error", &e)]))
+            });
+            match result {
+                Ok(entry) => {
+                    println!("{}", get_str_args(Msg::MsgLibraryImported, &[("file", &entry.name), ("hash", entry.short_hash())]));
+                    0
+                }
+                Err(msg) => {
+                    eprintln!("{}", msg);
+                    1
+                }
+            }
+        }
+        LibraryCommand::Delete { entry } => match library::delete(&entry, &display::enumerate()) {
+            Ok(entry) => {
+                println!("{}", get_str_args(Msg::MsgLibraryDeleted, &[("file", &entry.name)]));
+                0
+            }
+            Err(e) => {
+                eprintln!("{}", get_str_args(Msg::MsgLibraryDeleteFailed, &[("error", &e)]));
+                1
+            }
+        },
+    }
+}
+
+// Preset maintenance; switching goes through the running instance instead
+// (`preset apply`).
+fn run_preset_command(command: PresetCommand) -> i32 {
+    let result = match command {
+        PresetCommand::List { json } => {
+            let presets = presets::list();
+            if json {
+                println!("{}", json::Value::Array(presets.iter().map(presets::Preset::to_json).collect()).pretty());
+            } else {
+                print!("{}", presets::format_text(&presets));
+            }
+            return 0;
+        }
+        PresetCommand::Save { name } => presets::capture(&name).and_then(|preset| presets::save(&preset).map(|()| preset)),
+        PresetCommand::Set { name, entry, display } => library::find(&entry).and_then(|entry| {
+            let displays = display::enumerate();
+            let target = displays.get(display - 1).ok_or_else(|| format!("display {} not found", display))?;
+            let mut preset = presets::find(&name).unwrap_or_else(|| presets::Preset { name: name.trim().to_string(), displays: Vec::new() });
+            preset.set(target, &entry.hash);
+            presets::save(&preset).map(|()| preset)
+        }),
+        PresetCommand::Delete { name } => match presets::delete(&name) {
+            Ok(preset) => {
+                println!("{}", get_str_args(Msg::MsgPresetDeleted, &[("name", &preset.name)]));
+                return 0;
+            }
+            Err(e) => Err(e),
+        },
+    };
+    match result {
+        Ok(preset) => {
+            println!("{}", get_str_args(Msg::MsgPresetSaved, &[("name", &preset.name)]));
+            print!("{}", presets::format_text(std::slice::from_ref(&preset)));
+            0
+        }
+        Err(e) => {
+            eprintln!("{}", get_str_args(Msg::MsgPresetFailed, &[("error", &e)]));
+            1
+        }
+    }
+}
+
+// `schedule`: the rules with today's start times, the one in effect and the
+// next switch.
+fn run_schedule_command(json: bool) -> i32 {
+    let schedule = match schedule::Schedule::load() {
+        Ok(schedule) => schedule,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 1;
+        }
+    };
+    let report = schedule.to_json(schedule::Clock::now(&schedule::SystemClock));
+    if json {
+        println!("{}", report.pretty());
+    } else {
+        print!("{}", schedule::format_text(&report));
+    }
+    0
+}
+
+// Localized one-line summaries of a profile, shared by the tray submenu and
+// `profile info`. Always the same number of lines, in the same order.
+fn profile_lines(info: &icc::ProfileInfo) -> Vec<String> {
+    let unknown = get_str(Msg::InfoUnknown);
+    let value = |v: Option<String>| v.unwrap_or_else(|| unknown.clone());
+    let xy = |(x, y): (f64, f64)| format!("{:.4}, {:.4}", x, y);
+    // Prefer the primaries with the PCS adaptation undone
+    let primaries = match (&info.gamut, &info.primaries) {
+        (Some(g), _) => Some(g.primaries),
+        (None, Some(p)) => Some(p.map(|v| v.chromaticity())),
+        (None, None) => None,
+    }
+    .map(|p| format!("R {}  G {}  B {}", xy(p[0]), xy(p[1]), xy(p[2])));
+    let gamut_line = |msg: Msg, space: &str, pick: fn(&(gamut::Comparison, gamut::Comparison)) -> f64| {
+        let text = info.gamut.as_ref().map(|g| {
+            gamut::REFERENCES
+                .iter()
+                .zip(&g.comparisons)
+                .map(|(r, c)| format!("{} {:.1}%", r.name, pick(c) * 100.0))
+                .collect::<Vec<_>>()
+                .join(", ")
+        });
+        get_str_args(msg, &[("space", space), ("value", &value(text))])
+    };
+
+    let mut lines = vec![
+        get_str_args(Msg::InfoDescription, &[("value", &value(info.description.clone()))]),
+        get_str_args(Msg::InfoDevice, &[("value", &value(info.device()))]),
+        get_str_args(Msg::InfoCreated, &[("value", &value(info.created.map(|d| d.to_string())))]),
+        get_str_args(Msg::InfoVersion, &[("version", &info.version_string()), ("class", &info.class_name())]),
+        get_str_args(
+            Msg::InfoWhitePoint,
+            &[("value", &value(info.white_point.as_ref().map(|w| format!("{} (XYZ {:.4} {:.4} {:.4})", xy(w.chromaticity()), w.x, w.y, w.z))))],
+        ),
+        get_str_args(Msg::InfoLuminance, &[("value", &value(info.luminance.map(|l| format!("{:.1}", l))))]),
+        get_str_args(Msg::InfoPrimaries, &[("value", &value(primaries))]),
+        gamut_line(Msg::InfoGamutCoverage, "xy", |(xy, _)| xy.coverage),
+        gamut_line(Msg::InfoGamutArea, "xy", |(xy, _)| xy.relative_area),
+        gamut_line(Msg::InfoGamutCoverage, "u'v'", |(_, uv)| uv.coverage),
+        gamut_line(Msg::InfoGamutArea, "u'v'", |(_, uv)| uv.relative_area),
+        get_str(if info.has_vcgt { Msg::InfoVcgtPresent } else { Msg::InfoVcgtMissing }),
+    ];
+    lines.extend(mhc2_lines(info.mhc2.as_ref()));
+    lines.extend(curve_lines(info.calibration.as_ref()));
+    lines.extend(accuracy_lines(info.accuracy.as_ref()));
+    lines.push(get_str_args(Msg::InfoCopyright, &[("value", &value(info.copyright.clone()))]));
+    lines
+}
+
+// Summary and matrix of a profile's `MHC2` tag.
+fn mhc2_lines(mhc2: Option<&icc::Mhc2>) -> Vec<String> {
+    let Some(m) = mhc2 else {
+        let none = get_str(Msg::InfoMhc2None);
+        return vec![get_str_args(Msg::InfoMhc2, &[("value", &none)]), get_str_args(Msg::InfoMhc2Matrix, &[("value", &none)])];
+    };
+    let kind = |identity: bool| get_str(if identity { Msg::InfoMhc2Identity } else { Msg::InfoMhc2Custom });
+    let summary = get_str_args(
+        Msg::InfoMhc2Summary,
+        &[
+            ("min", &format!("{:.4}", m.min_luminance)),
+            ("max", &format!("{:.1}", m.max_luminance)),
+            ("entries", &m.luts[0].len().to_string()),
+            ("luts", &kind(m.luts_are_identity())),
+            ("matrix", &kind(m.matrix_is_identity())),
+        ],
+    );
+    let rows: Vec<String> =
+        m.matrix.iter().map(|row| row.iter().map(|v| format!("{:.4}", v)).collect::<Vec<_>>().join(" ")).collect();
+    vec![get_str_args(Msg::InfoMhc2, &[("value", &summary)]), get_str_args(Msg::InfoMhc2Matrix, &[("value", &rows.join(" / "))])]
+}
+
+// Embedded measurements and how well the profile reproduces them.
+fn accuracy_lines(report: Option<&accuracy::AccuracyReport>) -> Vec<String> {
+    let Some(report) = report else {
+        let none = get_str(Msg::InfoMeasurementsNone);
+        return vec![get_str_args(Msg::InfoMeasurements, &[("value", &none)]), get_str_args(Msg::InfoAccuracy, &[("value", &none)])];
+    };
+    let measurements = get_str_args(
+        Msg::InfoMeasurementsSummary,
+        &[
+            ("patches", &report.patches.to_string()),
+            ("instrument", &report.instrument.clone().unwrap_or_else(|| get_str(Msg::InfoUnknown))),
+        ],
+    );
+    let accuracy = match (report.mean, report.p95, report.max) {
+        (Some(mean), Some(p95), Some(max)) => get_str_args(
+            Msg::InfoAccuracySummary,
+            &[("mean", &format!("{:.2}", mean)), ("p95", &format!("{:.2}", p95)), ("max", &format!("{:.2}", max))],
+        ),
+        _ => get_str(Msg::InfoAccuracyNoModel),
+    };
+    vec![get_str_args(Msg::InfoMeasurements, &[("value", &measurements)]), get_str_args(Msg::InfoAccuracy, &[("value", &accuracy)])]
+}
+
+// Gamma, levels, issues and white shift of a calibration ramp.
+fn curve_lines(report: Option<&ramp::RampReport>) -> Vec<String> {
+    const CHANNELS: [&str; 3] = ["R", "G", "B"];
+    let Some(report) = report else {
+        let unknown = get_str(Msg::InfoUnknown);
+        return [Msg::InfoCurveGamma, Msg::InfoCurveLevels, Msg::InfoCurveIssues, Msg::InfoCurveWhite]
+            .into_iter()
+            .map(|msg| get_str_args(msg, &[("value", &unknown)]))
+            .collect();
+    };
+    let per_channel = |f: &dyn Fn(&ramp::ChannelReport) -> String| {
+        CHANNELS.iter().zip(&report.channels).map(|(name, c)| format!("{} {}", name, f(c))).collect::<Vec<_>>().join("  ")
+    };
+
+    let gamma = per_channel(&|c| c.gamma.map_or("-".to_string(), |g| format!("{:.2}", g)));
+    let levels = per_channel(&|c| format!("{}/{}", c.levels_8bit, c.levels_10bit));
+    let mut issues = Vec::new();
+    for (name, c) in CHANNELS.iter().zip(&report.channels) {
+        if !c.non_monotonic.is_empty() {
+            let ranges: Vec<String> = c.non_monotonic.iter().map(|(start, end)| format!("{}-{}", start, end)).collect();
+            issues.push(get_str_args(Msg::InfoCurveNonMonotonic, &[("channel", name), ("ranges", &ranges.join(", "))]));
+        }
+        if c.clipped_black > 0 || c.clipped_white > 0 {
+            issues.push(get_str_args(
+                Msg::InfoCurveClipped,
+                &[("channel", name), ("black", &c.clipped_black.to_string()), ("white", &c.clipped_white.to_string())],
+            ));
+        }
+    }
+    let issues = if issues.is_empty() { get_str(Msg::InfoCurveOk) } else { issues.join("; ") };
+    let white = format!(
+        "x {:.4}, y {:.4}, Δu'v' {:.4}, {:.1}%",
+        report.white.0,
+        report.white.1,
+        report.white_shift_uv,
+        report.white_luminance * 100.0
+    );
+
+    vec![
+        get_str_args(Msg::InfoCurveGamma, &[("value", &gamma)]),
+        get_str_args(Msg::InfoCurveLevels, &[("value", &levels)]),
+        get_str_args(Msg::InfoCurveIssues, &[("value", &issues)]),
+        get_str_args(Msg::InfoCurveWhite, &[("value", &white)]),
+    ]
+}
+
+// The exe uses the windows subsystem, so output only shows up when started
+// from a console and we attach to it explicitly.
+fn attach_parent_console() {
+    unsafe {
+        let _ = AttachConsole(ATTACH_PARENT_PROCESS);
+    }
 }
 
 // --- Wizard Window ---
@@ -266,10 +660,10 @@ pub struct WizardApp {
     #[nwg_layout_item(layout: layout, col: 0, row: 1, col_span: 3)]
     step0_lbl: nwg::Label,
 
-    #[nwg_control(collection: vec!["English", "中文", "日本語"])]
+    #[nwg_control]
     #[nwg_layout_item(layout: layout, col: 0, row: 2, col_span: 3)]
     #[nwg_events( OnComboxBoxSelection: [WizardApp::change_lang] )]
-    step0_combo: nwg::ComboBox<&'static str>,
+    step0_combo: nwg::ComboBox<String>,
 
     // ===== Step 1: Import ICC Profile =====
     #[nwg_control(text: "Import ICC Profile")]
@@ -330,8 +724,7 @@ impl WizardApp {
             *self.icon.borrow_mut() = Some(icon);
         }
 
-        let cur = CURRENT_LANG.load(Ordering::Relaxed);
-        self.step0_combo.set_selection(Some(cur));
+        fill_language_combo(&self.step0_combo);
         self.show_step(0);
         self.update_text();
     }
@@ -358,7 +751,7 @@ impl WizardApp {
                 self.step0_combo.set_visible(true);
                 self.btn_back.set_enabled(false);
                 self.btn_next.set_enabled(true);
-                self.btn_next.set_text(&get_str("wiz_btn_next"));
+                self.btn_next.set_text(&get_str(Msg::WizBtnNext));
                 *current = 0;
             }
             1 => {
@@ -369,7 +762,7 @@ impl WizardApp {
                 self.btn_back.set_enabled(true);
                 // Allow skipping - Next button is always enabled
                 self.btn_next.set_enabled(true);
-                self.btn_next.set_text(&get_str("wiz_btn_next"));
+                self.btn_next.set_text(&get_str(Msg::WizBtnNext));
                 *current = 1;
             }
             2 => {
@@ -377,7 +770,7 @@ impl WizardApp {
                 self.step2_lbl_congrats.set_visible(true);
                 self.step2_chk_startup.set_visible(true);
                 self.btn_back.set_enabled(true);
-                self.btn_next.set_text(&get_str("wiz_btn_finish"));
+                self.btn_next.set_text(&get_str(Msg::WizBtnFinish));
                 *current = 2;
             }
             _ => {}
@@ -385,8 +778,8 @@ impl WizardApp {
     }
 
     fn change_lang(&self) {
-        if let Some(idx) = self.step0_combo.selection() {
-            CURRENT_LANG.store(idx, Ordering::Relaxed);
+        if let Some(tag) = selected_language(&self.step0_combo) {
+            i18n::set_language(&tag);
             self.update_text();
         }
     }
@@ -410,7 +803,7 @@ impl WizardApp {
     fn import_profile(&self) {
         let mut dialog = nwg::FileDialog::default();
         nwg::FileDialog::builder()
-            .title(&get_str("msg_select_profile"))
+            .title(&get_str(Msg::MsgSelectProfile))
             .action(nwg::FileDialogAction::Open)
             .build(&mut dialog)
             .expect("Failed to create file dialog");
@@ -418,13 +811,17 @@ impl WizardApp {
         if dialog.run(Some(&self.window)) {
             if let Ok(path) = dialog.get_selected_item() {
                 let path_str = path.to_string_lossy().to_string();
-                install_profile(&path_str);
+                let result = install_profile(&path_str);
+                show_install_result(&result);
+                if result.is_err() {
+                    return;
+                }
                 *self.profile_imported.borrow_mut() = true;
                 let file_name = std::path::Path::new(&path_str)
                     .file_name()
                     .and_then(|n| n.to_str())
                     .unwrap_or("Unknown");
-                self.step1_lbl_status.set_text(&format!("{}{}", get_str("wiz_step1_profile"), file_name));
+                self.step1_lbl_status.set_text(&get_str_args(Msg::WizStep1Profile, &[("file", file_name)]));
             }
         }
     }
@@ -445,48 +842,48 @@ impl WizardApp {
     }
 
     fn update_text(&self) {
-        self.window.set_text(&get_str("wiz_title"));
+        self.window.set_text(&get_str(Msg::WizTitle));
 
         // Buttons
-        self.btn_back.set_text(&get_str("wiz_btn_back"));
-        self.btn_cancel.set_text(&get_str("wiz_btn_cancel"));
+        self.btn_back.set_text(&get_str(Msg::WizBtnBack));
+        self.btn_cancel.set_text(&get_str(Msg::WizBtnCancel));
         let step = *self.current_step.borrow();
         if step == 2 {
-            self.btn_next.set_text(&get_str("wiz_btn_finish"));
+            self.btn_next.set_text(&get_str(Msg::WizBtnFinish));
         } else {
-            self.btn_next.set_text(&get_str("wiz_btn_next"));
+            self.btn_next.set_text(&get_str(Msg::WizBtnNext));
         }
 
         // Step 0
-        self.step0_title.set_text(&get_str("wiz_title_welcome"));
-        self.step0_lbl.set_text(&get_str("wiz_lbl_lang"));
+        self.step0_title.set_text(&get_str(Msg::WizTitleWelcome));
+        self.step0_lbl.set_text(&get_str(Msg::WizLblLang));
 
         // Step 1
-        self.step1_title.set_text(&get_str("wiz_step1_title"));
-        self.step1_lbl_welcome.set_text(&get_str("wiz_step1_desc"));
+        self.step1_title.set_text(&get_str(Msg::WizStep1Title));
+        self.step1_lbl_welcome.set_text(&get_str(Msg::WizStep1Desc));
         if !*self.profile_imported.borrow() {
-            self.step1_lbl_status.set_text(&get_str("wiz_step1_no_profile"));
+            self.step1_lbl_status.set_text(&get_str(Msg::WizStep1NoProfile));
         }
-        self.step1_btn_import.set_text(&get_str("wiz_btn_import"));
+        self.step1_btn_import.set_text(&get_str(Msg::WizBtnImport));
 
         // Step 2
-        self.step2_title.set_text(&get_str("wiz_step2_title"));
-        self.step2_lbl_congrats.set_text(&get_str("wiz_congrats"));
-        self.step2_chk_startup.set_text(&get_str("wiz_chk_startup"));
+        self.step2_title.set_text(&get_str(Msg::WizStep2Title));
+        self.step2_lbl_congrats.set_text(&get_str(Msg::WizCongrats));
+        self.step2_chk_startup.set_text(&get_str(Msg::WizChkStartup));
     }
 }
 
 // --- Settings Window ---
 #[derive(Default, NwgUi)]
 pub struct SettingsApp {
-    #[nwg_control(size: (360, 220), position: (300, 300), title: "Settings", flags: "WINDOW|VISIBLE")]
+    #[nwg_control(size: (360, 320), position: (300, 300), title: "Settings", flags: "WINDOW|VISIBLE")]
     #[nwg_events( OnWindowClose: [SettingsApp::on_close], OnInit: [SettingsApp::init] )]
     window: nwg::Window,
 
     icon: RefCell<Option<nwg::Icon>>,
 
     // Use GridLayout for the form fields
-    #[nwg_layout(parent: window, max_column: Some(2), max_row: Some(3), margin: [20, 20, 20, 20], spacing: 15)]
+    #[nwg_layout(parent: window, max_column: Some(2), max_row: Some(5), margin: [20, 20, 20, 20], spacing: 15)]
     layout: nwg::GridLayout,
 
     #[nwg_control(text: "Polling Interval:")]
@@ -501,13 +898,30 @@ pub struct SettingsApp {
     #[nwg_layout_item(layout: layout, col: 0, row: 1)]
     lbl_lang: nwg::Label,
 
-    #[nwg_control(collection: vec!["English", "中文", "日本語"])]
+    #[nwg_control]
     #[nwg_layout_item(layout: layout, col: 1, row: 1)]
     #[nwg_events( OnComboxBoxSelection: [SettingsApp::lang_changed] )]
-    combo_lang: nwg::ComboBox<&'static str>,
+    combo_lang: nwg::ComboBox<String>,
+
+    #[nwg_control(text: "Enable local control API")]
+    #[nwg_layout_item(layout: layout, col: 0, row: 2, col_span: 2)]
+    chk_rpc: nwg::CheckBox,
+
+    #[nwg_control(text: "While fullscreen:")]
+    #[nwg_layout_item(layout: layout, col: 0, row: 3)]
+    lbl_fullscreen: nwg::Label,
+
+    // Entries in the order of `fullscreen::Policy::ALL`, filled by `update_text`
+    #[nwg_control]
+    #[nwg_layout_item(layout: layout, col: 1, row: 3)]
+    combo_fullscreen: nwg::ComboBox<String>,
+
+    // Buttons use absolute positioning to avoid being squashed by GridLayout
+    #[nwg_control(text: "Profile Library...", size: (150, 38), position: (20, 255))]
+    #[nwg_events( OnButtonClick: [SettingsApp::open_library] )]
+    btn_library: nwg::Button,
 
-    // Button uses absolute positioning to avoid being squashed by GridLayout
-    #[nwg_control(text: "Save", size: (130, 38), position: (115, 155))]
+    #[nwg_control(text: "Save", size: (150, 38), position: (190, 255))]
     #[nwg_events( OnButtonClick: [SettingsApp::save] )]
     btn_save: nwg::Button,
 }
@@ -530,20 +944,42 @@ impl SettingsApp {
         self.combo_interval.set_selection(Some(idx));
 
         // Init lang combo
-        let cur_lang = CURRENT_LANG.load(Ordering::Relaxed);
-        self.combo_lang.set_selection(Some(cur_lang));
+        fill_language_combo(&self.combo_lang);
+
+        let rpc_state = if rpc::is_enabled() { nwg::CheckBoxState::Checked } else { nwg::CheckBoxState::Unchecked };
+        self.chk_rpc.set_check_state(rpc_state);
+
+        let policy = fullscreen::Policy::ALL.iter().position(|&p| p == fullscreen::policy());
+        self.combo_fullscreen.set_selection(policy);
     }
 
     fn update_text(&self) {
-        self.window.set_text(&get_str("settings_title"));
-        self.lbl_interval.set_text(&get_str("lbl_interval"));
-        self.lbl_lang.set_text(&get_str("lbl_lang"));
-        self.btn_save.set_text(&get_str("btn_save"));
+        self.window.set_text(&get_str(Msg::SettingsTitle));
+        self.lbl_interval.set_text(&get_str(Msg::LblInterval));
+        self.lbl_lang.set_text(&get_str(Msg::LblLang));
+        self.chk_rpc.set_text(&get_str(Msg::ChkRpc));
+        self.lbl_fullscreen.set_text(&get_str(Msg::LblFullscreen));
+        self.btn_library.set_text(&get_str(Msg::BtnLibrary));
+
+        // Rebuilt in the current language, keeping the selection
+        let selection = self.combo_fullscreen.selection();
+        let policies = fullscreen::Policy::ALL.iter().map(|p| match p {
+            fullscreen::Policy::Keep => get_str(Msg::FullscreenKeep),
+            fullscreen::Policy::Suspend => get_str(Msg::FullscreenSuspend),
+            fullscreen::Policy::ReloadOnExit => get_str(Msg::FullscreenReloadOnExit),
+        });
+        self.combo_fullscreen.set_collection(policies.collect());
+        self.combo_fullscreen.set_selection(selection);
+        self.btn_save.set_text(&get_str(Msg::BtnSave));
+    }
+
+    fn open_library(&self) {
+        open_library_window();
     }
 
     fn lang_changed(&self) {
-        if let Some(idx) = self.combo_lang.selection() {
-            CURRENT_LANG.store(idx, Ordering::Relaxed);
+        if let Some(tag) = selected_language(&self.combo_lang) {
+            i18n::set_language(&tag);
             self.update_text();
         }
     }
@@ -555,6 +991,11 @@ impl SettingsApp {
             POLLING_INTERVAL_MS.store(sec * 1000, Ordering::Relaxed);
         }
 
+        rpc::set_enabled(self.chk_rpc.check_state() == nwg::CheckBoxState::Checked);
+        if let Some(policy) = self.combo_fullscreen.selection().and_then(|i| fullscreen::Policy::ALL.get(i)) {
+            fullscreen::set_policy(*policy);
+        }
+
         save_settings();
         self.window.close();
     }
@@ -565,6 +1006,158 @@ impl SettingsApp {
     }
 }
 
+// --- Library Window ---
+#[derive(Default, NwgUi)]
+pub struct LibraryApp {
+    #[nwg_control(size: (520, 400), position: (320, 200), title: "Profile Library", flags: "WINDOW|VISIBLE")]
+    #[nwg_events( OnWindowClose: [LibraryApp::on_close], OnInit: [LibraryApp::init] )]
+    window: nwg::Window,
+
+    // Entries in the order they are listed
+    entries: RefCell<Vec<library::Entry>>,
+
+    #[nwg_control(text: "Search:", size: (70, 25), position: (20, 20))]
+    lbl_search: nwg::Label,
+
+    #[nwg_control(size: (405, 25), position: (95, 18))]
+    #[nwg_events( OnTextInput: [LibraryApp::refresh] )]
+    txt_search: nwg::TextInput,
+
+    #[nwg_control(size: (480, 230), position: (20, 55))]
+    list: nwg::ListBox<String>,
+
+    #[nwg_control(text: "Display:", size: (70, 25), position: (20, 300))]
+    lbl_display: nwg::Label,
+
+    #[nwg_control(size: (405, 25), position: (95, 298))]
+    combo_display: nwg::ComboBox<String>,
+
+    #[nwg_control(text: "Import...", size: (150, 35), position: (20, 340))]
+    #[nwg_events( OnButtonClick: [LibraryApp::import] )]
+    btn_import: nwg::Button,
+
+    #[nwg_control(text: "Assign", size: (150, 35), position: (185, 340))]
+    #[nwg_events( OnButtonClick: [LibraryApp::assign] )]
+    btn_assign: nwg::Button,
+
+    #[nwg_control(text: "Delete", size: (150, 35), position: (350, 340))]
+    #[nwg_events( OnButtonClick: [LibraryApp::delete] )]
+    btn_delete: nwg::Button,
+}
+
+impl LibraryApp {
+    fn init(&self) {
+        self.window.set_text(&get_str(Msg::LibraryTitle));
+        self.lbl_search.set_text(&get_str(Msg::LblLibrarySearch));
+        self.lbl_display.set_text(&get_str(Msg::LblLibraryDisplay));
+        self.btn_import.set_text(&get_str(Msg::BtnLibraryImport));
+        self.btn_assign.set_text(&get_str(Msg::BtnLibraryAssign));
+        self.btn_delete.set_text(&get_str(Msg::BtnLibraryDelete));
+
+        let displays: Vec<String> = display::enumerate().iter().map(display_label).collect();
+        let has_displays = !displays.is_empty();
+        self.combo_display.set_collection(displays);
+        if has_displays {
+            self.combo_display.set_selection(Some(0));
+        }
+        self.refresh();
+    }
+
+    fn refresh(&self) {
+        let entries = library::search(&self.txt_search.text());
+        let rows = entries
+            .iter()
+            .map(|e| {
+                let device = e.device.as_deref().unwrap_or("-");
+                format!("{}  {}  [{}, {}]", e.short_hash(), e.title(), e.name, device)
+            })
+            .collect();
+        self.list.set_collection(rows);
+        *self.entries.borrow_mut() = entries;
+    }
+
+    fn selected(&self) -> Option<library::Entry> {
+        self.list.selection().and_then(|i| self.entries.borrow().get(i).cloned())
+    }
+
+    // 1-based, as in `dispwin -d`
+    fn selected_display(&self) -> usize {
+        self.combo_display.selection().unwrap_or(0) + 1
+    }
+
+    fn import(&self) {
+        let filters = "Profiles and calibration (*.icc;*.icm;*.cal;*.cube;*.csv)";
+        let Some(path) = choose_file(&self.window, &get_str(Msg::MsgSelectProfile), nwg::FileDialogAction::Open, filters) else {
+            return;
+        };
+        let displays = display::enumerate();
+        let target = displays.get(self.selected_display() - 1);
+        let result = check_importable(&path).and_then(|()| {
+            library::import(&path, target, &displays).map_err(|e| get_str_args(Msg::MsgLibraryImportFailed, &[("error", &e)]))
+        });
+        if let Err(msg) = result {
+            nwg::error_message("Error", &msg);
+        }
+        self.refresh();
+    }
+
+    fn assign(&self) {
+        let Some(entry) = self.selected() else { return };
+        let display = self.selected_display();
+        match assign_library_entry(&entry.hash, display) {
+            Ok(entry) => nwg::simple_message(
+                "Success",
+                &get_str_args(Msg::MsgAssignDone, &[("file", &entry.name), ("display", &display.to_string())]),
+            ),
+            Err(msg) => nwg::error_message("Error", &msg),
+        };
+        self.refresh();
+    }
+
+    fn delete(&self) {
+        let Some(entry) = self.selected() else { return };
+        let title = get_str(Msg::LibraryTitle);
+        let content = get_str_args(Msg::LibraryConfirmDelete, &[("file", &entry.name)]);
+        let params = nwg::MessageParams {
+            title: &title,
+            content: &content,
+            buttons: nwg::MessageButtons::YesNo,
+            icons: nwg::MessageIcons::Warning,
+        };
+        if nwg::modal_message(&self.window, &params) != nwg::MessageChoice::Yes {
+            return;
+        }
+        if let Err(e) = library::delete(&entry.hash, &display::enumerate()) {
+            nwg::error_message("Error", &get_str_args(Msg::MsgLibraryDeleteFailed, &[("error", &e)]));
+        }
+        self.refresh();
+    }
+
+    fn on_close(&self) {
+        LIBRARY_OPEN.store(false, Ordering::Relaxed);
+    }
+}
+
+thread_local! {
+    // Keeps the library window alive; opened from both the tray and Settings
+    static LIBRARY_WINDOW: RefCell<Option<Box<dyn std::any::Any>>> = RefCell::new(None);
+}
+
+fn open_library_window() {
+    if LIBRARY_OPEN.swap(true, Ordering::Relaxed) {
+        return;
+    }
+    let library = LibraryApp::build_ui(Default::default()).expect("Failed to build Library UI");
+    // Replaces (and destroys) the window closed last time, if any
+    LIBRARY_WINDOW.with(|w| *w.borrow_mut() = Some(Box::new(library)));
+}
+
+// "2: DELL U2720Q" — numbered like `--display` and `dispwin -d`
+fn display_label(d: &display::Display) -> String {
+    let name = if d.name.is_empty() { &d.device } else { &d.name };
+    format!("{}: {}", d.index + 1, name)
+}
+
 // --- System Tray App ---
 #[derive(Default, NwgUi)]
 pub struct SystemTrayApp {
@@ -575,6 +1168,12 @@ pub struct SystemTrayApp {
     #[nwg_events( OnWindowClose: [SystemTrayApp::exit], OnInit: [SystemTrayApp::init] )]
     window: nwg::Window,
 
+    #[nwg_resource(source_system: Some(nwg::OemIcon::Warning))]
+    warning_icon: nwg::Icon,
+
+    // Displays past their re-calibration limit, updated by `check_ages`
+    overdue: RefCell<Vec<age::ProfileAge>>,
+
     #[nwg_control(icon: Some(&data.tray_icon), tip: Some("Icc Auto Reloader"))]
     #[nwg_events( MousePressLeftUp: [SystemTrayApp::reload_click], OnContextMenu: [SystemTrayApp::show_menu] )]
     tray: nwg::TrayNotification,
@@ -593,38 +1192,223 @@ pub struct SystemTrayApp {
     #[nwg_events( OnMenuItemSelected: [SystemTrayApp::import_click] )]
     item_import: nwg::MenuItem,
 
-    #[nwg_control(parent: tray_menu)]
-    item_sep1: nwg::MenuSeparator,
+    #[nwg_control(parent: tray_menu, text: "Merge...")]
+    #[nwg_events( OnMenuItemSelected: [SystemTrayApp::merge_click] )]
+    item_merge: nwg::MenuItem,
 
-    #[nwg_control(parent: tray_menu, text: "Polling", check: true)]
-    #[nwg_events( OnMenuItemSelected: [SystemTrayApp::toggle_polling] )]
-    item_polling: nwg::MenuItem,
+    #[nwg_control(parent: tray_menu, text: "Profile Library")]
+    menu_library: nwg::Menu,
 
-    #[nwg_control(parent: tray_menu, text: "Startup", check: true)]
-    #[nwg_events( OnMenuItemSelected: [SystemTrayApp::toggle_startup] )]
-    item_startup: nwg::MenuItem,
+    #[nwg_control(parent: menu_library, text: "Manage Library...")]
+    #[nwg_events( OnMenuItemSelected: [SystemTrayApp::open_library] )]
+    item_library_manage: nwg::MenuItem,
 
-    #[nwg_control(parent: tray_menu, text: "Settings...")]
-    #[nwg_events( OnMenuItemSelected: [SystemTrayApp::open_settings] )]
-    item_settings: nwg::MenuItem,
+    #[nwg_control(parent: menu_library)]
+    item_library_sep: nwg::MenuSeparator,
 
-    #[nwg_control(parent: tray_menu)]
-    item_sep2: nwg::MenuSeparator,
+    // Library entries below the separator, rebuilt by `refresh_library_menu`:
+    // each item with the display number and entry hash it assigns
+    library_items: RefCell<Vec<(nwg::MenuItem, usize, String)>>,
+    library_menus: RefCell<Vec<nwg::Menu>>,
 
-    #[nwg_control(parent: tray_menu, text: "Exit")]
-    #[nwg_events( OnMenuItemSelected: [SystemTrayApp::exit] )]
-    item_exit: nwg::MenuItem,
+    #[nwg_control(parent: tray_menu, text: "Presets")]
+    menu_presets: nwg::Menu,
 
+    // One radio item per preset, rebuilt by `refresh_preset_menu`
+    preset_items: RefCell<Vec<(nwg::MenuItem, String)>>,
+
+    // Raised when a menu item was picked, see `bind_dynamic_menus`
     #[nwg_control(parent: window)]
-    #[nwg_events( OnTimerTick: [SystemTrayApp::on_timer] )]
-    poll_timer: nwg::AnimationTimer,
-}
+    #[nwg_events( OnNotice: [SystemTrayApp::dynamic_menu_pick] )]
+    menu_notice: nwg::Notice,
+    menu_handler: RefCell<Option<nwg::EventHandler>>,
 
-// Helper function to set menu item text using Windows API
-fn set_menu_item_text(_menu: &nwg::Menu, item: &nwg::MenuItem, text: &str) {
-    use nwg::ControlHandle;
+    #[nwg_control(parent: tray_menu, text: "Current Profile")]
+    menu_profile: nwg::Menu,
 
-    let (hmenu, item_id) = match item.handle {
+    // Read-only lines describing the current profile, see `profile_lines`
+    #[nwg_control(parent: menu_profile, disabled: true)]
+    item_profile_display: nwg::MenuItem,
+
+    #[nwg_control(parent: menu_profile)]
+    item_profile_sep: nwg::MenuSeparator,
+
+    #[nwg_control(parent: menu_profile, disabled: true)]
+    item_profile_description: nwg::MenuItem,
+
+    #[nwg_control(parent: menu_profile, disabled: true)]
+    item_profile_device: nwg::MenuItem,
+
+    #[nwg_control(parent: menu_profile, disabled: true)]
+    item_profile_created: nwg::MenuItem,
+
+    #[nwg_control(parent: menu_profile, disabled: true)]
+    item_profile_version: nwg::MenuItem,
+
+    #[nwg_control(parent: menu_profile, disabled: true)]
+    item_profile_white: nwg::MenuItem,
+
+    #[nwg_control(parent: menu_profile, disabled: true)]
+    item_profile_luminance: nwg::MenuItem,
+
+    #[nwg_control(parent: menu_profile, disabled: true)]
+    item_profile_primaries: nwg::MenuItem,
+
+    #[nwg_control(parent: menu_profile, disabled: true)]
+    item_profile_coverage_xy: nwg::MenuItem,
+
+    #[nwg_control(parent: menu_profile, disabled: true)]
+    item_profile_area_xy: nwg::MenuItem,
+
+    #[nwg_control(parent: menu_profile, disabled: true)]
+    item_profile_coverage_uv: nwg::MenuItem,
+
+    #[nwg_control(parent: menu_profile, disabled: true)]
+    item_profile_area_uv: nwg::MenuItem,
+
+    #[nwg_control(parent: menu_profile, disabled: true)]
+    item_profile_vcgt: nwg::MenuItem,
+
+    #[nwg_control(parent: menu_profile, disabled: true)]
+    item_profile_mhc2: nwg::MenuItem,
+
+    #[nwg_control(parent: menu_profile, disabled: true)]
+    item_profile_mhc2_matrix: nwg::MenuItem,
+
+    #[nwg_control(parent: menu_profile, disabled: true)]
+    item_profile_curve_gamma: nwg::MenuItem,
+
+    #[nwg_control(parent: menu_profile, disabled: true)]
+    item_profile_curve_levels: nwg::MenuItem,
+
+    #[nwg_control(parent: menu_profile, disabled: true)]
+    item_profile_curve_issues: nwg::MenuItem,
+
+    #[nwg_control(parent: menu_profile, disabled: true)]
+    item_profile_curve_white: nwg::MenuItem,
+
+    #[nwg_control(parent: menu_profile, disabled: true)]
+    item_profile_measurements: nwg::MenuItem,
+
+    #[nwg_control(parent: menu_profile, disabled: true)]
+    item_profile_accuracy: nwg::MenuItem,
+
+    #[nwg_control(parent: menu_profile, disabled: true)]
+    item_profile_copyright: nwg::MenuItem,
+
+    #[nwg_control(parent: tray_menu, text: "Export Calibration")]
+    menu_export: nwg::Menu,
+
+    #[nwg_control(parent: menu_export, text: "Live Ramp...")]
+    #[nwg_events( OnMenuItemSelected: [SystemTrayApp::export_live] )]
+    item_export_live: nwg::MenuItem,
+
+    #[nwg_control(parent: menu_export, text: "Expected Ramp...")]
+    #[nwg_events( OnMenuItemSelected: [SystemTrayApp::export_expected] )]
+    item_export_expected: nwg::MenuItem,
+
+    #[nwg_control(parent: menu_export)]
+    item_export_sep: nwg::MenuSeparator,
+
+    // Entry count of exported files, one of `export::SIZES`
+    #[nwg_control(parent: menu_export, text: "256", check: true)]
+    #[nwg_events( OnMenuItemSelected: [SystemTrayApp::select_export_size(SELF, CTRL)] )]
+    item_export_256: nwg::MenuItem,
+
+    #[nwg_control(parent: menu_export, text: "1024", check: false)]
+    #[nwg_events( OnMenuItemSelected: [SystemTrayApp::select_export_size(SELF, CTRL)] )]
+    item_export_1024: nwg::MenuItem,
+
+    #[nwg_control(parent: menu_export, text: "4096", check: false)]
+    #[nwg_events( OnMenuItemSelected: [SystemTrayApp::select_export_size(SELF, CTRL)] )]
+    item_export_4096: nwg::MenuItem,
+
+    #[nwg_control(parent: tray_menu)]
+    item_sep1: nwg::MenuSeparator,
+
+    #[nwg_control(parent: tray_menu, text: "Polling", check: true)]
+    #[nwg_events( OnMenuItemSelected: [SystemTrayApp::toggle_polling] )]
+    item_polling: nwg::MenuItem,
+
+    // Pauses that are not saved, unlike unchecking `item_polling`
+    #[nwg_control(parent: tray_menu, text: "Pause")]
+    menu_pause: nwg::Menu,
+
+    #[nwg_control(parent: menu_pause, text: "15 minutes")]
+    #[nwg_events( OnMenuItemSelected: [SystemTrayApp::pause_15_minutes] )]
+    item_pause_15m: nwg::MenuItem,
+
+    #[nwg_control(parent: menu_pause, text: "1 hour")]
+    #[nwg_events( OnMenuItemSelected: [SystemTrayApp::pause_1_hour] )]
+    item_pause_1h: nwg::MenuItem,
+
+    #[nwg_control(parent: menu_pause, text: "Until restart")]
+    #[nwg_events( OnMenuItemSelected: [SystemTrayApp::pause_until_restart] )]
+    item_pause_restart: nwg::MenuItem,
+
+    #[nwg_control(parent: menu_pause)]
+    item_pause_sep: nwg::MenuSeparator,
+
+    #[nwg_control(parent: menu_pause, text: "Resume now")]
+    #[nwg_events( OnMenuItemSelected: [SystemTrayApp::resume_now] )]
+    item_pause_resume: nwg::MenuItem,
+
+    #[nwg_control(parent: tray_menu, text: "Startup", check: true)]
+    #[nwg_events( OnMenuItemSelected: [SystemTrayApp::toggle_startup] )]
+    item_startup: nwg::MenuItem,
+
+    #[nwg_control(parent: tray_menu, text: "Settings...")]
+    #[nwg_events( OnMenuItemSelected: [SystemTrayApp::open_settings] )]
+    item_settings: nwg::MenuItem,
+
+    #[nwg_control(parent: tray_menu)]
+    item_sep2: nwg::MenuSeparator,
+
+    #[nwg_control(parent: tray_menu, text: "Exit")]
+    #[nwg_events( OnMenuItemSelected: [SystemTrayApp::exit] )]
+    item_exit: nwg::MenuItem,
+
+    #[nwg_control(parent: window)]
+    #[nwg_events( OnTimerTick: [SystemTrayApp::on_timer] )]
+    poll_timer: nwg::AnimationTimer,
+
+    // Profile ages change slowly; hourly is plenty
+    #[nwg_control(parent: window, interval: Duration::from_secs(3600), active: true)]
+    #[nwg_events( OnTimerTick: [SystemTrayApp::check_ages] )]
+    age_timer: nwg::AnimationTimer,
+
+    // Schedule rules start on the minute; this keeps switches within 30 s
+    #[nwg_control(parent: window, interval: Duration::from_secs(30), active: true)]
+    #[nwg_events( OnTimerTick: [SystemTrayApp::check_schedule] )]
+    schedule_timer: nwg::AnimationTimer,
+
+    // Follows the foreground program for the application rules
+    #[nwg_control(parent: window, interval: Duration::from_secs(1), active: true)]
+    #[nwg_events( OnTimerTick: [SystemTrayApp::check_app_rules] )]
+    app_timer: nwg::AnimationTimer,
+
+    // Runs while a timed pause does: ends it and counts down in the tooltip
+    #[nwg_control(parent: window)]
+    #[nwg_events( OnTimerTick: [SystemTrayApp::check_pause] )]
+    pause_timer: nwg::AnimationTimer,
+
+    // Raised by the IPC server thread when a second instance sent a request
+    #[nwg_control(parent: window)]
+    #[nwg_events( OnNotice: [SystemTrayApp::process_requests] )]
+    ipc_notice: nwg::Notice,
+
+    // Raised when an event arrived on `events`, e.g. from the engine worker
+    #[nwg_control(parent: window)]
+    #[nwg_events( OnNotice: [SystemTrayApp::process_events] )]
+    event_notice: nwg::Notice,
+    events: RefCell<Option<mpsc::Receiver<Event>>>,
+}
+
+// Helper function to set menu item text using Windows API
+fn set_menu_item_text(_menu: &nwg::Menu, item: &nwg::MenuItem, text: &str) {
+    use nwg::ControlHandle;
+
+    let (hmenu, item_id) = match item.handle {
         ControlHandle::MenuItem(parent, id) => (parent, id),
         ControlHandle::NoHandle => return,
         _ => return,
@@ -646,33 +1430,193 @@ fn set_menu_item_text(_menu: &nwg::Menu, item: &nwg::MenuItem, text: &str) {
     }
 }
 
+// Submenus have no command id, so their text is set by position in the parent
+fn set_submenu_text(menu: &nwg::Menu, text: &str) {
+    use nwg::ControlHandle;
+    use windows::Win32::UI::WindowsAndMessaging::{GetMenuItemCount, GetSubMenu, HMENU, MIIM_STRING};
+    use windows::core::PWSTR;
+
+    let ControlHandle::Menu(parent, hmenu) = menu.handle else { return };
+    let parent = HMENU(parent as isize);
+    let text_wide: Vec<u16> = text.encode_utf16().chain(std::iter::once(0)).collect();
+
+    unsafe {
+        for position in 0..GetMenuItemCount(parent) {
+            if GetSubMenu(parent, position).0 != hmenu as isize {
+                continue;
+            }
+            let info = MENUITEMINFOW {
+                cbSize: std::mem::size_of::<MENUITEMINFOW>() as u32,
+                fMask: MIIM_STRING,
+                dwTypeData: PWSTR::from_raw(text_wide.as_ptr() as *mut u16),
+                cch: text_wide.len() as u32,
+                ..Default::default()
+            };
+            let _ = SetMenuItemInfoW(parent, position as u32, true, &info);
+            return;
+        }
+    }
+}
+
+// Removes the items of a submenu from position `first` on. Submenus are only
+// detached; their nwg owners destroy them when dropped.
+fn remove_menu_items(menu: &nwg::Menu, first: i32) {
+    use nwg::ControlHandle;
+    use windows::Win32::UI::WindowsAndMessaging::{GetMenuItemCount, RemoveMenu, HMENU, MF_BYPOSITION};
+
+    let ControlHandle::Menu(_, hmenu) = menu.handle else { return };
+    let hmenu = HMENU(hmenu as isize);
+    unsafe {
+        for position in (first..GetMenuItemCount(hmenu)).rev() {
+            let _ = RemoveMenu(hmenu, position as u32, MF_BYPOSITION);
+        }
+    }
+}
+
+// Shows a menu item's check mark as a radio bullet.
+fn set_radio_check(item: &nwg::MenuItem) {
+    use nwg::ControlHandle;
+    use windows::Win32::UI::WindowsAndMessaging::{HMENU, MFT_RADIOCHECK, MIIM_FTYPE};
+
+    let ControlHandle::MenuItem(hmenu, id) = item.handle else { return };
+    let info = MENUITEMINFOW {
+        cbSize: std::mem::size_of::<MENUITEMINFOW>() as u32,
+        fMask: MIIM_FTYPE,
+        fType: MFT_RADIOCHECK,
+        ..Default::default()
+    };
+    unsafe {
+        let _ = SetMenuItemInfoW(HMENU(hmenu as isize), id, false, &info);
+    }
+}
+
+// Hash of the library entry in use on a display: its calibration file, or
+// else its profile.
+fn active_library_hash(d: &display::Display) -> Option<String> {
+    let file = calibration::source_for(d).or_else(|| display::profile_path(d))?;
+    library::entry_for_file(&file).map(|e| e.hash)
+}
+
+// Runs a file dialog; None when cancelled.
+fn choose_file(parent: &nwg::Window, title: &str, action: nwg::FileDialogAction, filters: &str) -> Option<PathBuf> {
+    let mut dialog = nwg::FileDialog::default();
+    nwg::FileDialog::builder()
+        .title(title)
+        .action(action)
+        .filters(filters)
+        .build(&mut dialog)
+        .expect("Failed to create file dialog");
+
+    if !dialog.run(Some(parent)) {
+        return None;
+    }
+    dialog.get_selected_item().ok().map(PathBuf::from)
+}
+
+// The first display with an associated profile, and that profile's file.
+fn current_profile() -> Option<(display::Display, PathBuf)> {
+    display::enumerate().into_iter().find_map(|d| display::profile_path(&d).map(|p| (d, p)))
+}
+
 impl SystemTrayApp {
     fn init(&self) {
         // Icon is already loaded via nwg_resource
 
         // Set internationalized menu text
-        set_menu_item_text(&self.tray_menu, &self.item_reload, &get_str("tray_reload"));
-        set_menu_item_text(&self.tray_menu, &self.item_import, &get_str("tray_import"));
-        set_menu_item_text(&self.tray_menu, &self.item_polling, &get_str("tray_polling"));
-        set_menu_item_text(&self.tray_menu, &self.item_startup, &get_str("tray_startup"));
-        set_menu_item_text(&self.tray_menu, &self.item_settings, &get_str("tray_settings"));
-        set_menu_item_text(&self.tray_menu, &self.item_exit, &get_str("tray_exit"));
+        set_menu_item_text(&self.tray_menu, &self.item_reload, &get_str(Msg::TrayReload));
+        set_menu_item_text(&self.tray_menu, &self.item_import, &get_str(Msg::TrayImport));
+        set_menu_item_text(&self.tray_menu, &self.item_merge, &get_str(Msg::TrayMerge));
+        set_menu_item_text(&self.tray_menu, &self.item_polling, &get_str(Msg::TrayPolling));
+        set_menu_item_text(&self.tray_menu, &self.item_startup, &get_str(Msg::TrayStartup));
+        set_menu_item_text(&self.tray_menu, &self.item_settings, &get_str(Msg::TraySettings));
+        set_menu_item_text(&self.tray_menu, &self.item_exit, &get_str(Msg::TrayExit));
+        set_submenu_text(&self.menu_profile, &get_str(Msg::TrayCurrentProfile));
+        self.update_export_text();
+        self.update_pause_text();
+        self.check_ages();
+        self.bind_dynamic_menus();
+        self.listen_for_events();
+        self.check_schedule();
 
         if is_polling_enabled() {
             let interval = POLLING_INTERVAL_MS.load(Ordering::Relaxed);
             self.poll_timer.set_interval(Duration::from_millis(interval as u64));
             self.poll_timer.start();
         }
+
+        // Accept commands from second instances and the control API
+        let sender = self.ipc_notice.sender();
+        ipc::set_waker(move || sender.notice());
+        ipc::start_server();
+        rpc::start();
+        // Requests from our own command line were queued before the tray existed
+        self.process_requests();
+    }
+
+    fn process_requests(&self) {
+        let mut requests = ipc::take_pending().into_iter();
+        while let Some(pending) = requests.next() {
+            let Err(pending) = answer_on_worker(pending) else { continue };
+            let response = match &pending.request {
+                Request::Reload => {
+                    engine::reload_profile();
+                    Response::Ok(get_str(Msg::MsgReloadRequested))
+                }
+                Request::Install(_) | Request::Assign { .. } | Request::ApplyPreset(_) | Request::Status => {
+                    unreachable!("answered on the worker")
+                }
+                Request::Pause => {
+                    self.set_polling(false);
+                    Response::Ok(get_str(Msg::MsgPaused))
+                }
+                Request::PauseFor(seconds) => {
+                    let duration = seconds.map(Duration::from_secs);
+                    self.pause_reloads(duration);
+                    Response::Ok(pause_message(duration))
+                }
+                Request::Resume => {
+                    // Ending a pause leaves the saved polling setting alone
+                    if engine::paused().is_some() {
+                        self.resume_now();
+                    } else {
+                        self.set_polling(true);
+                    }
+                    Response::Ok(get_str(Msg::MsgResumed))
+                }
+                Request::OpenSettings => {
+                    self.open_settings();
+                    Response::Ok(get_str(Msg::MsgSettingsOpened))
+                }
+                Request::Quit => {
+                    // An explicit command, so no confirmation dialog
+                    pending.respond(Response::Ok(get_str(Msg::MsgQuitting)));
+                    // Requests queued behind QUIT will not run
+                    for other in requests {
+                        other.respond(Response::Error(get_str(Msg::MsgQuitting)));
+                    }
+                    nwg::stop_thread_dispatch();
+                    return;
+                }
+            };
+            pending.respond(response);
+        }
     }
 
     fn show_menu(&self) {
         // Update internationalized menu text (in case language was changed)
-        set_menu_item_text(&self.tray_menu, &self.item_reload, &get_str("tray_reload"));
-        set_menu_item_text(&self.tray_menu, &self.item_import, &get_str("tray_import"));
-        set_menu_item_text(&self.tray_menu, &self.item_polling, &get_str("tray_polling"));
-        set_menu_item_text(&self.tray_menu, &self.item_startup, &get_str("tray_startup"));
-        set_menu_item_text(&self.tray_menu, &self.item_settings, &get_str("tray_settings"));
-        set_menu_item_text(&self.tray_menu, &self.item_exit, &get_str("tray_exit"));
+        set_menu_item_text(&self.tray_menu, &self.item_reload, &get_str(Msg::TrayReload));
+        set_menu_item_text(&self.tray_menu, &self.item_import, &get_str(Msg::TrayImport));
+        set_menu_item_text(&self.tray_menu, &self.item_merge, &get_str(Msg::TrayMerge));
+        set_menu_item_text(&self.tray_menu, &self.item_polling, &get_str(Msg::TrayPolling));
+        set_menu_item_text(&self.tray_menu, &self.item_startup, &get_str(Msg::TrayStartup));
+        set_menu_item_text(&self.tray_menu, &self.item_settings, &get_str(Msg::TraySettings));
+        set_menu_item_text(&self.tray_menu, &self.item_exit, &get_str(Msg::TrayExit));
+        set_submenu_text(&self.menu_profile, &get_str(Msg::TrayCurrentProfile));
+        self.update_export_text();
+        self.update_pause_text();
+        self.refresh_profile_info();
+        self.refresh_library_menu();
+        self.refresh_preset_menu();
 
         // Update checks
         self.item_polling.set_checked(is_polling_enabled());
@@ -683,28 +1627,436 @@ impl SystemTrayApp {
     }
 
     fn reload_click(&self) {
-        reload_profile();
+        engine::reload_profile();
+    }
+
+    fn open_library(&self) {
+        open_library_window();
+    }
+
+    // Library and preset items are created at runtime, so the derived event
+    // handlers do not know them. This handler sees every menu selection of
+    // the window and leaves it to `dynamic_menu_pick` to tell whether it was
+    // one of ours.
+    fn bind_dynamic_menus(&self) {
+        let sender = self.menu_notice.sender();
+        let handler = nwg::full_bind_event_handler(&self.window.handle, move |event, _data, handle| {
+            if matches!(event, nwg::Event::OnMenuItemSelected) {
+                MENU_PICK.with(|pick| pick.set(Some(handle)));
+                sender.notice();
+            }
+        });
+        *self.menu_handler.borrow_mut() = Some(handler);
+    }
+
+    // The switch runs on the engine worker; failures come back as an
+    // `ApplyFailed` event, see `process_events`.
+    fn dynamic_menu_pick(&self) {
+        let Some(picked) = MENU_PICK.with(|pick| pick.take()) else { return };
+        let entry = self.library_items.borrow().iter().find(|(item, ..)| item.handle == picked).map(|(_, n, hash)| (*n, hash.clone()));
+        let preset = self.preset_items.borrow().iter().find(|(item, _)| item.handle == picked).map(|(_, name)| name.clone());
+        match (entry, preset) {
+            (Some((number, hash)), _) => assign_on_worker(hash, number, |_| {}),
+            (None, Some(name)) => queue_preset(&name, |_| {}),
+            (None, None) => {}
+        }
+    }
+
+    // Forwards the event bus to the UI thread.
+    fn listen_for_events(&self) {
+        let (tx, rx) = mpsc::channel();
+        let notice = self.event_notice.sender();
+        let bus = events::subscribe();
+        std::thread::spawn(move || {
+            for event in bus {
+                if tx.send(event).is_err() {
+                    break;
+                }
+                notice.notice();
+            }
+        });
+        *self.events.borrow_mut() = Some(rx);
+    }
+
+    fn process_events(&self) {
+        let events: Vec<Event> = self.events.borrow().as_ref().map(|rx| rx.try_iter().collect()).unwrap_or_default();
+        for event in events {
+            match event {
+                // The displays use other profiles now
+                Event::ProfileInstalled(_) | Event::PresetApplied(_) => self.check_ages(),
+                Event::ApplyFailed(error) => {
+                    self.tray.show(&error, Some(&get_str(Msg::ApplyFailedTitle)), Some(nwg::TrayNotificationFlags::ERROR_ICON), None);
+                    self.refresh_profile_info();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // Presets as radio items, the active one selected.
+    fn refresh_preset_menu(&self) {
+        set_submenu_text(&self.menu_presets, &get_str(Msg::TrayPresets));
+        remove_menu_items(&self.menu_presets, 0);
+        self.preset_items.borrow_mut().clear();
+
+        let presets = presets::list();
+        let active = presets::active();
+        let mut items = Vec::new();
+        if presets.is_empty() {
+            let mut item = nwg::MenuItem::default();
+            let built = nwg::MenuItem::builder()
+                .text(&get_str(Msg::TrayPresetsEmpty))
+                .disabled(true)
+                .parent(&self.menu_presets)
+                .build(&mut item);
+            if built.is_ok() {
+                items.push((item, String::new()));
+            }
+        }
+        for preset in presets {
+            let mut item = nwg::MenuItem::default();
+            let built = nwg::MenuItem::builder()
+                .text(&preset.name)
+                .check(active.as_deref() == Some(preset.name.as_str()))
+                .parent(&self.menu_presets)
+                .build(&mut item);
+            if built.is_ok() {
+                set_radio_check(&item);
+                items.push((item, preset.name));
+            }
+        }
+        *self.preset_items.borrow_mut() = items;
+    }
+
+    // Lists the most recent library entries, checked where they are in use.
+    // With several displays each one gets a submenu, entries made for that
+    // monitor first.
+    fn refresh_library_menu(&self) {
+        set_submenu_text(&self.menu_library, &get_str(Msg::TrayLibrary));
+        set_menu_item_text(&self.menu_library, &self.item_library_manage, &get_str(Msg::TrayLibraryManage));
+        // Everything after "Manage Library..." and the separator
+        remove_menu_items(&self.menu_library, 2);
+        self.library_items.borrow_mut().clear();
+        self.library_menus.borrow_mut().clear();
+
+        let entries: Vec<library::Entry> = library::list().into_iter().take(TRAY_LIBRARY_ENTRIES).collect();
+        let displays = display::enumerate();
+        let mut items = Vec::new();
+        let mut menus = Vec::new();
+        if entries.is_empty() {
+            let mut item = nwg::MenuItem::default();
+            let built = nwg::MenuItem::builder()
+                .text(&get_str(Msg::TrayLibraryEmpty))
+                .disabled(true)
+                .parent(&self.menu_library)
+                .build(&mut item);
+            if built.is_ok() {
+                items.push((item, 0, String::new()));
+            }
+        }
+
+        for d in displays.iter().filter(|_| !entries.is_empty()) {
+            let mut parent = self.menu_library.handle;
+            if displays.len() > 1 {
+                let mut menu = nwg::Menu::default();
+                if nwg::Menu::builder().text(&display_label(d)).parent(&self.menu_library).build(&mut menu).is_err() {
+                    continue;
+                }
+                parent = menu.handle;
+                menus.push(menu);
+            }
+            let active = active_library_hash(d);
+            let mut ordered: Vec<&library::Entry> = entries.iter().collect();
+            ordered.sort_by_key(|e| e.monitor.as_deref() != Some(d.id.as_str()));
+            for entry in ordered {
+                let mut item = nwg::MenuItem::default();
+                let built = nwg::MenuItem::builder()
+                    .text(entry.title())
+                    .check(active.as_deref() == Some(entry.hash.as_str()))
+                    .parent(parent)
+                    .build(&mut item);
+                if built.is_ok() {
+                    items.push((item, d.index + 1, entry.hash.clone()));
+                }
+            }
+        }
+        *self.library_items.borrow_mut() = items;
+        *self.library_menus.borrow_mut() = menus;
+    }
+
+    fn check_schedule(&self) {
+        run_schedule();
+        self.refresh_profile_info();
+    }
+
+    fn check_app_rules(&self) {
+        if run_app_rules() {
+            self.refresh_profile_info();
+        }
+    }
+
+    // Switches the tray into its warning state while any display is past its
+    // re-calibration limit, with a balloon for each one that just got there.
+    fn check_ages(&self) {
+        let ages = age::check_all();
+        for age in age::newly_overdue(&ages) {
+            let file = age.profile.as_ref().and_then(|p| p.file_name()).map_or(String::new(), |f| f.to_string_lossy().to_string());
+            let text = get_str_args(
+                Msg::AgeNotifyBody,
+                &[
+                    ("display", &age.display.device),
+                    ("file", &file),
+                    ("days", &(age.age_days.unwrap_or_default() as u32).to_string()),
+                    ("max", &age.max_days.unwrap_or_default().to_string()),
+                ],
+            );
+            let title = get_str(Msg::AgeNotifyTitle);
+            self.tray.show(&text, Some(&title), Some(nwg::TrayNotificationFlags::WARNING_ICON), None);
+        }
+        *self.overdue.borrow_mut() = ages.into_iter().filter(age::ProfileAge::is_overdue).collect();
+        self.refresh_profile_info();
+    }
+
+    // Updates the "Current Profile" submenu and the tooltip. Profiles are
+    // cached by `icc::inspect`, so this is cheap enough for every poll.
+    fn refresh_profile_info(&self) {
+        let items = [
+            &self.item_profile_description,
+            &self.item_profile_device,
+            &self.item_profile_created,
+            &self.item_profile_version,
+            &self.item_profile_white,
+            &self.item_profile_luminance,
+            &self.item_profile_primaries,
+            &self.item_profile_coverage_xy,
+            &self.item_profile_area_xy,
+            &self.item_profile_coverage_uv,
+            &self.item_profile_area_uv,
+            &self.item_profile_vcgt,
+            &self.item_profile_mhc2,
+            &self.item_profile_mhc2_matrix,
+            &self.item_profile_curve_gamma,
+            &self.item_profile_curve_levels,
+            &self.item_profile_curve_issues,
+            &self.item_profile_curve_white,
+            &self.item_profile_measurements,
+            &self.item_profile_accuracy,
+            &self.item_profile_copyright,
+        ];
+        let mut tip = String::from("Icc Auto Reloader");
+
+        let current = current_profile();
+        let info = current.as_ref().map(|(_, path)| icc::inspect(path));
+        let header = match &current {
+            Some((d, path)) => {
+                let file = path.file_name().map_or(String::new(), |f| f.to_string_lossy().to_string());
+                get_str_args(Msg::InfoDisplay, &[("display", &d.device), ("file", &file)])
+            }
+            None => get_str(Msg::InfoNoProfile),
+        };
+        set_menu_item_text(&self.tray_menu, &self.item_profile_display, &header);
+
+        match info {
+            Some(Ok(info)) => {
+                for (item, line) in items.iter().zip(profile_lines(&info)) {
+                    set_menu_item_text(&self.tray_menu, item, &line);
+                }
+                tip.push('\n');
+                tip.push_str(info.description.as_deref().unwrap_or(&header));
+                if let (true, Some((d, _))) = (info.relies_on_mhc2, &current) {
+                    tip.push('\n');
+                    tip.push_str(&get_str_args(Msg::TipMhc2Only, &[("display", &d.device)]));
+                }
+            }
+            Some(Err(e)) => {
+                let error = get_str_args(Msg::MsgProfileUnreadable, &[("error", &e)]);
+                set_menu_item_text(&self.tray_menu, items[0], &error);
+                for item in &items[1..] {
+                    set_menu_item_text(&self.tray_menu, item, "-");
+                }
+                tip.push('\n');
+                tip.push_str(&header);
+            }
+            None => {
+                for item in &items {
+                    set_menu_item_text(&self.tray_menu, item, "-");
+                }
+            }
+        }
+
+        match engine::paused().map(|p| p.remaining()) {
+            Some(Some(left)) => {
+                let minutes = left.as_secs().div_ceil(60).to_string();
+                tip.push('\n');
+                tip.push_str(&get_str_args(Msg::TipPausedFor, &[("minutes", &minutes)]));
+            }
+            Some(None) => {
+                tip.push('\n');
+                tip.push_str(&get_str(Msg::TipPausedRestart));
+            }
+            None => {}
+        }
+        if let Some(held) = engine::held_by() {
+            tip.push('\n');
+            tip.push_str(&get_str_args(Msg::TipHeld, &[("reason", &held)]));
+        }
+        for overdue in self.overdue.borrow().iter() {
+            tip.push('\n');
+            tip.push_str(&get_str_args(Msg::AgeTipOverdue, &[("display", &overdue.display.device)]));
+        }
+        let icon = if self.overdue.borrow().is_empty() { &self.tray_icon } else { &self.warning_icon };
+        self.tray.set_icon(icon);
+
+        // The shell truncates tooltips at 128 UTF-16 units
+        let tip: String = tip.chars().take(120).collect();
+        self.tray.set_tip(&tip);
     }
 
     fn import_click(&self) {
         let mut dialog = nwg::FileDialog::default();
         nwg::FileDialog::builder()
-            .title(&get_str("msg_select_profile"))
+            .title(&get_str(Msg::MsgSelectProfile))
             .action(nwg::FileDialogAction::Open)
             .build(&mut dialog)
             .expect("Failed to create file dialog");
 
         if dialog.run(Some(&self.window)) {
             if let Ok(path) = dialog.get_selected_item() {
-                install_profile(&path.to_string_lossy());
+                show_install_result(&install_profile(&path.to_string_lossy()));
+                self.check_ages();
             }
         }
     }
 
+    fn update_export_text(&self) {
+        set_submenu_text(&self.menu_export, &get_str(Msg::TrayExport));
+        set_menu_item_text(&self.tray_menu, &self.item_export_live, &get_str(Msg::TrayExportLive));
+        set_menu_item_text(&self.tray_menu, &self.item_export_expected, &get_str(Msg::TrayExportExpected));
+        for (item, size) in self.export_size_items().into_iter().zip(export::SIZES) {
+            set_menu_item_text(&self.tray_menu, item, &get_str_args(Msg::TrayExportSize, &[("size", &size.to_string())]));
+        }
+    }
+
+    fn export_size_items(&self) -> [&nwg::MenuItem; 3] {
+        [&self.item_export_256, &self.item_export_1024, &self.item_export_4096]
+    }
+
+    fn select_export_size(&self, selected: &nwg::MenuItem) {
+        for item in self.export_size_items() {
+            item.set_checked(item.handle == selected.handle);
+        }
+    }
+
+    fn export_live(&self) {
+        self.export_ramp(RampSource::Live);
+    }
+
+    fn export_expected(&self) {
+        self.export_ramp(RampSource::Expected);
+    }
+
+    // Saves the first display's ramp in the format picked by extension.
+    fn export_ramp(&self, source: RampSource) {
+        let filters = "ArgyllCMS (*.cal)|1D LUT (*.cube)|CSV (*.csv)";
+        let Some(mut path) = choose_file(&self.window, &get_str(Msg::TrayExport), nwg::FileDialogAction::Save, filters) else {
+            return;
+        };
+        if export::Format::from_path(&path).is_none() {
+            path.set_extension("cal");
+        }
+        let size = self
+            .export_size_items()
+            .into_iter()
+            .zip(export::SIZES)
+            .find(|(item, _)| item.checked())
+            .map_or(export::SIZES[0], |(_, size)| size);
+
+        match export::source_ramp(&source, 1).and_then(|ramp| export::save(&path, &ramp, size)) {
+            Ok(()) => nwg::simple_message("Success", &get_str_args(Msg::MsgExportDone, &[("file", &path.to_string_lossy())])),
+            Err(e) => nwg::error_message("Error", &get_str_args(Msg::MsgExportFailed, &[("error", &e)])),
+        };
+    }
+
+    // Asks for a profile, the curves to put in its vcgt and where to save
+    // the result.
+    fn merge_click(&self) {
+        use nwg::FileDialogAction::{Open, Save};
+        let profiles = "ICC (*.icc;*.icm)";
+        let Some(profile) = choose_file(&self.window, &get_str(Msg::MsgSelectProfile), Open, profiles) else { return };
+        let curve_filters = "Calibration (*.cal;*.cube;*.csv;*.icc;*.icm)";
+        let Some(curves) = choose_file(&self.window, &get_str(Msg::MsgSelectCurves), Open, curve_filters) else { return };
+        let Some(mut output) = choose_file(&self.window, &get_str(Msg::TrayMerge), Save, profiles) else { return };
+        if output.extension().is_none() {
+            output.set_extension("icc");
+        }
+
+        match icc_writer::merge_calibration(&profile, &curves, Some(&output)) {
+            Ok(path) => nwg::simple_message("Success", &get_str_args(Msg::MsgMergeDone, &[("file", &path.to_string_lossy())])),
+            Err(e) => nwg::error_message("Error", &get_str_args(Msg::MsgMergeFailed, &[("error", &e)])),
+        };
+    }
+
+    fn update_pause_text(&self) {
+        set_submenu_text(&self.menu_pause, &get_str(Msg::TrayPause));
+        set_menu_item_text(&self.menu_pause, &self.item_pause_15m, &get_str(Msg::TrayPause15m));
+        set_menu_item_text(&self.menu_pause, &self.item_pause_1h, &get_str(Msg::TrayPause1h));
+        set_menu_item_text(&self.menu_pause, &self.item_pause_restart, &get_str(Msg::TrayPauseRestart));
+        set_menu_item_text(&self.menu_pause, &self.item_pause_resume, &get_str(Msg::TrayPauseResume));
+        self.item_pause_resume.set_enabled(engine::paused().is_some());
+    }
+
+    fn pause_15_minutes(&self) {
+        self.pause_reloads(Some(Duration::from_secs(15 * 60)));
+    }
+
+    fn pause_1_hour(&self) {
+        self.pause_reloads(Some(Duration::from_secs(3600)));
+    }
+
+    fn pause_until_restart(&self) {
+        self.pause_reloads(None);
+    }
+
+    fn pause_reloads(&self, duration: Option<Duration>) {
+        engine::pause_for(duration);
+        self.check_pause();
+    }
+
+    // Ends a pause early, with an immediate drift check like when it runs out.
+    fn resume_now(&self) {
+        if engine::end_pause() {
+            self.check_pause();
+        }
+    }
+
+    // Keeps the countdown in the tooltip current and runs the drift check as
+    // soon as a pause is over.
+    fn check_pause(&self) {
+        match engine::paused().and_then(|p| p.remaining()) {
+            Some(left) => {
+                // Next tick when the minute shown changes, or when the pause ends
+                let into_minute = Duration::from_millis((left.as_millis() % 60_000) as u64);
+                let next = if into_minute.is_zero() { Duration::from_secs(60) } else { into_minute };
+                self.pause_timer.set_interval(next);
+                self.pause_timer.start();
+            }
+            None => {
+                self.pause_timer.stop();
+                if engine::paused().is_none() && is_polling_enabled() {
+                    engine::poll_once();
+                }
+            }
+        }
+        self.refresh_profile_info();
+    }
+
     fn toggle_polling(&self) {
-        let current = is_polling_enabled();
-        set_polling_enabled(!current);
-        if !current { // If now enabled
+        self.set_polling(!is_polling_enabled());
+    }
+
+    fn set_polling(&self, enabled: bool) {
+        set_polling_enabled(enabled);
+        if enabled {
             let interval = POLLING_INTERVAL_MS.load(Ordering::Relaxed);
             self.poll_timer.set_interval(Duration::from_millis(interval as u64));
             self.poll_timer.start();
@@ -754,15 +2106,14 @@ impl SystemTrayApp {
             self.poll_timer.start();
         }
 
-        if is_gamma_linear() {
-            reload_profile();
-        }
+        engine::poll_once();
+        self.refresh_profile_info();
     }
 
     fn exit(&self) {
         // Show confirmation dialog before exiting
-        let title = get_str("exit_confirm_title");
-        let msg = get_str("exit_confirm_msg");
+        let title = get_str(Msg::ExitConfirmTitle);
+        let msg = get_str(Msg::ExitConfirmMsg);
 
         let title_wide: Vec<u16> = title.encode_utf16().chain(std::iter::once(0)).collect();
         let msg_wide: Vec<u16> = msg.encode_utf16().chain(std::iter::once(0)).collect();
@@ -784,78 +2135,269 @@ impl SystemTrayApp {
 
 // --- Helper Functions ---
 
-fn is_gamma_linear() -> bool {
-    unsafe {
-        let hdc = GetDC(None);
-        if hdc.is_invalid() {
-            return false;
+fn install_profile(path_str: &str) -> Result<(), String> {
+    let path = Path::new(path_str);
+    check_importable(path)?;
+    let displays = display::enumerate();
+    let entry = library::import(path, displays.first(), &displays).map_err(|e| {
+        engine::record_failure(&format!("Cannot add {} to the library: {}", path_str, e));
+        get_str_args(Msg::MsgLibraryImportFailed, &[("error", &e)])
+    })?;
+    // Like `dispwin -I`, a plain install targets the first display
+    install_entry(&entry, 1)
+}
+
+// Refuses files that would not work once installed: unreadable or broken
+// profiles and calibration files without usable curves. Warnings only go to
+// the log.
+fn check_importable(path: &Path) -> Result<(), String> {
+    if calibration::is_calibration_file(path) {
+        return calibration::load(path).map(|_| ()).map_err(|e| {
+            engine::record_failure(&format!("Cannot use calibration file {}: {}", path.display(), e));
+            get_str_args(Msg::MsgCalibrationInvalid, &[("error", &e)])
+        });
+    }
+    let issues = lint::check_file(path).map_err(|e| {
+        engine::record_failure(&format!("Cannot read profile {}: {}", path.display(), e));
+        get_str_args(Msg::MsgProfileUnreadable, &[("error", &e)])
+    })?;
+    for issue in issues.iter().filter(|i| i.severity != lint::Severity::Error) {
+        log::warn(&format!("{}: {} ({})", path.display(), issue.message, issue.code));
+    }
+    if lint::has_errors(&issues) {
+        engine::record_failure(&format!("Profile {} has errors, not installed", path.display()));
+        return Err(get_str_args(Msg::MsgProfileHasErrors, &[("issues", lint::format_text(&issues).trim_end())]));
+    }
+    Ok(())
+}
+
+// Installs a library entry on display `number` (1-based, as in `dispwin -d`).
+// Runs dispwin; see `assign_on_worker` for callers that must not wait.
+fn assign_library_entry(key: &str, number: usize) -> Result<library::Entry, String> {
+    let entry = library::find(key).map_err(|e| get_str_args(Msg::MsgAssignFailed, &[("error", &e)]))?;
+    install_entry(&entry, number)?;
+    Ok(entry)
+}
+
+// Installs an entry by hand. The display no longer follows a preset, so none
+// is active afterwards.
+fn install_entry(entry: &library::Entry, number: usize) -> Result<(), String> {
+    apply_entry(entry, number, &display::enumerate())?;
+    presets::set_active(None);
+    engine::reload_profile();
+    events::emit(Event::ProfileInstalled(entry.path().to_string_lossy().to_string()));
+    Ok(())
+}
+
+// Assigns every entry of a preset, then reloads once. Displays that fail are
+// reported, the others keep their new calibration. Runs dispwin; see
+// `queue_preset` for callers that must not wait.
+fn apply_preset(name: &str) -> Result<presets::Preset, String> {
+    let preset = presets::find(name).ok_or_else(|| get_str_args(Msg::MsgPresetUnknown, &[("name", name)]))?;
+    let displays = display::enumerate();
+    let mut errors = Vec::new();
+    for (number, hash) in preset.targets(&displays) {
+        let result = library::find(&hash).map_err(|e| get_str_args(Msg::MsgAssignFailed, &[("error", &e)]));
+        if let Err(e) = result.and_then(|entry| apply_entry(&entry, number, &displays)) {
+            errors.push(e);
         }
+    }
+    presets::set_active(Some(&preset.name));
+    log::info(&format!("Switched to preset {}", preset.name));
+    // The reload and every drift check from here on use the new sources
+    engine::reload_profile();
+    events::emit(Event::PresetApplied(preset.name.clone()));
+    if errors.is_empty() {
+        Ok(preset)
+    } else {
+        Err(errors.join("\n"))
+    }
+}
+
+// Preset switches handed to the engine worker that have not run yet, oldest
+// first.
+static QUEUED_PRESETS: Mutex<Vec<String>> = Mutex::new(Vec::new());
 
-        let mut ramp = [[0u16; 256]; 3]; 
-        let mut result_bool = false;
+// The preset in use once the queued switches have run.
+fn expected_preset() -> Option<String> {
+    QUEUED_PRESETS.lock().ok().and_then(|q| q.last().cloned()).or_else(presets::active)
+}
 
-        // Dynamic load GetDeviceGammaRamp to avoid link errors
-        let lib = GetModuleHandleW(w!("gdi32.dll"));
-        if let Ok(lib) = lib {
-            let proc = GetProcAddress(lib, PCSTR(b"GetDeviceGammaRamp\0".as_ptr() as _));
-            if let Some(proc) = proc {
-                 let func: unsafe extern "system" fn(HDC, *mut c_void) -> BOOL = std::mem::transmute(proc);
-                 result_bool = func(hdc, &mut ramp as *mut _ as *mut c_void).as_bool();
+// Runs `apply_preset` on the engine worker and hands the outcome to `done`
+// there. Failures are also published as `ApplyFailed`.
+fn queue_preset<F>(name: &str, done: F)
+where
+    F: FnOnce(Result<presets::Preset, String>) + Send + 'static,
+{
+    let name = name.to_string();
+    if let Ok(mut queued) = QUEUED_PRESETS.lock() {
+        queued.push(name.clone());
+    }
+    engine::run_on_worker(move || {
+        let result = apply_preset(&name);
+        if let Ok(mut queued) = QUEUED_PRESETS.lock() {
+            if let Some(i) = queued.iter().position(|n| *n == name) {
+                queued.remove(i);
             }
         }
+        if let Err(e) = &result {
+            events::emit(Event::ApplyFailed(e.clone()));
+        }
+        done(result);
+    });
+}
 
-        ReleaseDC(None, hdc);
-
-        if !result_bool {
-            return false;
+// `assign_library_entry` on the engine worker, like `queue_preset`.
+fn assign_on_worker<F>(key: String, number: usize, done: F)
+where
+    F: FnOnce(Result<library::Entry, String>) + Send + 'static,
+{
+    engine::run_on_worker(move || {
+        let result = assign_library_entry(&key, number);
+        if let Err(e) = &result {
+            events::emit(Event::ApplyFailed(e.clone()));
         }
+        done(result);
+    });
+}
 
-        let tolerance = 256; 
+// Install, assign and preset requests run dispwin, and the status report
+// reads every profile (and dispwin's version the first time), so they are
+// answered from the engine worker instead of the thread that took them from
+// the queue. Other requests are handed back.
+fn answer_on_worker(pending: ipc::Pending) -> Result<(), ipc::Pending> {
+    match pending.request.clone() {
+        Request::Install(path) => engine::run_on_worker(move || {
+            pending.respond(match install_profile(&path) {
+                Ok(()) => Response::Ok(get_str(Msg::MsgInstallSuccess)),
+                Err(msg) => Response::Error(msg),
+            });
+        }),
+        Request::Assign { display, entry } => assign_on_worker(entry, display, move |result| {
+            pending.respond(match result {
+                Ok(entry) => Response::Ok(get_str_args(Msg::MsgAssignDone, &[("file", &entry.name), ("display", &display.to_string())])),
+                Err(msg) => Response::Error(msg),
+            });
+        }),
+        Request::ApplyPreset(name) => queue_preset(&name, move |result| {
+            pending.respond(match result {
+                Ok(preset) => Response::Ok(get_str_args(Msg::MsgPresetApplied, &[("name", &preset.name)])),
+                Err(msg) => Response::Error(msg),
+            });
+        }),
+        Request::Status => engine::run_on_worker(move || pending.respond(Response::Ok(status::report(true).to_string()))),
+        _ => return Err(pending),
+    }
+    Ok(())
+}
 
-        for channel in 0..3 {
-            for i in 0..256 {
-                let expected = (i as u32 * 65535 / 255) as i32;
-                let actual = ramp[channel][i] as i32;
-                if (actual - expected).abs() > tolerance {
-                    return false; 
-                }
+// Runs the schedule rule that just took effect, if any, on the engine worker.
+// Presets and entries already in use are left alone, so starting up does not
+// reinstall them.
+fn run_schedule() {
+    let Some(action) = schedule::due(&schedule::SystemClock) else { return };
+    let report = |action: &schedule::Action, result: Result<(), String>| match result {
+        Ok(()) => log::info(&format!("Schedule switched to {}", schedule::describe(action))),
+        Err(e) => engine::record_failure(&format!("Schedule could not switch to {}: {}", schedule::describe(action), e)),
+    };
+    match action.clone() {
+        schedule::Action::Preset(name) => {
+            if expected_preset().is_some_and(|active| active.eq_ignore_ascii_case(&name)) {
+                return;
             }
+            queue_preset(&name, move |result| report(&action, result.map(|_| ())));
+        }
+        schedule::Action::Entry { entry, display } => {
+            let displays = display::enumerate();
+            let in_use = displays.get(display.saturating_sub(1)).and_then(active_library_hash);
+            if library::find(&entry).is_ok_and(|e| in_use.as_deref() == Some(e.hash.as_str())) {
+                return;
+            }
+            assign_on_worker(entry, display, move |result| report(&action, result.map(|_| ())));
         }
-
-        true 
     }
 }
 
-fn reload_profile() {
-    let dispwin_path = find_dispwin();
-    if let Some(path) = dispwin_path {
-        let _ = Command::new(path)
-            .arg("-L")
-            .creation_flags(0x08000000) 
-            .spawn(); 
+// Reply to a pause request: for how long, or until restart.
+fn pause_message(duration: Option<Duration>) -> String {
+    match duration {
+        Some(d) => get_str_args(Msg::MsgPausedFor, &[("minutes", &d.as_secs().div_ceil(60).to_string())]),
+        None => get_str(Msg::MsgPausedRestart),
     }
 }
 
-fn install_profile(path_str: &str) {
-    let dispwin_path = find_dispwin();
-    if let Some(exe) = dispwin_path {
-        let status = Command::new(&exe)
-            .arg("-I")
-            .arg(path_str)
-            .creation_flags(0x08000000)
-            .status();
-            
-        if let Ok(s) = status {
-            if s.success() {
-                reload_profile();
-                nwg::simple_message("Success", &get_str("msg_install_success"));
-            } else {
-                nwg::error_message("Error", &get_str("msg_install_fail"));
+// Carries out what the application rules ask for after a focus change or a
+// program exit. Returns whether anything happened. Preset switches run on the
+// engine worker; until they have, the rules already see the preset they asked
+// for.
+fn run_app_rules() -> bool {
+    let effects = app_rules::check(expected_preset().as_deref());
+    for effect in &effects {
+        match effect {
+            app_rules::Effect::ApplyPreset(name) => {
+                let preset = name.clone();
+                queue_preset(name, move |result| {
+                    if let Err(e) = result {
+                        engine::record_failure(&format!("Application rule could not switch to preset {}: {}", preset, e));
+                    }
+                });
+            }
+            app_rules::Effect::Suspend(program) => engine::hold("app_rules", Some(program)),
+            app_rules::Effect::Resume => engine::hold("app_rules", None),
+            app_rules::Effect::Reload => {
+                log::info("A program with a reload_on_exit rule exited, reloading");
+                engine::reload_profile();
             }
         }
+    }
+    !effects.is_empty()
+}
+
+// Profiles are installed with `dispwin -I`; a calibration file is checked,
+// then kept loaded on the display like a profile's vcgt. Either way the
+// library copy is used, so the original file may be moved or deleted. The
+// caller reloads.
+fn apply_entry(entry: &library::Entry, number: usize, displays: &[display::Display]) -> Result<(), String> {
+    let Some(exe) = find_dispwin() else {
+        engine::record_failure("dispwin.exe not found, cannot install profile");
+        return Err(get_str(Msg::MsgDispwinMissing));
+    };
+    let Some(target) = displays.get(number.saturating_sub(1)) else {
+        let e = format!("display {} not found", number);
+        return Err(get_str_args(Msg::MsgAssignFailed, &[("error", &e)]));
+    };
+    let path = entry.path();
+
+    if entry.calibration {
+        calibration::set_source(target, Some(path.as_path()));
+        log::info(&format!("Using calibration file {} for {}", path.display(), target.device));
     } else {
-        nwg::error_message("Error", &get_str("msg_dispwin_missing"));
+        let mut cmd = Command::new(&exe);
+        // Same addressing as the reload, see `engine::start_reload`
+        if displays.len() > 1 {
+            cmd.arg("-d").arg(number.to_string());
+        }
+        let status = cmd.arg("-I").arg(&path).creation_flags(0x08000000).status();
+        if !status.is_ok_and(|s| s.success()) {
+            engine::record_failure(&format!("dispwin -I failed for {}", path.display()));
+            return Err(get_str(Msg::MsgInstallFail));
+        }
+        log::info(&format!("Installed profile {} on {}", path.display(), target.device));
+        engine::warn_if_mhc2_only(&path);
+        // The display's calibration comes from the profile again
+        calibration::set_source(target, None);
     }
+    if let Err(e) = library::set_monitor(&entry.hash, target) {
+        log::warn(&format!("Cannot update the library index: {}", e));
+    }
+    Ok(())
+}
+
+fn show_install_result(result: &Result<(), String>) {
+    match result {
+        Ok(()) => nwg::simple_message("Success", &get_str(Msg::MsgInstallSuccess)),
+        Err(msg) => nwg::error_message("Error", msg),
+    };
 }
 
 fn find_dispwin() -> Option<PathBuf> {
@@ -872,6 +2414,31 @@ fn find_dispwin() -> Option<PathBuf> {
     None
 }
 
+fn color_directory() -> PathBuf {
+    let root = std::env::var_os("SystemRoot").map(PathBuf::from).unwrap_or_else(|| PathBuf::from(r"C:\Windows"));
+    root.join("System32").join("spool").join("drivers").join("color")
+}
+
+// Per-user data: log file and other state that is not a setting
+fn app_data_dir() -> PathBuf {
+    std::env::var_os("LOCALAPPDATA")
+        .map(PathBuf::from)
+        .unwrap_or_else(std::env::temp_dir)
+        .join("IccAutoReloader")
+}
+
+fn list_installed_profiles() -> Vec<PathBuf> {
+    let mut profiles: Vec<PathBuf> = std::fs::read_dir(color_directory())
+        .map(|entries| entries.filter_map(|e| e.ok()).map(|e| e.path()).collect())
+        .unwrap_or_default();
+    profiles.retain(|p| {
+        let ext = p.extension().and_then(|e| e.to_str()).map(|e| e.to_ascii_lowercase());
+        matches!(ext.as_deref(), Some("icc") | Some("icm"))
+    });
+    profiles.sort();
+    profiles
+}
+
 // Registry Logic (using windows crate primitives for compatibility)
 fn get_reg_dword(val_name: PCWSTR) -> Option<u32> {
     unsafe {
@@ -894,16 +2461,85 @@ fn get_reg_dword(val_name: PCWSTR) -> Option<u32> {
     None
 }
 
+fn get_reg_string(val_name: PCWSTR) -> Option<String> {
+    unsafe {
+        let mut key: HKEY = HKEY::default();
+        if RegOpenKeyExW(HKEY_CURRENT_USER, REG_SUBKEY, 0, KEY_READ, &mut key).is_err() {
+            return None;
+        }
+        let mut buf = [0u16; 1024];
+        let mut size = (buf.len() * 2) as u32;
+        let res = RegQueryValueExW(key, val_name, None, None, Some(buf.as_mut_ptr() as *mut u8), Some(&mut size));
+        let _ = RegCloseKey(key);
+        if res.is_err() {
+            return None;
+        }
+        let len = (size as usize / 2).min(buf.len());
+        let len = buf[..len].iter().position(|&c| c == 0).unwrap_or(len);
+        Some(String::from_utf16_lossy(&buf[..len]))
+    }
+}
+
+fn set_reg_string(val_name: PCWSTR, value: &str) {
+    let wide: Vec<u16> = value.encode_utf16().chain(std::iter::once(0)).collect();
+    unsafe {
+        let mut key: HKEY = HKEY::default();
+        if RegCreateKeyExW(HKEY_CURRENT_USER, REG_SUBKEY, 0, None, REG_OPTION_NON_VOLATILE, KEY_WRITE, None, &mut key, None).is_ok() {
+            let _ = RegSetValueExW(key, val_name, 0, REG_SZ, Some(std::slice::from_raw_parts(wide.as_ptr() as *const u8, wide.len() * 2)));
+            let _ = RegCloseKey(key);
+        }
+    }
+}
+
+fn set_reg_dword(val_name: PCWSTR, value: u32) {
+    unsafe {
+        let mut key: HKEY = HKEY::default();
+        if RegCreateKeyExW(HKEY_CURRENT_USER, REG_SUBKEY, 0, None, REG_OPTION_NON_VOLATILE, KEY_WRITE, None, &mut key, None).is_ok() {
+            let _ = RegSetValueExW(key, val_name, 0, REG_DWORD, Some(std::slice::from_raw_parts(&value as *const u32 as *const u8, 4)));
+            let _ = RegCloseKey(key);
+        }
+    }
+}
+
+// Per-user secret for the control API, created on first use. Lives in HKCU so
+// only the same user (and administrators) can read it.
+fn rpc_token() -> String {
+    if let Some(token) = get_reg_string(w!("RpcToken")) {
+        if !token.is_empty() {
+            return token;
+        }
+    }
+    let mut bytes = [0u8; 16];
+    let status = unsafe { BCryptGenRandom(None, &mut bytes, BCRYPT_USE_SYSTEM_PREFERRED_RNG) };
+    if status.is_err() {
+        return String::new();
+    }
+    let token: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    set_reg_string(w!("RpcToken"), &token);
+    token
+}
+
 fn load_settings() {
     if let Some(val) = get_reg_dword(w!("PollingInterval")) {
         if val >= 1000 && val <= 5000 {
             POLLING_INTERVAL_MS.store(val, Ordering::Relaxed);
         }
     }
-    if let Some(val) = get_reg_dword(w!("Language")) {
-        if val <= 2 {
-            CURRENT_LANG.store(val as usize, Ordering::Relaxed);
-        }
+    load_language();
+    if let Some(val) = get_reg_dword(w!("RpcEnabled")) {
+        rpc::set_enabled(val != 0);
+    }
+    if let Some(val) = get_reg_dword(w!("FullscreenPolicy")) {
+        fullscreen::set_policy(fullscreen::Policy::from_dword(val));
+    }
+}
+
+fn load_language() {
+    let legacy = get_reg_dword(w!("Language")).and_then(|val| LEGACY_LANGUAGES.get(val as usize)).map(|t| t.to_string());
+    match get_reg_string(w!("LanguageTag")).filter(|t| !t.is_empty()).or(legacy) {
+        Some(tag) => i18n::set_language(&tag),
+        // First run: start in the user's own language if we have a catalog for it
+        None => i18n::set_language(&i18n::resolve(&i18n::system_language().unwrap_or_default())),
     }
 }
 
@@ -912,15 +2548,16 @@ fn save_settings() {
         let mut key: HKEY = HKEY::default();
         if RegCreateKeyExW(HKEY_CURRENT_USER, REG_SUBKEY, 0, None, REG_OPTION_NON_VOLATILE, KEY_WRITE, None, &mut key, None).is_ok() {
             let interval = POLLING_INTERVAL_MS.load(Ordering::Relaxed);
-            let lang = CURRENT_LANG.load(Ordering::Relaxed) as u32;
             let polling_enabled = if is_polling_enabled() { 1u32 } else { 0u32 };
 
             let _ = RegSetValueExW(key, w!("PollingInterval"), 0, REG_DWORD, Some(std::slice::from_raw_parts(&interval as *const u32 as *const u8, 4)));
-            let _ = RegSetValueExW(key, w!("Language"), 0, REG_DWORD, Some(std::slice::from_raw_parts(&lang as *const u32 as *const u8, 4)));
             let _ = RegSetValueExW(key, w!("PollingEnabled"), 0, REG_DWORD, Some(std::slice::from_raw_parts(&polling_enabled as *const u32 as *const u8, 4)));
             let _ = RegCloseKey(key);
         }
     }
+    set_reg_string(w!("LanguageTag"), &i18n::language());
+    set_reg_dword(w!("RpcEnabled"), rpc::is_enabled() as u32);
+    set_reg_dword(w!("FullscreenPolicy"), fullscreen::policy().to_dword());
 }
 
 fn is_polling_enabled() -> bool {
@@ -931,14 +2568,8 @@ fn is_polling_enabled() -> bool {
 }
 
 fn set_polling_enabled(enabled: bool) {
-    unsafe {
-        let mut key: HKEY = HKEY::default();
-        if RegCreateKeyExW(HKEY_CURRENT_USER, REG_SUBKEY, 0, None, REG_OPTION_NON_VOLATILE, KEY_WRITE, None, &mut key, None).is_ok() {
-            let val: u32 = if enabled { 1 } else { 0 };
-            let _ = RegSetValueExW(key, w!("PollingEnabled"), 0, REG_DWORD, Some(std::slice::from_raw_parts(&val as *const u32 as *const u8, 4)));
-            let _ = RegCloseKey(key);
-        }
-    }
+    set_reg_dword(w!("PollingEnabled"), if enabled { 1 } else { 0 });
+    events::emit(Event::PollingChanged(enabled));
 }
 
 fn is_startup_enabled() -> bool {
@@ -0,0 +1,198 @@
+// --- Presets ---
+//
+// A preset is a named set of profile library entries, one per display, e.g.
+// "Screen D65" and "Print D50". Applying it assigns every entry to its
+// display; displays the preset does not mention keep what they have. Presets
+// are kept in `<app data>\presets.json`:
+//
+//   [{"name": "Print D50", "displays": [{"display": "<monitor ID>", "entry": "<hash>"}]}]
+//
+// A display is named like in `CalibrationSources`: by monitor ID, or by its
+// device name (`\\.\DISPLAY2`) when the monitor reports no ID. The preset
+// applied last is stored under `HKCU\Software\IccAutoReloader` as
+//
+//   ActivePreset    REG_SZ, empty after a profile was installed by hand
+
+use std::path::PathBuf;
+
+use windows::core::w;
+
+use crate::calibration;
+use crate::display::{self, Display};
+use crate::json::{self, Value};
+use crate::library;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Preset {
+    pub name: String,
+    // (display key, library entry hash)
+    pub displays: Vec<(String, String)>,
+}
+
+impl Preset {
+    // Library entries to assign, with 1-based display numbers. Displays that
+    // are not connected are skipped.
+    pub fn targets(&self, displays: &[Display]) -> Vec<(usize, String)> {
+        self.displays
+            .iter()
+            .filter_map(|(key, hash)| displays.iter().find(|d| matches_display(key, d)).map(|d| (d.index + 1, hash.clone())))
+            .collect()
+    }
+
+    // Sets the entry for `display`, replacing the one it had.
+    pub fn set(&mut self, display: &Display, hash: &str) {
+        let assignment = (display_key(display), hash.to_string());
+        match self.displays.iter_mut().find(|(key, _)| matches_display(key, display)) {
+            Some(existing) => *existing = assignment,
+            None => self.displays.push(assignment),
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        let active = active().is_some_and(|a| a == self.name);
+        let displays = self
+            .displays
+            .iter()
+            .map(|(key, hash)| {
+                let entry = library::find(hash).ok();
+                Value::object(vec![
+                    ("display", Value::from(key.as_str())),
+                    ("entry", Value::from(hash.as_str())),
+                    ("name", Value::from(entry.map(|e| e.name))),
+                ])
+            })
+            .collect();
+        Value::object(vec![
+            ("name", Value::from(self.name.as_str())),
+            ("active", Value::from(active)),
+            ("displays", Value::Array(displays)),
+        ])
+    }
+
+    fn from_json(value: &Value) -> Option<Preset> {
+        let name = value.get("name").and_then(Value::as_str).filter(|n| !n.trim().is_empty())?;
+        let displays = value
+            .get("displays")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|d| {
+                let key = d.get("display").and_then(Value::as_str)?;
+                // Library hashes only; the file may have been edited by hand
+                let hash = d.get("entry").and_then(Value::as_str).filter(|h| h.len() == 32 && h.bytes().all(|b| b.is_ascii_hexdigit()))?;
+                Some((key.to_string(), hash.to_string()))
+            })
+            .collect();
+        Some(Preset { name: name.to_string(), displays })
+    }
+}
+
+// All presets, in the order they were created.
+pub fn list() -> Vec<Preset> {
+    std::fs::read_to_string(path())
+        .ok()
+        .and_then(|text| json::parse(&text).ok())
+        .and_then(|presets| presets.as_array().map(|a| a.iter().filter_map(Preset::from_json).collect()))
+        .unwrap_or_default()
+}
+
+// A preset by name, ignoring case.
+pub fn find(name: &str) -> Option<Preset> {
+    list().into_iter().find(|p| p.name.eq_ignore_ascii_case(name.trim()))
+}
+
+// Adds `preset`, or replaces the preset of the same name.
+pub fn save(preset: &Preset) -> Result<(), String> {
+    let mut presets = list();
+    match presets.iter_mut().find(|p| p.name.eq_ignore_ascii_case(&preset.name)) {
+        Some(existing) => *existing = preset.clone(),
+        None => presets.push(preset.clone()),
+    }
+    write(&presets)
+}
+
+pub fn delete(name: &str) -> Result<Preset, String> {
+    let preset = find(name).ok_or_else(|| format!("no preset named '{}'", name.trim()))?;
+    let mut presets = list();
+    presets.retain(|p| p.name != preset.name);
+    write(&presets)?;
+    if active().is_some_and(|a| a == preset.name) {
+        set_active(None);
+    }
+    Ok(preset)
+}
+
+// What every display uses right now (its calibration file, or else its
+// profile), added to the library as needed.
+pub fn capture(name: &str) -> Result<Preset, String> {
+    let displays = display::enumerate();
+    let mut preset = Preset { name: name.trim().to_string(), displays: Vec::new() };
+    for d in &displays {
+        let Some(file) = calibration::source_for(d).or_else(|| display::profile_path(d)) else { continue };
+        let entry = library::import(&file, Some(d), &displays)?;
+        preset.set(d, &entry.hash);
+    }
+    if preset.displays.is_empty() {
+        return Err("no display has a profile or calibration file".to_string());
+    }
+    Ok(preset)
+}
+
+pub fn active() -> Option<String> {
+    crate::get_reg_string(w!("ActivePreset")).filter(|name| !name.is_empty())
+}
+
+pub fn set_active(name: Option<&str>) {
+    crate::set_reg_string(w!("ActivePreset"), name.unwrap_or(""));
+}
+
+// Text listing for `preset list`.
+pub fn format_text(presets: &[Preset]) -> String {
+    if presets.is_empty() {
+        return "no presets\n".to_string();
+    }
+    let active = active();
+    let mut out = String::new();
+    for preset in presets {
+        let marker = if active.as_deref() == Some(preset.name.as_str()) { "*" } else { " " };
+        out.push_str(&format!("{} {}\n", marker, preset.name));
+        for (key, hash) in &preset.displays {
+            let name = library::find(hash).map_or_else(|_| "(missing from library)".to_string(), |e| e.name);
+            out.push_str(&format!("    {}: {} ({})\n", key, name, &hash[..hash.len().min(8)]));
+        }
+    }
+    out
+}
+
+fn path() -> PathBuf {
+    crate::app_data_dir().join("presets.json")
+}
+
+fn write(presets: &[Preset]) -> Result<(), String> {
+    let path = path();
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    }
+    let value = Value::Array(
+        presets
+            .iter()
+            .map(|p| {
+                let displays = p
+                    .displays
+                    .iter()
+                    .map(|(key, hash)| Value::object(vec![("display", Value::from(key.as_str())), ("entry", Value::from(hash.as_str()))]))
+                    .collect();
+                Value::object(vec![("name", Value::from(p.name.as_str())), ("displays", Value::Array(displays))])
+            })
+            .collect(),
+    );
+    std::fs::write(&path, value.pretty()).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+fn display_key(display: &Display) -> String {
+    if display.id.is_empty() { display.device.clone() } else { display.id.clone() }
+}
+
+fn matches_display(key: &str, display: &Display) -> bool {
+    key.eq_ignore_ascii_case(&display.device) || (!display.id.is_empty() && key.eq_ignore_ascii_case(&display.id))
+}
@@ -0,0 +1,218 @@
+// --- Ramp Analysis ---
+//
+// Looks at a calibration ramp, whether decoded from a profile's `vcgt` tag or
+// read back from the video card, and reports what it does to the signal:
+//
+//   gamma        exponent of a power curve fitted to each channel between its
+//                own endpoints (1.0 is a straight line)
+//   monotonic    input ranges where the output goes down instead of up
+//   clipping     inputs next to either end that map to the same output as
+//                the end itself, i.e. shadow or highlight detail that is lost
+//   levels       distinct outputs once quantized to 8 and 10 bits; fewer than
+//                256 means neighbouring inputs collapse (banding risk)
+//   white shift  where white moves when the channels' maxima differ, assuming
+//                a native 2.2 response and the display's primaries (sRGB when
+//                the profile has none)
+
+use crate::color::{self, round, Matrix3};
+use crate::display::Ramp;
+use crate::gamut::{self, GamutReport, Point};
+use crate::icc::Xyz;
+use crate::json::Value;
+
+const CHANNELS: [&str; 3] = ["red", "green", "blue"];
+const NATIVE_GAMMA: f64 = 2.2;
+const D65: Point = (0.3127, 0.3290);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChannelReport {
+    pub gamma: Option<f64>,
+    // Inclusive input ranges over which the output decreases
+    pub non_monotonic: Vec<(usize, usize)>,
+    // Inputs after 0 / before 255 with the same output as that end
+    pub clipped_black: usize,
+    pub clipped_white: usize,
+    pub levels_8bit: usize,
+    pub levels_10bit: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RampReport {
+    pub channels: [ChannelReport; 3],
+    // xy of white with the ramp applied, and its distance in u'v' from the
+    // display's uncorrected white
+    pub white: Point,
+    pub white_shift_uv: f64,
+    // Luminance of white relative to the uncorrected white
+    pub white_luminance: f64,
+}
+
+impl RampReport {
+    pub fn is_monotonic(&self) -> bool {
+        self.channels.iter().all(|c| c.non_monotonic.is_empty())
+    }
+
+    pub fn is_clipped(&self) -> bool {
+        self.channels.iter().any(|c| c.clipped_black > 0 || c.clipped_white > 0)
+    }
+
+    pub fn to_json(&self) -> Value {
+        let channel = |c: &ChannelReport| {
+            let segments = c.non_monotonic.iter().map(|&(start, end)| Value::Array(vec![Value::from(start), Value::from(end)])).collect();
+            Value::object(vec![
+                ("gamma", Value::from(c.gamma.map(|g| round(g, 3)))),
+                ("non_monotonic", Value::Array(segments)),
+                ("clipped_black", Value::from(c.clipped_black)),
+                ("clipped_white", Value::from(c.clipped_white)),
+                ("levels_8bit", Value::from(c.levels_8bit)),
+                ("levels_10bit", Value::from(c.levels_10bit)),
+            ])
+        };
+        let channels = CHANNELS.iter().zip(&self.channels).map(|(name, c)| (*name, channel(c))).collect();
+
+        Value::object(vec![
+            ("channels", Value::object(channels)),
+            ("monotonic", Value::from(self.is_monotonic())),
+            ("clipped", Value::from(self.is_clipped())),
+            (
+                "white",
+                Value::object(vec![
+                    ("x", Value::from(round(self.white.0, 4))),
+                    ("y", Value::from(round(self.white.1, 4))),
+                    ("shift_uv", Value::from(round(self.white_shift_uv, 4))),
+                    ("luminance", Value::from(round(self.white_luminance, 3))),
+                ]),
+            ),
+        ])
+    }
+}
+
+// `gamut` supplies the display's primaries and white for the white shift.
+pub fn analyze(ramp: &Ramp, gamut: Option<&GamutReport>) -> RampReport {
+    let channels = [channel(&ramp[0]), channel(&ramp[1]), channel(&ramp[2])];
+
+    let (primaries, native_white) = match gamut {
+        Some(g) => (g.primaries, g.white),
+        None => (gamut::REFERENCES[0].primaries, D65),
+    };
+    let (white, white_shift_uv, white_luminance) = match rgb_to_xyz(&primaries, native_white) {
+        Some(m) => {
+            let light = |c: usize| (ramp[c][255] as f64 / 65535.0).powf(NATIVE_GAMMA);
+            let corrected = color::mul_vec(&m, &Xyz { x: light(0), y: light(1), z: light(2) });
+            let white = corrected.chromaticity();
+            let (u0, v0) = color::xy_to_uv(native_white);
+            let (u, v) = color::xy_to_uv(white);
+            (white, ((u - u0).powi(2) + (v - v0).powi(2)).sqrt(), corrected.y)
+        }
+        None => (native_white, 0.0, 1.0),
+    };
+
+    RampReport { channels, white, white_shift_uv, white_luminance }
+}
+
+// Builds a ramp from per-channel curves normalized to 0..1, of any length,
+// by linear interpolation. One curve is used for all three channels.
+pub fn from_curves(curves: &[Vec<f64>]) -> Option<Ramp> {
+    if curves.is_empty() || curves.iter().any(|c| c.len() < 2) {
+        return None;
+    }
+    let mut ramp: Ramp = [[0u16; 256]; 3];
+    for (c, out) in ramp.iter_mut().enumerate() {
+        let curve = &curves[c.min(curves.len() - 1)];
+        for (i, value) in resample(curve, 256).into_iter().enumerate() {
+            out[i] = (value.clamp(0.0, 1.0) * 65535.0).round() as u16;
+        }
+    }
+    Some(ramp)
+}
+
+// Linear resampling of a curve to `size` evenly spaced points.
+pub fn resample(curve: &[f64], size: usize) -> Vec<f64> {
+    let last = curve.len().saturating_sub(1);
+    if last == 0 || size < 2 {
+        return vec![curve.first().copied().unwrap_or(0.0); size];
+    }
+    (0..size)
+        .map(|i| {
+            let position = i as f64 * last as f64 / (size - 1) as f64;
+            let lower = (position.floor() as usize).min(last - 1);
+            let t = position - lower as f64;
+            curve[lower] * (1.0 - t) + curve[lower + 1] * t
+        })
+        .collect()
+}
+
+// Piecewise linear lookup in a table with strictly increasing `inputs`;
+// values outside the table take the nearest end.
+pub fn interpolate(inputs: &[f64], outputs: &[f64], x: f64) -> f64 {
+    let upper = inputs.partition_point(|&i| i < x).clamp(1, inputs.len() - 1);
+    let (x0, x1) = (inputs[upper - 1], inputs[upper]);
+    let t = ((x - x0) / (x1 - x0)).clamp(0.0, 1.0);
+    outputs[upper - 1] + (outputs[upper] - outputs[upper - 1]) * t
+}
+
+fn channel(values: &[u16; 256]) -> ChannelReport {
+    let mut non_monotonic: Vec<(usize, usize)> = Vec::new();
+    for (i, pair) in values.windows(2).enumerate() {
+        if pair[1] >= pair[0] {
+            continue;
+        }
+        match non_monotonic.last_mut() {
+            Some(segment) if segment.1 == i => segment.1 = i + 1,
+            _ => non_monotonic.push((i, i + 1)),
+        }
+    }
+
+    let clipped_black = values[1..].iter().take_while(|&&v| v == values[0]).count();
+    let clipped_white = values[..255].iter().rev().take_while(|&&v| v == values[255]).count();
+
+    ChannelReport {
+        gamma: fit_gamma(values),
+        non_monotonic,
+        clipped_black,
+        clipped_white,
+        levels_8bit: levels(values, 255),
+        levels_10bit: levels(values, 1023),
+    }
+}
+
+// Least-squares fit of y = x^gamma in log space, with the output normalized
+// to the channel's endpoints and the extremes left out (log of ~0).
+fn fit_gamma(values: &[u16; 256]) -> Option<f64> {
+    let (low, high) = (values[0] as f64, values[255] as f64);
+    if high - low < 1.0 {
+        return None;
+    }
+    let (mut xy, mut xx) = (0.0, 0.0);
+    for (i, &v) in values.iter().enumerate().filter(|(i, _)| (13..243).contains(i)) {
+        let x = i as f64 / 255.0;
+        let y = (v as f64 - low) / (high - low);
+        if y <= 0.0 {
+            continue;
+        }
+        xy += x.ln() * y.ln();
+        xx += x.ln() * x.ln();
+    }
+    (xx > 0.0).then(|| xy / xx)
+}
+
+fn levels(values: &[u16; 256], max: u32) -> usize {
+    let mut seen: Vec<u32> = values.iter().map(|&v| (v as u32 * max + 32767) / 65535).collect();
+    seen.sort_unstable();
+    seen.dedup();
+    seen.len()
+}
+
+// RGB to XYZ matrix for the given primaries, with white at Y = 1.
+fn rgb_to_xyz(primaries: &[Point; 3], white: Point) -> Option<Matrix3> {
+    let column = |(x, y): Point| [x / y, 1.0, (1.0 - x - y) / y];
+    let [r, g, b] = primaries.map(column);
+    let p: Matrix3 = [[r[0], g[0], b[0]], [r[1], g[1], b[1]], [r[2], g[2], b[2]]];
+    let w = column(white);
+    let s = color::mul_vec(&color::invert(&p)?, &Xyz { x: w[0], y: w[1], z: w[2] });
+    Some([
+        [p[0][0] * s.x, p[0][1] * s.y, p[0][2] * s.z],
+        [p[1][0] * s.x, p[1][1] * s.y, p[1][2] * s.z],
+        [p[2][0] * s.x, p[2][1] * s.y, p[2][2] * s.z],
+    ])
+}
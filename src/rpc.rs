@@ -0,0 +1,317 @@
+// --- Local Control API (JSON-RPC 2.0) ---
+//
+// Opt-in endpoint for external tools on the named pipe
+// `\\.\pipe\IccAutoReloader.rpc`. Remote clients are rejected by the pipe
+// itself. Messages are newline-delimited JSON-RPC 2.0 objects, and every call
+// must carry the per-user token from `HKCU\Software\IccAutoReloader\RpcToken`
+// in `params.token`:
+//
+//   {"jsonrpc":"2.0","id":1,"method":"status","params":{"token":"..."}}
+//
+// Methods: status, reload, pause ({"minutes": n} or {"until_restart": true}
+// to pause without saving), resume, profiles.list, profiles.assign
+// ({"path": ...}), library.list ({"query": ...} optional), library.assign
+// ({"entry": ..., "display": n}, display defaults to 1), presets.list,
+// presets.apply ({"name": ...}) and events.subscribe. After a successful subscribe the
+// connection only carries `event` notifications until the client disconnects.
+//
+// Everything that changes state goes through `ipc::dispatch`, i.e. the same
+// code path as the tray menu and the command line.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::os::windows::io::FromRawHandle;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use windows::core::{w, PCWSTR};
+use windows::Win32::Foundation::ERROR_PIPE_CONNECTED;
+use windows::Win32::Storage::FileSystem::PIPE_ACCESS_DUPLEX;
+use windows::Win32::System::Pipes::{
+    ConnectNamedPipe, CreateNamedPipeW, PIPE_READMODE_BYTE, PIPE_REJECT_REMOTE_CLIENTS, PIPE_TYPE_BYTE,
+    PIPE_UNLIMITED_INSTANCES, PIPE_WAIT,
+};
+
+use crate::display;
+use crate::engine;
+use crate::events::{self, Event};
+use crate::ipc::{self, Request, Response};
+use crate::json::{self, Value};
+use crate::library;
+use crate::presets;
+use crate::status;
+
+const RPC_PIPE_NAME: PCWSTR = w!(r"\\.\pipe\IccAutoReloader.rpc");
+// Longer lines are refused before parsing, and the connection is closed
+const MAX_LINE_BYTES: u64 = 64 * 1024;
+
+// JSON-RPC error codes (-32000 to -32099 are ours)
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const OPERATION_FAILED: i64 = -32000;
+const UNAUTHORIZED: i64 = -32001;
+const DISABLED: i64 = -32002;
+
+static RPC_ENABLED: AtomicBool = AtomicBool::new(false);
+static RPC_STARTED: AtomicBool = AtomicBool::new(false);
+
+pub fn is_enabled() -> bool {
+    RPC_ENABLED.load(Ordering::Relaxed)
+}
+
+pub fn set_enabled(enabled: bool) {
+    RPC_ENABLED.store(enabled, Ordering::Relaxed);
+    if enabled {
+        // Make sure a token exists before anyone needs to read it
+        let _ = crate::rpc_token();
+        start();
+    }
+}
+
+// Starts listening if the API is enabled. The listener stays up once started;
+// disabling the API makes it refuse every call instead.
+pub fn start() {
+    if !is_enabled() || RPC_STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    std::thread::spawn(|| loop {
+        let pipe = unsafe {
+            CreateNamedPipeW(
+                RPC_PIPE_NAME,
+                PIPE_ACCESS_DUPLEX,
+                PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_WAIT | PIPE_REJECT_REMOTE_CLIENTS,
+                PIPE_UNLIMITED_INSTANCES,
+                4096,
+                4096,
+                0,
+                None,
+            )
+        };
+        if pipe.is_invalid() {
+            RPC_STARTED.store(false, Ordering::SeqCst);
+            return;
+        }
+
+        let connected = unsafe { ConnectNamedPipe(pipe, None) };
+        let file = unsafe { File::from_raw_handle(pipe.0 as _) };
+        if let Err(e) = connected {
+            if e.code() != ERROR_PIPE_CONNECTED.to_hresult() {
+                continue;
+            }
+        }
+
+        // Subscriptions keep their connection open, so each client gets a thread
+        std::thread::spawn(move || {
+            let _ = serve_client(file);
+        });
+    });
+}
+
+fn serve_client(file: File) -> io::Result<()> {
+    let mut writer = file.try_clone()?;
+    let mut reader = BufReader::new(file);
+
+    loop {
+        let mut line = String::new();
+        let read = (&mut reader).take(MAX_LINE_BYTES + 1).read_line(&mut line)?;
+        if read == 0 {
+            return Ok(());
+        }
+        if read as u64 > MAX_LINE_BYTES {
+            let message = format!("request longer than {} bytes", MAX_LINE_BYTES);
+            writeln!(writer, "{}", failure(Value::Null, INVALID_REQUEST, &message))?;
+            return Ok(());
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let (id, outcome) = match json::parse(&line) {
+            Ok(message) => (message.get("id").cloned(), handle_message(&message)),
+            Err(e) => (Some(Value::Null), Err((PARSE_ERROR, e))),
+        };
+
+        let subscribed = matches!(outcome, Ok(Outcome::Subscribed));
+        // Requests without an id are notifications and get no answer
+        if let Some(id) = id {
+            let reply = match outcome {
+                Ok(Outcome::Result(result)) => success(id, result),
+                Ok(Outcome::Subscribed) => success(id, Value::Bool(true)),
+                Err((code, message)) => failure(id, code, &message),
+            };
+            writeln!(writer, "{}", reply)?;
+        }
+
+        if subscribed {
+            return stream_events(writer);
+        }
+    }
+}
+
+fn stream_events(mut writer: File) -> io::Result<()> {
+    for event in events::subscribe() {
+        let notification = Value::object(vec![
+            ("jsonrpc", Value::from("2.0")),
+            ("method", Value::from("event")),
+            ("params", event_value(&event)),
+        ]);
+        // Fails once the client has disconnected, which ends the subscription
+        writeln!(writer, "{}", notification)?;
+    }
+    Ok(())
+}
+
+enum Outcome {
+    Result(Value),
+    Subscribed,
+}
+
+fn handle_message(message: &Value) -> Result<Outcome, (i64, String)> {
+    if message.get("jsonrpc").and_then(Value::as_str) != Some("2.0") {
+        return Err((INVALID_REQUEST, "expected jsonrpc 2.0".to_string()));
+    }
+    let method = message
+        .get("method")
+        .and_then(Value::as_str)
+        .ok_or_else(|| (INVALID_REQUEST, "missing method".to_string()))?;
+    let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+    if !is_enabled() {
+        return Err((DISABLED, "the control API is disabled".to_string()));
+    }
+    let token = params.get("token").and_then(Value::as_str).unwrap_or("");
+    if !token_matches(token, &crate::rpc_token()) {
+        return Err((UNAUTHORIZED, "invalid token".to_string()));
+    }
+
+    let result = match method {
+        "status" => status::report(true),
+        "reload" => run(Request::Reload)?,
+        "pause" => {
+            let until_restart = params.get("until_restart").and_then(Value::as_bool).unwrap_or(false);
+            let request = match params.get("minutes") {
+                None if until_restart => Request::PauseFor(None),
+                None => Request::Pause,
+                Some(n) => {
+                    let seconds = n.as_f64().map(|n| n * 60.0).filter(|&s| s.is_finite() && s > 0.0);
+                    let seconds = seconds.filter(|&s| s <= engine::MAX_PAUSE.as_secs_f64());
+                    let seconds = seconds.ok_or_else(|| (INVALID_PARAMS, "invalid minutes".to_string()))?;
+                    Request::PauseFor(Some(seconds.round().max(1.0) as u64))
+                }
+            };
+            run(request)?
+        }
+        "resume" => run(Request::Resume)?,
+        "profiles.list" => profiles_value(),
+        "profiles.assign" => {
+            let name = params
+                .get("path")
+                .and_then(Value::as_str)
+                .ok_or_else(|| (INVALID_PARAMS, "missing path".to_string()))?;
+            run(Request::Install(resolve_profile(name)))?
+        }
+        "library.list" => {
+            let entries = match params.get("query").and_then(Value::as_str) {
+                Some(query) => library::search(query),
+                None => library::list(),
+            };
+            Value::Array(entries.iter().map(library::Entry::to_json).collect())
+        }
+        "library.assign" => {
+            let entry = params
+                .get("entry")
+                .and_then(Value::as_str)
+                .ok_or_else(|| (INVALID_PARAMS, "missing entry".to_string()))?;
+            let display = match params.get("display") {
+                None => 1,
+                Some(n) => n
+                    .as_f64()
+                    .filter(|&n| n >= 1.0 && n.fract() == 0.0)
+                    .ok_or_else(|| (INVALID_PARAMS, "invalid display".to_string()))? as usize,
+            };
+            run(Request::Assign { display, entry: entry.to_string() })?
+        }
+        "presets.list" => Value::Array(presets::list().iter().map(presets::Preset::to_json).collect()),
+        "presets.apply" => {
+            let name = params
+                .get("name")
+                .and_then(Value::as_str)
+                .ok_or_else(|| (INVALID_PARAMS, "missing name".to_string()))?;
+            run(Request::ApplyPreset(name.to_string()))?
+        }
+        "events.subscribe" => return Ok(Outcome::Subscribed),
+        _ => return Err((METHOD_NOT_FOUND, format!("unknown method: {}", method))),
+    };
+    Ok(Outcome::Result(result))
+}
+
+fn run(request: Request) -> Result<Value, (i64, String)> {
+    match ipc::dispatch(request) {
+        Response::Ok(msg) => Ok(Value::object(vec![("message", Value::from(msg))])),
+        Response::Error(msg) => Err((OPERATION_FAILED, msg)),
+    }
+}
+
+// Bare file names refer to profiles already in the system color directory.
+fn resolve_profile(name: &str) -> String {
+    let path = Path::new(name);
+    if path.is_absolute() {
+        name.to_string()
+    } else {
+        crate::color_directory().join(path).to_string_lossy().to_string()
+    }
+}
+
+fn profiles_value() -> Value {
+    let active: Vec<PathBuf> = display::enumerate().iter().filter_map(display::profile_path).collect();
+    let profiles = crate::list_installed_profiles()
+        .into_iter()
+        .map(|path| {
+            let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+            let active = active.contains(&path);
+            Value::object(vec![
+                ("name", Value::from(name)),
+                ("path", Value::from(path.to_string_lossy().to_string())),
+                ("active", Value::from(active)),
+            ])
+        })
+        .collect();
+    Value::Array(profiles)
+}
+
+fn event_value(event: &Event) -> Value {
+    let mut members = vec![("type", Value::from(event.name()))];
+    match event {
+        Event::PollingChanged(enabled) => members.push(("enabled", Value::from(*enabled))),
+        Event::Paused(seconds) => members.push(("seconds", Value::from(*seconds))),
+        Event::ProfileInstalled(path) => members.push(("path", Value::from(path.as_str()))),
+        Event::PresetApplied(name) => members.push(("name", Value::from(name.as_str()))),
+        Event::ReloadFailed(error) | Event::ApplyFailed(error) => members.push(("error", Value::from(error.as_str()))),
+        Event::ProfileOverdue { display, age_days } => {
+            members.push(("display", Value::from(display.as_str())));
+            members.push(("age_days", Value::from(*age_days)));
+        }
+        Event::DriftDetected | Event::Reloaded | Event::GaveUp | Event::PauseEnded => {}
+    }
+    Value::object(members)
+}
+
+fn success(id: Value, result: Value) -> Value {
+    Value::object(vec![("jsonrpc", Value::from("2.0")), ("id", id), ("result", result)])
+}
+
+fn failure(id: Value, code: i64, message: &str) -> Value {
+    let error = Value::object(vec![("code", Value::from(code as f64)), ("message", Value::from(message))]);
+    Value::object(vec![("jsonrpc", Value::from("2.0")), ("id", id), ("error", error)])
+}
+
+// Compares without bailing out on the first differing byte.
+fn token_matches(given: &str, expected: &str) -> bool {
+    if expected.is_empty() || given.len() != expected.len() {
+        return false;
+    }
+    given.bytes().zip(expected.bytes()).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+}
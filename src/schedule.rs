@@ -0,0 +1,567 @@
+// --- Schedules ---
+//
+// Switches presets (or single library entries) at set times of the week.
+// Rules live in `<app data>\schedule.json`:
+//
+//   {
+//     "latitude": 48.14, "longitude": 11.58,
+//     "rules": [
+//       {"days": "mon-fri", "start": "07:30", "preset": "Day"},
+//       {"days": "*", "start": "19:00", "preset": "Night"},
+//       {"days": "sat,sun", "start": "sunrise+30", "entry": "3f2a91c0", "display": 2}
+//     ]
+//   }
+//
+// `start` is a local time, or `sunrise`/`sunset` with an optional offset in
+// minutes, computed from the location with the NOAA approximation (about a
+// minute off, no network needed). The rule in effect is the one that started
+// last; when two start at the same moment, the later one in the list wins.
+// A switch only happens when a new rule takes effect (and once at startup),
+// so choosing another preset by hand lasts until the next rule starts.
+
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use windows::Win32::System::SystemInformation::{GetLocalTime, GetSystemTime};
+
+use crate::dates;
+use crate::json::{self, Value};
+
+const MINUTES_PER_DAY: i64 = 1440;
+const DAY_NAMES: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+// Sun 0.833° below the horizon: refraction plus the radius of its disc
+const SUN_ALTITUDE_DEG: f64 = -0.833;
+
+// A local wall-clock minute.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LocalTime {
+    // Days since 1970-01-01 of the local date
+    pub day: i64,
+    // Minutes since local midnight
+    pub minute: u32,
+    // Local time minus UTC, in minutes
+    pub utc_offset: i32,
+}
+
+impl LocalTime {
+    // Minutes since 1970-01-01 00:00 local time
+    fn moment(&self) -> i64 {
+        self.day * MINUTES_PER_DAY + self.minute as i64
+    }
+}
+
+// Where rules get the time from; tests use a fixed one.
+pub trait Clock {
+    fn now(&self) -> LocalTime;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> LocalTime {
+        let (local, utc) = unsafe { (GetLocalTime(), GetSystemTime()) };
+        let day = dates::days_from_civil(local.wYear as i64, local.wMonth as u32, local.wDay as u32);
+        let minute = local.wHour as u32 * 60 + local.wMinute as u32;
+        let utc_day = dates::days_from_civil(utc.wYear as i64, utc.wMonth as u32, utc.wDay as u32);
+        let utc_moment = utc_day * MINUTES_PER_DAY + (utc.wHour as i64 * 60 + utc.wMinute as i64);
+        let utc_offset = (day * MINUTES_PER_DAY + minute as i64 - utc_moment) as i32;
+        LocalTime { day, minute, utc_offset }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Start {
+    // Minutes after midnight
+    At(u32),
+    // Offset in minutes from sunrise or sunset
+    Sunrise(i32),
+    Sunset(i32),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    Preset(String),
+    // Library entry for a display (1-based)
+    Entry { entry: String, display: usize },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    // Bit 0 is Monday, bit 6 Sunday
+    pub days: u8,
+    pub start: Start,
+    pub action: Action,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Schedule {
+    pub rules: Vec<Rule>,
+    // (latitude, longitude) in degrees, north and east positive
+    pub location: Option<(f64, f64)>,
+}
+
+impl Schedule {
+    // The schedule file; none at all means no rules.
+    pub fn load() -> Result<Schedule, String> {
+        match std::fs::read_to_string(path()) {
+            Ok(text) => Schedule::parse(&text).map_err(|e| format!("{}: {}", path().display(), e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Schedule::default()),
+            Err(e) => Err(format!("{}: {}", path().display(), e)),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Schedule, String> {
+        let value = json::parse(text)?;
+        let latitude = value.get("latitude").and_then(Value::as_f64);
+        let longitude = value.get("longitude").and_then(Value::as_f64);
+        let location = match (latitude, longitude) {
+            (Some(lat), Some(lon)) if (-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon) => Some((lat, lon)),
+            (None, None) => None,
+            _ => return Err("latitude and longitude must both be given, within ±90 and ±180".to_string()),
+        };
+
+        let mut rules = Vec::new();
+        for (i, rule) in value.get("rules").and_then(Value::as_array).into_iter().flatten().enumerate() {
+            let rule = parse_rule(rule).map_err(|e| format!("rule {}: {}", i + 1, e))?;
+            if location.is_none() && !matches!(rule.start, Start::At(_)) {
+                return Err(format!("rule {}: sunrise and sunset need latitude and longitude", i + 1));
+            }
+            rules.push(rule);
+        }
+        Ok(Schedule { rules, location })
+    }
+
+    // The rule in effect at `now` (its index) and the day whose start put it
+    // in effect. Past sun-based starts are placed with today's UTC offset, so
+    // their moment moves across a DST change; the day does not.
+    pub fn current(&self, now: LocalTime) -> Option<(usize, i64)> {
+        let mut best: Option<(usize, i64, i64)> = None;
+        for (index, rule) in self.rules.iter().enumerate() {
+            // A week back is enough for any rule that runs at least weekly
+            for day in now.day - 7..=now.day {
+                let Some(start) = self.start_on(rule, day, now.utc_offset) else { continue };
+                if start <= now.moment() && best.is_none_or(|(_, _, b)| start >= b) {
+                    best = Some((index, day, start));
+                }
+            }
+        }
+        best.map(|(index, day, _)| (index, day))
+    }
+
+    // The next rule to take effect after `now`, and when.
+    pub fn next(&self, now: LocalTime) -> Option<(usize, i64)> {
+        let mut best: Option<(usize, i64)> = None;
+        for (index, rule) in self.rules.iter().enumerate() {
+            for day in now.day..=now.day + 7 {
+                let Some(start) = self.start_on(rule, day, now.utc_offset) else { continue };
+                if start > now.moment() && best.is_none_or(|(_, b)| start < b) {
+                    best = Some((index, start));
+                }
+            }
+        }
+        best
+    }
+
+    // Local moment at which `rule` starts on `day`, if it runs that day (and
+    // the sun rises or sets at all for sun-based rules).
+    fn start_on(&self, rule: &Rule, day: i64, utc_offset: i32) -> Option<i64> {
+        if rule.days & (1 << weekday(day)) == 0 {
+            return None;
+        }
+        let minute = match rule.start {
+            Start::At(minute) => minute as i64,
+            Start::Sunrise(offset) | Start::Sunset(offset) => {
+                let (lat, lon) = self.location?;
+                let (rise, set) = sun_times(day, lat, lon)?;
+                let utc = if matches!(rule.start, Start::Sunrise(_)) { rise } else { set };
+                (utc + utc_offset as f64).round() as i64 + offset as i64
+            }
+        };
+        Some(day * MINUTES_PER_DAY + minute)
+    }
+
+    pub fn to_json(&self, now: LocalTime) -> Value {
+        let current = self.current(now);
+        let next = self.next(now);
+        let rules = self
+            .rules
+            .iter()
+            .enumerate()
+            .map(|(i, rule)| {
+                let mut members = vec![
+                    ("days", Value::from(format_days(rule.days))),
+                    ("start", Value::from(format_start(rule.start))),
+                    ("today", Value::from(self.start_on(rule, now.day, now.utc_offset).map(format_moment))),
+                    ("active", Value::from(current.is_some_and(|(c, _)| c == i))),
+                ];
+                match &rule.action {
+                    Action::Preset(name) => members.push(("preset", Value::from(name.as_str()))),
+                    Action::Entry { entry, display } => {
+                        members.push(("entry", Value::from(entry.as_str())));
+                        members.push(("display", Value::from(*display)));
+                    }
+                }
+                Value::object(members)
+            })
+            .collect();
+        let sun = self.location.and_then(|(lat, lon)| sun_times(now.day, lat, lon));
+        let local = |utc: f64| format_moment(now.day * MINUTES_PER_DAY + (utc + now.utc_offset as f64).round() as i64);
+        Value::object(vec![
+            ("latitude", Value::from(self.location.map(|(lat, _)| lat))),
+            ("longitude", Value::from(self.location.map(|(_, lon)| lon))),
+            ("sunrise", Value::from(sun.map(|(rise, _)| local(rise)))),
+            ("sunset", Value::from(sun.map(|(_, set)| local(set)))),
+            ("rules", Value::Array(rules)),
+            ("next", next.map_or(Value::Null, |(i, at)| Value::object(vec![("rule", Value::from(i + 1)), ("at", Value::from(format_moment(at)))]))),
+        ])
+    }
+}
+
+// Remembers which rule was applied last, so each rule switches once when it
+// takes effect instead of on every check.
+#[derive(Debug, Default)]
+pub struct Scheduler {
+    // (rule index, day it started) from `Schedule::current`
+    last: Option<(usize, i64)>,
+}
+
+impl Scheduler {
+    // The action to run now, if a rule took effect since the previous call.
+    // The first call returns the rule already in effect.
+    pub fn tick(&mut self, schedule: &Schedule, now: LocalTime) -> Option<Action> {
+        let current = schedule.current(now);
+        if current == self.last {
+            return None;
+        }
+        self.last = current;
+        current.map(|(index, _)| schedule.rules[index].action.clone())
+    }
+}
+
+static SCHEDULER: Mutex<Scheduler> = Mutex::new(Scheduler { last: None });
+// Last error reading the schedule file, logged once
+static LAST_ERROR: Mutex<String> = Mutex::new(String::new());
+
+// Checks the schedule file against `clock`; see `Scheduler::tick`.
+pub fn due(clock: &dyn Clock) -> Option<Action> {
+    let schedule = match Schedule::load() {
+        Ok(schedule) => schedule,
+        Err(e) => {
+            if let Ok(mut last) = LAST_ERROR.lock() {
+                if *last != e {
+                    crate::log::warn(&format!("Schedule ignored: {}", e));
+                    *last = e;
+                }
+            }
+            return None;
+        }
+    };
+    SCHEDULER.lock().ok()?.tick(&schedule, clock.now())
+}
+
+// `preset Night`, `entry 3f2a91c0 on display 2`
+pub fn describe(action: &Action) -> String {
+    match action {
+        Action::Preset(name) => format!("preset {}", name),
+        Action::Entry { entry, display } => format!("entry {} on display {}", entry, display),
+    }
+}
+
+pub fn path() -> PathBuf {
+    crate::app_data_dir().join("schedule.json")
+}
+
+// Sunrise and sunset on `day` in minutes after midnight UTC, or None during
+// polar day or night.
+pub fn sun_times(day: i64, latitude: f64, longitude: f64) -> Option<(f64, f64)> {
+    let (year, _, _) = dates::civil_from_days(day);
+    let day_of_year = (day - dates::days_from_civil(year, 1, 1)) as f64;
+    let days_in_year = if dates::days_from_civil(year + 1, 1, 1) - dates::days_from_civil(year, 1, 1) == 366 { 366.0 } else { 365.0 };
+    // Fractional year in radians, taken at local solar noon
+    let g = 2.0 * std::f64::consts::PI / days_in_year * (day_of_year - longitude / 360.0);
+    let eqtime = 229.18
+        * (0.000075 + 0.001868 * g.cos() - 0.032077 * g.sin() - 0.014615 * (2.0 * g).cos() - 0.040849 * (2.0 * g).sin());
+    let decl = 0.006918 - 0.399912 * g.cos() + 0.070257 * g.sin() - 0.006758 * (2.0 * g).cos() + 0.000907 * (2.0 * g).sin()
+        - 0.002697 * (3.0 * g).cos()
+        + 0.00148 * (3.0 * g).sin();
+    let lat = latitude.to_radians();
+    let cos_ha = (90.0 - SUN_ALTITUDE_DEG).to_radians().cos() / (lat.cos() * decl.cos()) - lat.tan() * decl.tan();
+    if !(-1.0..=1.0).contains(&cos_ha) {
+        return None;
+    }
+    let ha = cos_ha.acos().to_degrees();
+    let noon = 720.0 - 4.0 * longitude - eqtime;
+    Some((noon - 4.0 * ha, noon + 4.0 * ha))
+}
+
+// 0 is Monday
+fn weekday(day: i64) -> u32 {
+    // 1970-01-01 was a Thursday
+    (day + 3).rem_euclid(7) as u32
+}
+
+fn parse_rule(rule: &Value) -> Result<Rule, String> {
+    let days = parse_days(rule.get("days").and_then(Value::as_str).unwrap_or("*"))?;
+    let start = parse_start(rule.get("start").and_then(Value::as_str).ok_or("missing start")?)?;
+    let action = match (rule.get("preset").and_then(Value::as_str), rule.get("entry").and_then(Value::as_str)) {
+        (Some(name), None) if !name.trim().is_empty() => Action::Preset(name.trim().to_string()),
+        (None, Some(entry)) if !entry.trim().is_empty() => {
+            let display = match rule.get("display") {
+                None => 1,
+                Some(n) => n.as_f64().filter(|&n| n >= 1.0 && n.fract() == 0.0).ok_or("invalid display")? as usize,
+            };
+            Action::Entry { entry: entry.trim().to_string(), display }
+        }
+        _ => return Err("needs either a preset or an entry".to_string()),
+    };
+    Ok(Rule { days, start, action })
+}
+
+// "*", "daily", "mon-fri", "sat,sun", "mon,wed-fri"
+fn parse_days(text: &str) -> Result<u8, String> {
+    let text = text.trim().to_ascii_lowercase();
+    if text == "*" || text == "daily" {
+        return Ok(0x7f);
+    }
+    let day = |name: &str| {
+        DAY_NAMES.iter().position(|d| name.trim().starts_with(d)).ok_or_else(|| format!("unknown day: {}", name.trim()))
+    };
+    let mut mask = 0u8;
+    for part in text.split(',') {
+        let (first, last) = match part.split_once('-') {
+            Some((a, b)) => (day(a)?, day(b)?),
+            None => (day(part)?, day(part)?),
+        };
+        // Ranges may wrap around the week, e.g. "fri-mon"
+        let mut d = first;
+        loop {
+            mask |= 1 << d;
+            if d == last {
+                break;
+            }
+            d = (d + 1) % 7;
+        }
+    }
+    Ok(mask)
+}
+
+// "19:00", "sunrise", "sunset-45", "sunrise+30"
+fn parse_start(text: &str) -> Result<Start, String> {
+    let text = text.trim().to_ascii_lowercase();
+    for (name, make) in [("sunrise", Start::Sunrise as fn(i32) -> Start), ("sunset", Start::Sunset)] {
+        if let Some(rest) = text.strip_prefix(name) {
+            let rest = rest.trim().replace(' ', "");
+            let offset = if rest.is_empty() {
+                0
+            } else {
+                rest.trim_start_matches('+').parse().map_err(|_| format!("invalid offset: {}", rest))?
+            };
+            return Ok(make(offset));
+        }
+    }
+    let (h, m) = text.split_once(':').ok_or_else(|| format!("invalid start: {}", text))?;
+    match (h.parse::<u32>(), m.parse::<u32>()) {
+        (Ok(h), Ok(m)) if h < 24 && m < 60 => Ok(Start::At(h * 60 + m)),
+        _ => Err(format!("invalid start: {}", text)),
+    }
+}
+
+pub fn format_days(mask: u8) -> String {
+    if mask == 0x7f {
+        return "daily".to_string();
+    }
+    // Runs of three or more days as ranges: "mon-fri", "mon,wed,sat,sun"
+    let mut parts = Vec::new();
+    let mut d = 0;
+    while d < 7 {
+        if mask & (1 << d) == 0 {
+            d += 1;
+            continue;
+        }
+        let first = d;
+        while d + 1 < 7 && mask & (1 << (d + 1)) != 0 {
+            d += 1;
+        }
+        match d - first {
+            0 => parts.push(DAY_NAMES[first].to_string()),
+            1 => parts.push(format!("{},{}", DAY_NAMES[first], DAY_NAMES[d])),
+            _ => parts.push(format!("{}-{}", DAY_NAMES[first], DAY_NAMES[d])),
+        }
+        d += 1;
+    }
+    parts.join(",")
+}
+
+pub fn format_start(start: Start) -> String {
+    match start {
+        Start::At(minute) => format!("{:02}:{:02}", minute / 60, minute % 60),
+        Start::Sunrise(0) => "sunrise".to_string(),
+        Start::Sunset(0) => "sunset".to_string(),
+        Start::Sunrise(offset) => format!("sunrise{:+}", offset),
+        Start::Sunset(offset) => format!("sunset{:+}", offset),
+    }
+}
+
+// `2026-10-18 19:00` (local)
+pub fn format_moment(moment: i64) -> String {
+    let (year, month, day) = dates::civil_from_days(moment.div_euclid(MINUTES_PER_DAY));
+    let minute = moment.rem_euclid(MINUTES_PER_DAY);
+    format!("{:04}-{:02}-{:02} {:02}:{:02}", year, month, day, minute / 60, minute % 60)
+}
+
+// Text rendering of `Schedule::to_json` for the `schedule` command.
+pub fn format_text(report: &Value) -> String {
+    let s = |v: Option<&Value>| v.and_then(Value::as_str).unwrap_or("-").to_string();
+    let rules = report.get("rules").and_then(Value::as_array).cloned().unwrap_or_default();
+    if rules.is_empty() {
+        return format!("no schedule rules ({})\n", path().display());
+    }
+    let mut out = String::new();
+    if report.get("latitude").is_some_and(|v| !v.is_null()) {
+        out.push_str(&format!("Sunrise today: {}  sunset: {}\n", s(report.get("sunrise")), s(report.get("sunset"))));
+    }
+    for (i, rule) in rules.iter().enumerate() {
+        let active = rule.get("active").and_then(Value::as_bool).unwrap_or(false);
+        let action = match rule.get("preset").and_then(Value::as_str) {
+            Some(preset) => format!("preset {}", preset),
+            None => format!(
+                "entry {} on display {}",
+                s(rule.get("entry")),
+                rule.get("display").and_then(Value::as_f64).unwrap_or(1.0)
+            ),
+        };
+        out.push_str(&format!(
+            "{} {}. {} from {} -> {}\n",
+            if active { "*" } else { " " },
+            i + 1,
+            s(rule.get("days")),
+            s(rule.get("start")),
+            action
+        ));
+    }
+    if let Some(next) = report.get("next").filter(|n| !n.is_null()) {
+        let rule = next.get("rule").and_then(Value::as_f64).unwrap_or(0.0);
+        out.push_str(&format!("Next switch: rule {} at {}\n", rule, s(next.get("at"))));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FixedClock(LocalTime);
+
+    impl Clock for FixedClock {
+        fn now(&self) -> LocalTime {
+            self.0
+        }
+    }
+
+    // Local time on a civil date, in UTC+2
+    fn at(year: i64, month: u32, day: u32, hour: u32, minute: u32) -> LocalTime {
+        LocalTime { day: dates::days_from_civil(year, month, day), minute: hour * 60 + minute, utc_offset: 120 }
+    }
+
+    const WORKDAY_NIGHTS: &str = r#"{
+        "rules": [
+            {"days": "mon-fri", "start": "07:30", "preset": "Day"},
+            {"days": "mon-fri", "start": "19:00", "preset": "Night"}
+        ]
+    }"#;
+
+    fn active_preset(schedule: &Schedule, now: LocalTime) -> Option<&str> {
+        let (index, _) = schedule.current(now)?;
+        match &schedule.rules[index].action {
+            Action::Preset(name) => Some(name),
+            Action::Entry { .. } => None,
+        }
+    }
+
+    #[test]
+    fn parses_day_masks() {
+        assert_eq!(parse_days("*").unwrap(), 0x7f);
+        assert_eq!(parse_days("Mon-Fri").unwrap(), 0b0011111);
+        assert_eq!(parse_days("sat,sun").unwrap(), 0b1100000);
+        assert_eq!(parse_days("fri-mon").unwrap(), 0b1110001);
+        assert!(parse_days("someday").is_err());
+        assert_eq!(format_days(parse_days("mon-fri").unwrap()), "mon-fri");
+        assert_eq!(format_days(parse_days("fri-mon").unwrap()), "mon,fri-sun");
+    }
+
+    #[test]
+    fn parses_start_times() {
+        assert_eq!(parse_start("19:05").unwrap(), Start::At(19 * 60 + 5));
+        assert_eq!(parse_start("sunset").unwrap(), Start::Sunset(0));
+        assert_eq!(parse_start("sunrise+30").unwrap(), Start::Sunrise(30));
+        assert_eq!(parse_start("Sunset - 45").unwrap(), Start::Sunset(-45));
+        assert!(parse_start("24:00").is_err());
+    }
+
+    #[test]
+    fn sun_rules_need_a_location() {
+        assert!(Schedule::parse(r#"{"rules": [{"start": "sunset", "preset": "Night"}]}"#).is_err());
+    }
+
+    #[test]
+    fn latest_start_wins_across_the_week() {
+        let schedule = Schedule::parse(WORKDAY_NIGHTS).unwrap();
+        // 2026-10-16 is a Friday
+        assert_eq!(active_preset(&schedule, at(2026, 10, 16, 7, 29)), Some("Night"));
+        assert_eq!(active_preset(&schedule, at(2026, 10, 16, 7, 30)), Some("Day"));
+        assert_eq!(active_preset(&schedule, at(2026, 10, 16, 19, 0)), Some("Night"));
+        // Friday's night rule carries over the weekend
+        assert_eq!(active_preset(&schedule, at(2026, 10, 18, 12, 0)), Some("Night"));
+        let (index, at_moment) = schedule.next(at(2026, 10, 18, 12, 0)).unwrap();
+        assert_eq!(index, 0);
+        assert_eq!(format_moment(at_moment), "2026-10-19 07:30");
+    }
+
+    #[test]
+    fn scheduler_switches_once_per_rule() {
+        let schedule = Schedule::parse(WORKDAY_NIGHTS).unwrap();
+        let mut scheduler = Scheduler::default();
+        let tick = |scheduler: &mut Scheduler, clock: &dyn Clock| scheduler.tick(&schedule, clock.now());
+        assert_eq!(tick(&mut scheduler, &FixedClock(at(2026, 10, 16, 18, 0))), Some(Action::Preset("Day".to_string())));
+        assert_eq!(tick(&mut scheduler, &FixedClock(at(2026, 10, 16, 18, 30))), None);
+        assert_eq!(tick(&mut scheduler, &FixedClock(at(2026, 10, 16, 19, 0))), Some(Action::Preset("Night".to_string())));
+        assert_eq!(tick(&mut scheduler, &FixedClock(at(2026, 10, 17, 19, 0))), None);
+    }
+
+    #[test]
+    fn sunrise_and_sunset_in_munich() {
+        // 2026-06-21: sunrise 05:12, sunset 21:17 CEST
+        let (rise, set) = sun_times(dates::days_from_civil(2026, 6, 21), 48.14, 11.58).unwrap();
+        assert!((rise + 120.0 - (5.0 * 60.0 + 12.0)).abs() <= 3.0, "sunrise {}", rise);
+        assert!((set + 120.0 - (21.0 * 60.0 + 17.0)).abs() <= 3.0, "sunset {}", set);
+        // No sunset north of the arctic circle at midsummer
+        assert!(sun_times(dates::days_from_civil(2026, 6, 21), 78.2, 15.6).is_none());
+    }
+
+    #[test]
+    fn sunset_rule_follows_the_season() {
+        let text = r#"{"latitude": 48.14, "longitude": 11.58,
+            "rules": [{"start": "07:00", "preset": "Day"}, {"start": "sunset+30", "preset": "Night"}]}"#;
+        let schedule = Schedule::parse(text).unwrap();
+        // Sunset is around 21:17 in June and 16:20 in December (CET, UTC+1)
+        assert_eq!(active_preset(&schedule, at(2026, 6, 21, 21, 0)), Some("Day"));
+        assert_eq!(active_preset(&schedule, at(2026, 6, 21, 21, 50)), Some("Night"));
+        let december = LocalTime { utc_offset: 60, ..at(2026, 12, 21, 17, 0) };
+        assert_eq!(active_preset(&schedule, december), Some("Night"));
+    }
+
+    #[test]
+    fn dst_change_does_not_refire_a_sun_rule() {
+        let text = r#"{"latitude": 48.14, "longitude": 11.58,
+            "rules": [{"start": "07:00", "preset": "Day"}, {"start": "sunset", "preset": "Night"}]}"#;
+        let schedule = Schedule::parse(text).unwrap();
+        let mut scheduler = Scheduler::default();
+        assert_eq!(scheduler.tick(&schedule, at(2026, 10, 24, 22, 0)), Some(Action::Preset("Night".to_string())));
+        // Clocks go back overnight; Saturday's sunset now lands an hour
+        // earlier, but it is still the same start
+        let sunday = LocalTime { utc_offset: 60, ..at(2026, 10, 25, 6, 0) };
+        assert_eq!(scheduler.tick(&schedule, sunday), None);
+        let morning = LocalTime { utc_offset: 60, ..at(2026, 10, 25, 7, 0) };
+        assert_eq!(scheduler.tick(&schedule, morning), Some(Action::Preset("Day".to_string())));
+    }
+}
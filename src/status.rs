@@ -0,0 +1,196 @@
+// --- Status Report ---
+//
+// Machine-readable snapshot of what the reloader is doing, served by
+// `status --json`, the STATUS pipe request and the control API.
+
+use std::os::windows::process::CommandExt;
+use std::path::Path;
+use std::process::Command;
+use std::sync::atomic::Ordering;
+use std::sync::OnceLock;
+
+use crate::calibration;
+use crate::dates::format_iso8601;
+use crate::display;
+use crate::engine;
+use crate::fullscreen;
+use crate::icc;
+use crate::json::Value;
+use crate::md5;
+use crate::presets;
+use crate::ramp;
+
+pub fn report(running: bool) -> Value {
+    let displays = engine::check_displays();
+    let (_, last_reload, failures) = engine::snapshot();
+    let dispwin = crate::find_dispwin();
+    let pause = engine::paused();
+
+    let displays: Vec<Value> = displays
+        .iter()
+        .map(|d| {
+            let profile = display::profile_path(&d.display);
+            let gamut = profile.as_ref().and_then(|p| icc::inspect(p).ok()).and_then(|i| i.gamut);
+            Value::object(vec![
+                ("index", Value::from(d.display.index + 1)),
+                ("device", Value::from(d.display.device.as_str())),
+                ("name", Value::from(d.display.name.as_str())),
+                ("id", Value::from(d.display.id.as_str())),
+                ("classification", Value::from(d.class.name())),
+                ("checked_at", Value::from(format_iso8601(d.checked))),
+                ("profile", profile.as_ref().map_or(Value::Null, |p| profile_value(p))),
+                ("calibration_file", Value::from(calibration::source_for(&d.display).map(|p| p.to_string_lossy().to_string()))),
+                ("ramp", d.ramp.as_ref().map_or(Value::Null, |r| ramp::analyze(r, gamut.as_ref()).to_json())),
+            ])
+        })
+        .collect();
+
+    let last_reload = last_reload.map_or(Value::Null, |r| {
+        Value::object(vec![
+            ("time", Value::from(format_iso8601(r.time))),
+            ("ok", Value::from(r.ok)),
+            ("detail", Value::from(r.detail)),
+        ])
+    });
+
+    let failures: Vec<Value> = failures
+        .into_iter()
+        .map(|f| Value::object(vec![("time", Value::from(format_iso8601(f.time))), ("message", Value::from(f.message))]))
+        .collect();
+
+    Value::object(vec![
+        ("running", Value::from(running)),
+        ("version", Value::from(env!("CARGO_PKG_VERSION"))),
+        ("mode", Value::from(if crate::headless::is_active() { "headless" } else { "tray" })),
+        (
+            "backend",
+            Value::object(vec![
+                ("name", Value::from("dispwin")),
+                ("path", Value::from(dispwin.as_ref().map(|p| p.to_string_lossy().to_string()))),
+                ("version", Value::from(dispwin.as_deref().and_then(dispwin_version))),
+            ]),
+        ),
+        (
+            "polling",
+            Value::object(vec![
+                ("enabled", Value::from(crate::is_polling_enabled())),
+                ("interval_ms", Value::from(crate::POLLING_INTERVAL_MS.load(Ordering::Relaxed))),
+                ("gave_up", Value::from(engine::has_given_up())),
+                // Timed pause; the seconds left are null when it lasts until restart
+                ("paused", Value::from(pause.is_some())),
+                ("pause_remaining_s", Value::from(pause.and_then(|p| p.remaining()).map(|d| d.as_secs()))),
+                ("fullscreen", Value::from(fullscreen::state().map(|s| s.name()))),
+                ("fullscreen_policy", Value::from(fullscreen::policy().name())),
+                // Set while application rules or a fullscreen program hold drift reloads back
+                ("held_by", Value::from(engine::held_by())),
+            ]),
+        ),
+        ("preset", Value::from(presets::active())),
+        ("displays", Value::Array(displays)),
+        ("last_reload", last_reload),
+        ("recent_failures", Value::Array(failures)),
+    ])
+}
+
+fn profile_value(path: &Path) -> Value {
+    let data = std::fs::read(path).ok();
+    // Header bytes 84..100 hold the profile ID; all zeros means "not set"
+    let profile_id = data
+        .as_ref()
+        .filter(|d| d.len() >= 128)
+        .map(|d| &d[84..100])
+        .filter(|id| id.iter().any(|&b| b != 0))
+        .map(md5::hex);
+
+    Value::object(vec![
+        ("path", Value::from(path.to_string_lossy().to_string())),
+        ("md5", Value::from(data.as_ref().map(|d| md5::hex(&md5::digest(d))))),
+        ("profile_id", Value::from(profile_id)),
+        // Calibrated through MHC2, which the gamma ramp reload cannot restore
+        ("relies_on_mhc2", Value::from(engine::warn_if_mhc2_only(path))),
+    ])
+}
+
+// dispwin prints its version in the usage text, e.g.
+// "Test display LUTs and install profile, Version 3.3.0"
+fn dispwin_version(path: &Path) -> Option<String> {
+    static VERSION: OnceLock<Option<String>> = OnceLock::new();
+    VERSION
+        .get_or_init(|| {
+            let output = Command::new(path).arg("-?").creation_flags(0x08000000).output().ok()?;
+            let text = format!("{}{}", String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr));
+            let rest = &text[text.find("Version ")? + "Version ".len()..];
+            rest.split_whitespace().next().map(|v| v.trim_end_matches(',').to_string())
+        })
+        .clone()
+}
+
+// Human-readable rendering of `report` for `status` without `--json`.
+pub fn format_text(report: &Value) -> String {
+    let mut out = String::new();
+    let s = |v: Option<&Value>| v.and_then(Value::as_str).unwrap_or("-").to_string();
+
+    let running = report.get("running").and_then(Value::as_bool).unwrap_or(false);
+    out.push_str(&format!("Running:     {}\n", if running { s(report.get("mode")) } else { "no".to_string() }));
+
+    let backend = report.get("backend");
+    out.push_str(&format!(
+        "Backend:     dispwin {} ({})\n",
+        s(backend.and_then(|b| b.get("version"))),
+        s(backend.and_then(|b| b.get("path")))
+    ));
+
+    let polling = report.get("polling");
+    let enabled = polling.and_then(|p| p.get("enabled")).and_then(Value::as_bool).unwrap_or(false);
+    let interval = polling.and_then(|p| p.get("interval_ms")).and_then(Value::as_f64).unwrap_or(0.0);
+    let mut polling_text = if enabled { format!("every {} ms", interval) } else { "off".to_string() };
+    if polling.and_then(|p| p.get("paused")).and_then(Value::as_bool).unwrap_or(false) {
+        match polling.and_then(|p| p.get("pause_remaining_s")).and_then(Value::as_f64) {
+            Some(seconds) => polling_text.push_str(&format!(", paused ({} min left)", (seconds / 60.0).ceil())),
+            None => polling_text.push_str(", paused until restart"),
+        }
+    }
+    if let Some(held) = polling.and_then(|p| p.get("held_by")).and_then(Value::as_str) {
+        polling_text.push_str(&format!(", reloads on hold ({})", held));
+    }
+    out.push_str(&format!("Polling:     {}\n", polling_text));
+    if let Some(preset) = report.get("preset").and_then(Value::as_str) {
+        out.push_str(&format!("Preset:      {}\n", preset));
+    }
+
+    for d in report.get("displays").and_then(Value::as_array).into_iter().flatten() {
+        let index = d.get("index").and_then(Value::as_f64).unwrap_or(0.0);
+        out.push_str(&format!("Display {}:   {} [{}]\n", index, s(d.get("name")), s(d.get("classification"))));
+        if let Some(p) = d.get("profile").filter(|p| !p.is_null()) {
+            out.push_str(&format!("  Profile:   {}\n  MD5:       {}\n", s(p.get("path")), s(p.get("md5"))));
+            if p.get("relies_on_mhc2").and_then(Value::as_bool).unwrap_or(false) {
+                let path = s(p.get("path"));
+                out.push_str(&format!("  Warning:   {}\n", engine::mhc2_warning(Path::new(&path))));
+            }
+        }
+        if let Some(r) = d.get("ramp").filter(|r| !r.is_null()) {
+            let channels = r.get("channels");
+            let gamma: Vec<String> = ["red", "green", "blue"]
+                .iter()
+                .map(|c| channels.and_then(|ch| ch.get(c)).and_then(|ch| ch.get("gamma")).and_then(Value::as_f64))
+                .map(|g| g.map_or("-".to_string(), |g| format!("{:.2}", g)))
+                .collect();
+            let flag = |key: &str| r.get(key).and_then(Value::as_bool).unwrap_or(false);
+            out.push_str(&format!(
+                "  Ramp:      gamma {}{}{}\n",
+                gamma.join("/"),
+                if flag("monotonic") { "" } else { ", not monotonic" },
+                if flag("clipped") { ", clipped" } else { "" }
+            ));
+        }
+    }
+
+    match report.get("last_reload").filter(|r| !r.is_null()) {
+        Some(r) => out.push_str(&format!("Last reload: {} {}\n", s(r.get("time")), s(r.get("detail")))),
+        None => out.push_str("Last reload: -\n"),
+    }
+    for f in report.get("recent_failures").and_then(Value::as_array).into_iter().flatten() {
+        out.push_str(&format!("Failure:     {} {}\n", s(f.get("time")), s(f.get("message"))));
+    }
+    out
+}